@@ -0,0 +1,270 @@
+use crate::page::{extract_links, Regions};
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use regex::Regex;
+use select::document::Document;
+use select::predicate::{Attr, Name};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::LazyLock;
+use url::Url;
+
+/// A `url(...)` in CSS, quoted or not.
+static CSS_URL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"url\(\s*["']?([^"')\s]+)["']?\s*\)"#).expect("the CSS url() pattern is valid")
+});
+
+/// Properties of structured data whose values are URLs a reader may follow.
+const JSON_LD_KEYS: &[&str] = &[
+    "url",
+    "image",
+    "logo",
+    "sameAs",
+    "contentUrl",
+    "thumbnailUrl",
+    "embedUrl",
+    "mainEntityOfPage",
+];
+
+/// A fetched page as extractors see it.
+pub struct Source<'a> {
+    /// What relative links on the page resolve against.
+    pub base_url: &'a Url,
+    pub html: &'a str,
+    /// `html` parsed.
+    pub document: &'a Document,
+    /// The parts of the page the run takes links from.
+    pub regions: &'a Regions,
+}
+
+/// A way of finding the URLs a page refers to. Besides the `<a>`, `<img>`
+/// and `<script>` targets the run's options ask for, the links of each
+/// extractor given to [`Checker::with_extractor`] are checked too.
+///
+/// [`Checker::with_extractor`]: crate::Checker::with_extractor
+pub trait Extractor: Send {
+    fn extract(&self, page: &Source) -> HashSet<Url>;
+}
+
+/// The URL in an attribute of each of some element, from the page's regions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attribute {
+    pub element: &'static str,
+    pub attr: &'static str,
+}
+
+impl Attribute {
+    /// The `href` of `<a>`.
+    pub const LINKS: Attribute = Attribute::new("a", "href");
+    /// The `src` of `<img>`.
+    pub const IMAGES: Attribute = Attribute::new("img", "src");
+    /// The `src` of `<script>`.
+    pub const SCRIPTS: Attribute = Attribute::new("script", "src");
+
+    pub const fn new(element: &'static str, attr: &'static str) -> Self {
+        Attribute { element, attr }
+    }
+}
+
+impl Extractor for Attribute {
+    fn extract(&self, page: &Source) -> HashSet<Url> {
+        if page.regions.is_everywhere() {
+            extract_links(page.base_url, page.document, self.element, self.attr)
+        } else {
+            (page.regions).extract_links(page.base_url, page.html, self.element, self.attr)
+        }
+    }
+}
+
+/// The `url(...)` targets of `<style>` elements and `style` attributes, such
+/// as background images and fonts, anywhere on the page. `data:` URLs are left out.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CssUrls;
+
+impl Extractor for CssUrls {
+    fn extract(&self, page: &Source) -> HashSet<Url> {
+        let base_parser = Url::options().base_url(Some(page.base_url));
+        let sheets = page.document.find(Name("style")).map(|n| n.text());
+        let inline = (page.document.find(Attr("style", ())))
+            .filter_map(|n| n.attr("style").map(str::to_string));
+        let css: Vec<String> = sheets.chain(inline).collect();
+        (css.iter())
+            .flat_map(|css| CSS_URL.captures_iter(css))
+            .filter_map(|found| base_parser.parse(&found[1]).ok())
+            .filter(|url| url.scheme() != "data")
+            .collect()
+    }
+}
+
+/// The links in the page's JSON-LD structured data, such as the `url`,
+/// `image` and `sameAs` of what it describes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonLd;
+
+impl JsonLd {
+    fn collect(base_url: &Url, value: &Value, links: &mut HashSet<Url>) {
+        match value {
+            Value::Object(object) => {
+                for (key, value) in object {
+                    if JSON_LD_KEYS.contains(&key.as_str()) {
+                        let urls = match value {
+                            Value::String(url) => vec![url.as_str()],
+                            Value::Array(values) => {
+                                values.iter().filter_map(Value::as_str).collect()
+                            }
+                            _ => vec![],
+                        };
+                        let urls = urls.into_iter().filter_map(|url| base_url.join(url).ok());
+                        links.extend(urls.filter(|url| matches!(url.scheme(), "http" | "https")));
+                    }
+                    JsonLd::collect(base_url, value, links);
+                }
+            }
+            Value::Array(values) => {
+                for value in values {
+                    JsonLd::collect(base_url, value, links);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Extractor for JsonLd {
+    fn extract(&self, page: &Source) -> HashSet<Url> {
+        let mut links = HashSet::new();
+        for script in page.document.find(Attr("type", "application/ld+json")) {
+            // Pages with broken structured data still have their other links checked.
+            if let Ok(data) = serde_json::from_str::<Value>(&script.text()) {
+                JsonLd::collect(page.base_url, &data, &mut links);
+            }
+        }
+        links
+    }
+}
+
+/// The link and image targets of a page written in CommonMark, including
+/// those in raw HTML.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Markdown;
+
+impl Extractor for Markdown {
+    fn extract(&self, page: &Source) -> HashSet<Url> {
+        (markdown_targets(page.html, page.base_url).into_iter())
+            .map(|target| target.url)
+            .collect()
+    }
+}
+
+/// Where a Markdown link or image points, and the line it is on.
+#[derive(Debug, Clone)]
+pub struct MarkdownTarget {
+    pub url: Url,
+    pub image: bool,
+    pub line: usize,
+}
+
+/// The link and image targets of CommonMark `text`, in the order they appear.
+pub fn markdown_targets(text: &str, base_url: &Url) -> Vec<MarkdownTarget> {
+    let newlines: Vec<usize> = text.match_indices('\n').map(|(at, _)| at).collect();
+    let options = Options::ENABLE_TABLES | Options::ENABLE_FOOTNOTES | Options::ENABLE_TASKLISTS;
+    let mut targets = vec![];
+    for (event, range) in Parser::new_ext(text, options).into_offset_iter() {
+        let line = newlines.partition_point(|&at| at < range.start) + 1;
+        let target = |url: Url, image| MarkdownTarget { url, image, line };
+        match event {
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                targets.extend(base_url.join(&dest_url).map(|url| target(url, false)));
+            }
+            Event::Start(Tag::Image { dest_url, .. }) => {
+                targets.extend(base_url.join(&dest_url).map(|url| target(url, true)));
+            }
+            Event::Html(html) | Event::InlineHtml(html) => {
+                let document = Document::from(html.as_ref());
+                let (links, images) = (Attribute::LINKS, Attribute::IMAGES);
+                let links = extract_links(base_url, &document, links.element, links.attr);
+                let images = extract_links(base_url, &document, images.element, images.attr);
+                targets.extend(links.into_iter().map(|url| target(url, false)));
+                targets.extend(images.into_iter().map(|url| target(url, true)));
+            }
+            _ => {}
+        }
+    }
+    targets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(path: &str) -> Url {
+        Url::parse("https://example.com/")
+            .unwrap()
+            .join(path)
+            .unwrap()
+    }
+
+    fn extract(extractor: &dyn Extractor, html: &str) -> HashSet<Url> {
+        let base_url = url("/blog/");
+        let document = Document::from(html);
+        extractor.extract(&Source {
+            base_url: &base_url,
+            html,
+            document: &document,
+            regions: &Regions::default(),
+        })
+    }
+
+    #[test]
+    fn attribute_reads_its_element() {
+        let html = r#"<a href="post">Post</a><img src="/cover.png">"#;
+        assert_eq!(
+            extract(&Attribute::LINKS, html),
+            [url("/blog/post")].into_iter().collect()
+        );
+        assert_eq!(
+            extract(&Attribute::IMAGES, html),
+            [url("/cover.png")].into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn css_urls_reads_sheets_and_style_attributes_but_not_data() {
+        let html = r#"<style>body { background: url( "/bg.png" ) }
+            @font-face { src: url(fonts/a.woff2) }</style>
+            <div style="background-image: url('hero.jpg')"></div>
+            <i style="background: url(data:image/png;base64,AAAA)"></i>"#;
+        let expected = [
+            url("/bg.png"),
+            url("/blog/fonts/a.woff2"),
+            url("/blog/hero.jpg"),
+        ];
+        assert_eq!(extract(&CssUrls, html), expected.into_iter().collect());
+    }
+
+    #[test]
+    fn json_ld_reads_nested_url_properties() {
+        let html = r#"<script type="application/ld+json">{
+            "@type": "Article",
+            "url": "post",
+            "headline": "https://example.com/not-a-link",
+            "author": {"sameAs": ["https://social.example/me", "mailto:me@example.com"]}
+        }</script>
+        <script type="application/ld+json">{ broken</script>"#;
+        let expected = [url("/blog/post"), url("https://social.example/me")];
+        assert_eq!(extract(&JsonLd, html), expected.into_iter().collect());
+    }
+
+    #[test]
+    fn markdown_targets_have_their_lines() {
+        let text = "# Title\n\nSee [the guide](guide.md).\n\n![Logo](/logo.png)\n\n<a href=\"raw.html\">raw</a>\n";
+        let targets: Vec<_> = (markdown_targets(text, &url("/docs/")).into_iter())
+            .map(|target| (target.url, target.image, target.line))
+            .collect();
+        let expected = vec![
+            (url("/docs/guide.md"), false, 3),
+            (url("/logo.png"), true, 5),
+            (url("/docs/raw.html"), false, 7),
+        ];
+        assert_eq!(targets, expected);
+    }
+}
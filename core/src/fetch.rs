@@ -0,0 +1,195 @@
+use crate::extract::{Extractor, Source};
+use crate::normalize::without_fragment;
+use crate::page::Regions;
+use futures::future::LocalBoxFuture;
+use futures::stream::{self, StreamExt};
+use select::document::Document;
+use select::predicate::Name;
+use std::collections::BTreeSet;
+use url::Url;
+
+/// What a request got back.
+#[derive(Debug, Clone)]
+pub struct Response {
+    /// Where the request ended up after redirects.
+    pub url: Url,
+    pub status: u16,
+    pub content_type: Option<String>,
+    /// Only read of the page being checked; links may leave it empty.
+    pub body: String,
+}
+
+/// A request that got no response.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("fetching {url} failed: {message}")]
+pub struct FetchError {
+    pub url: Url,
+    pub message: String,
+}
+
+/// Makes the requests of [`check_page`], e.g. with `fetch()` in a browser or
+/// a worker. The futures needn't be `Send`, as those of wasm32 aren't.
+pub trait Fetch {
+    fn fetch<'a>(&'a self, url: &'a Url) -> LocalBoxFuture<'a, Result<Response, FetchError>>;
+}
+
+/// What checking one link found.
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub url: Url,
+    pub status: Option<u16>,
+    /// Why there is no status.
+    pub error: Option<String>,
+}
+
+impl Outcome {
+    /// Answered with a status [`is_ok_status`] accepts.
+    pub fn is_ok(&self) -> bool {
+        self.status.is_some_and(is_ok_status)
+    }
+}
+
+/// Whether a link answering with `status` works: 200, 304 when an earlier
+/// answer was revalidated, or 206 when only its first bytes were asked for.
+/// A redirect is followed to where it leads, so isn't OK by itself.
+pub fn is_ok_status(status: u16) -> bool {
+    matches!(status, 200 | 206 | 304)
+}
+
+/// Fetches `page` and checks the `http` and `https` links each of
+/// `extractors` finds on it, `concurrency` at a time, in URL order.
+pub async fn check_page(
+    fetch: &impl Fetch,
+    page: &Url,
+    extractors: &[&dyn Extractor],
+    concurrency: usize,
+) -> Result<Vec<Outcome>, FetchError> {
+    let response = fetch.fetch(page).await?;
+    let links: BTreeSet<Url> = {
+        let document = Document::from(response.body.as_str());
+        let base = document.find(Name("base")).find_map(|n| n.attr("href"));
+        let base_url = (base.and_then(|href| response.url.join(href).ok()))
+            .unwrap_or_else(|| response.url.clone());
+        let source = Source {
+            base_url: &base_url,
+            html: &response.body,
+            document: &document,
+            regions: &Regions::default(),
+        };
+        (extractors.iter())
+            .flat_map(|extractor| extractor.extract(&source))
+            .filter(|url| matches!(url.scheme(), "http" | "https"))
+            .map(without_fragment)
+            .collect()
+    };
+    let outcomes = stream::iter(links)
+        .map(|url| async move {
+            match fetch.fetch(&url).await {
+                Ok(response) => Outcome {
+                    url,
+                    status: Some(response.status),
+                    error: None,
+                },
+                Err(err) => Outcome {
+                    url,
+                    status: None,
+                    error: Some(err.message),
+                },
+            }
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await;
+    Ok(outcomes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::Attribute;
+    use futures::executor::block_on;
+    use futures::FutureExt;
+    use std::cell::RefCell;
+
+    fn url(path: &str) -> Url {
+        Url::parse("https://example.com/")
+            .unwrap()
+            .join(path)
+            .unwrap()
+    }
+
+    /// Answers from a fixed list of pages and records what was asked for.
+    struct Pages {
+        pages: Vec<(Url, u16, &'static str)>,
+        fetched: RefCell<Vec<Url>>,
+    }
+
+    impl Fetch for Pages {
+        fn fetch<'a>(&'a self, url: &'a Url) -> LocalBoxFuture<'a, Result<Response, FetchError>> {
+            self.fetched.borrow_mut().push(url.clone());
+            let found = self.pages.iter().find(|(page, ..)| page == url);
+            let response = match found {
+                Some((page, status, body)) => Ok(Response {
+                    url: page.clone(),
+                    status: *status,
+                    content_type: Some("text/html".to_string()),
+                    body: body.to_string(),
+                }),
+                None => Err(FetchError {
+                    url: url.clone(),
+                    message: "connection refused".to_string(),
+                }),
+            };
+            async move { response }.boxed_local()
+        }
+    }
+
+    #[test]
+    fn ok_statuses() {
+        assert!([200, 206, 304].into_iter().all(is_ok_status));
+        assert!(![204, 301, 404, 500].into_iter().any(is_ok_status));
+        let outcome = |status| Outcome {
+            url: url("/"),
+            status,
+            error: None,
+        };
+        assert!(outcome(Some(304)).is_ok());
+        assert!(!outcome(None).is_ok());
+    }
+
+    #[test]
+    fn check_page_checks_each_http_link_once_in_url_order() {
+        let html = r#"<base href="/docs/">
+            <a href="b">B</a><a href="a#intro">A</a><a href="a">A again</a>
+            <a href="mailto:me@example.com">Mail</a><a href="/gone">Gone</a>"#;
+        let fetch = Pages {
+            pages: vec![
+                (url("/"), 200, html),
+                (url("/docs/a"), 200, ""),
+                (url("/docs/b"), 404, ""),
+            ],
+            fetched: RefCell::new(vec![]),
+        };
+        let outcomes = block_on(check_page(&fetch, &url("/"), &[&Attribute::LINKS], 2)).unwrap();
+        let found: Vec<_> = (outcomes.iter())
+            .map(|outcome| (outcome.url.path(), outcome.status, outcome.error.as_deref()))
+            .collect();
+        let expected = vec![
+            ("/docs/a", Some(200), None),
+            ("/docs/b", Some(404), None),
+            ("/gone", None, Some("connection refused")),
+        ];
+        assert_eq!(found, expected);
+        assert_eq!(fetch.fetched.borrow().len(), 4);
+    }
+
+    #[test]
+    fn check_page_fails_when_the_page_does() {
+        let fetch = Pages {
+            pages: vec![],
+            fetched: RefCell::new(vec![]),
+        };
+        let err = block_on(check_page(&fetch, &url("/"), &[&Attribute::LINKS], 2)).unwrap_err();
+        assert_eq!(err.url, url("/"));
+    }
+}
@@ -0,0 +1,6 @@
+pub mod extract;
+pub mod fetch;
+pub mod normalize;
+pub mod page;
+
+pub use fetch::{check_page, is_ok_status, Fetch, FetchError, Outcome, Response};
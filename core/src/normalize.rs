@@ -0,0 +1,61 @@
+use url::Url;
+
+/// `url` on the host `to` instead, which may carry a `:port` too. IPv6
+/// addresses are bracketed, as in `[::1]:8080`.
+pub fn rewrite_host(mut url: Url, to: &str) -> Url {
+    let (host, port) = match to.rsplit_once(':') {
+        // The last colon of an IPv6 address may only set the port after its `]`.
+        Some((host, port)) if !host.contains(':') || host.ends_with(']') => {
+            (host, port.parse().ok())
+        }
+        _ => (to, None),
+    };
+    if url.set_host(Some(host)).is_ok() && port.is_some() {
+        let _ = url.set_port(port);
+    }
+    url
+}
+
+/// `url` without its fragment, so links to parts of one page count as one.
+pub fn without_fragment(mut url: Url) -> Url {
+    url.set_fragment(None);
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(path: &str) -> Url {
+        Url::parse("https://example.com/")
+            .unwrap()
+            .join(path)
+            .unwrap()
+    }
+
+    #[test]
+    fn rewrite_host_keeps_the_path() {
+        let rewritten = rewrite_host(url("/docs/?q=1"), "staging.example.com");
+        assert_eq!(rewritten.as_str(), "https://staging.example.com/docs/?q=1");
+    }
+
+    #[test]
+    fn rewrite_host_takes_a_port() {
+        let rewritten = rewrite_host(url("/docs/"), "localhost:8080");
+        assert_eq!(rewritten.as_str(), "https://localhost:8080/docs/");
+    }
+
+    #[test]
+    fn rewrite_host_takes_bracketed_ipv6() {
+        let rewritten = rewrite_host(url("/docs/"), "[::1]:8080");
+        assert_eq!(rewritten.as_str(), "https://[::1]:8080/docs/");
+        let rewritten = rewrite_host(url("/docs/"), "[::1]");
+        assert_eq!(rewritten.as_str(), "https://[::1]/docs/");
+    }
+
+    #[test]
+    fn without_fragment_drops_only_the_fragment() {
+        let stripped = without_fragment(url("/docs/?q=1#install"));
+        assert_eq!(stripped, url("/docs/?q=1"));
+    }
+}
@@ -0,0 +1,197 @@
+use scraper::{ElementRef, Html, Selector};
+use select::document::Document;
+use select::predicate::Name;
+use std::collections::HashSet;
+use url::Url;
+
+/// A CSS selector of [`Regions`] that doesn't parse.
+#[derive(Debug, thiserror::Error)]
+#[error("invalid selector {selector:?}: {message}")]
+pub struct InvalidSelector {
+    pub selector: String,
+    pub message: String,
+}
+
+/// Collects the `attr` of each `element`, resolved against `base_url`. Those
+/// inside `<noscript>`, such as the fallback images of lazy loaders, are included.
+pub fn extract_links(
+    base_url: &Url,
+    document: &Document,
+    element: &str,
+    attr: &str,
+) -> HashSet<Url> {
+    let base_parser = Url::options().base_url(Some(base_url));
+
+    let mut links: HashSet<Url> = document
+        .find(Name(element))
+        .filter_map(|n| n.attr(attr))
+        .filter_map(|link| base_parser.parse(link).ok())
+        .collect();
+    // With scripting on, as browsers have it, `<noscript>` holds its markup as text.
+    for noscript in document.find(Name("noscript")) {
+        let fallback = Document::from(noscript.text().as_str());
+        links.extend(extract_links(base_url, &fallback, element, attr));
+    }
+    links
+}
+
+/// The images, scripts and stylesheets the page loads, wherever they are on it.
+pub fn resources(base_url: &Url, document: &Document) -> HashSet<Url> {
+    let base_parser = Url::options().base_url(Some(base_url));
+    let stylesheets = (document.find(Name("link")))
+        .filter(|n| {
+            n.attr("rel").is_some_and(|rel| {
+                rel.split_ascii_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case("stylesheet"))
+            })
+        })
+        .filter_map(|n| n.attr("href"));
+    let sources = (document
+        .find(Name("img"))
+        .chain(document.find(Name("script"))))
+    .filter_map(|n| n.attr("src"));
+    (stylesheets.chain(sources))
+        .filter_map(|link| base_parser.parse(link).ok())
+        .collect()
+}
+
+/// Parts of a page to take links from: inside `within` when set, and
+/// outside `exclude`, e.g. to skip navigation repeated on every page.
+#[derive(Debug, Clone, Default)]
+pub struct Regions {
+    pub within: Option<Selector>,
+    pub exclude: Option<Selector>,
+}
+
+impl Regions {
+    /// Parses the CSS selectors of the regions to keep and to skip.
+    pub fn new(within: Option<&str>, exclude: Option<&str>) -> Result<Regions, InvalidSelector> {
+        let parse = |selector: &str| {
+            Selector::parse(selector).map_err(|err| InvalidSelector {
+                selector: selector.to_string(),
+                message: err.to_string(),
+            })
+        };
+        Ok(Regions {
+            within: within.map(parse).transpose()?,
+            exclude: exclude.map(parse).transpose()?,
+        })
+    }
+
+    /// Whether links are taken from the whole page.
+    pub fn is_everywhere(&self) -> bool {
+        self.within.is_none() && self.exclude.is_none()
+    }
+
+    fn contains(&self, element: ElementRef) -> bool {
+        let scopes: Vec<ElementRef> = std::iter::once(element)
+            .chain(element.ancestors().filter_map(ElementRef::wrap))
+            .collect();
+        let inside = |selector: &Selector| scopes.iter().any(|scope| selector.matches(scope));
+        self.within.as_ref().is_none_or(inside) && !self.exclude.as_ref().is_some_and(inside)
+    }
+
+    /// Like [`extract_links`], keeping only links in the regions. A
+    /// `<noscript>` in the regions counts as a whole.
+    pub fn extract_links(
+        &self,
+        base_url: &Url,
+        html: &str,
+        element: &str,
+        attr: &str,
+    ) -> HashSet<Url> {
+        let base_parser = Url::options().base_url(Some(base_url));
+        let elements = Selector::parse(element).expect("element names are valid selectors");
+        let noscripts = Selector::parse("noscript").expect("noscript is a valid selector");
+
+        let document = Html::parse_document(html);
+        let mut links: HashSet<Url> = document
+            .select(&elements)
+            .filter(|&element| self.contains(element))
+            .filter_map(|element| element.value().attr(attr))
+            .filter_map(|link| base_parser.parse(link).ok())
+            .collect();
+        for noscript in document.select(&noscripts).filter(|&n| self.contains(n)) {
+            let fallback = Html::parse_fragment(&noscript.text().collect::<String>());
+            links.extend(
+                (fallback.select(&elements))
+                    .filter_map(|element| element.value().attr(attr))
+                    .filter_map(|link| base_parser.parse(link).ok()),
+            );
+        }
+        links
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(path: &str) -> Url {
+        Url::parse("https://example.com/")
+            .unwrap()
+            .join(path)
+            .unwrap()
+    }
+
+    const PAGE: &str = r#"<html><body>
+        <nav><a href="/home">Home</a></nav>
+        <main>
+            <a href="about">About</a>
+            <noscript><img src="/lazy.png"></noscript>
+            <footer class="share"><a href="https://social.example/">Share</a></footer>
+        </main>
+    </body></html>"#;
+
+    #[test]
+    fn extract_links_resolves_against_the_base_and_reads_noscript() {
+        let links = extract_links(&url("/docs/"), &Document::from(PAGE), "a", "href");
+        let expected = [
+            url("/home"),
+            url("/docs/about"),
+            url("https://social.example/"),
+        ];
+        assert_eq!(links, expected.into_iter().collect());
+        let images = extract_links(&url("/docs/"), &Document::from(PAGE), "img", "src");
+        assert_eq!(images, [url("/lazy.png")].into_iter().collect());
+    }
+
+    #[test]
+    fn resources_finds_stylesheets_images_and_scripts() {
+        let html = r#"<link rel="Alternate Stylesheet" href="/dark.css">
+            <link rel="icon" href="/favicon.ico">
+            <img src="/logo.png"><script src="/app.js"></script>"#;
+        let found = resources(&url("/"), &Document::from(html));
+        let expected = [url("/dark.css"), url("/logo.png"), url("/app.js")];
+        assert_eq!(found, expected.into_iter().collect());
+    }
+
+    #[test]
+    fn regions_keep_links_within_and_outside_exclude() {
+        let regions = Regions::new(Some("main"), Some(".share")).unwrap();
+        assert!(!regions.is_everywhere());
+        let links = regions.extract_links(&url("/docs/"), PAGE, "a", "href");
+        assert_eq!(links, [url("/docs/about")].into_iter().collect());
+        let images = regions.extract_links(&url("/docs/"), PAGE, "img", "src");
+        assert_eq!(images, [url("/lazy.png")].into_iter().collect());
+    }
+
+    #[test]
+    fn regions_exclude_alone_keeps_the_rest_of_the_page() {
+        let regions = Regions::new(None, Some("nav")).unwrap();
+        let links = regions.extract_links(&url("/"), PAGE, "a", "href");
+        assert_eq!(
+            links,
+            [url("/about"), url("https://social.example/")]
+                .into_iter()
+                .collect()
+        );
+    }
+
+    #[test]
+    fn regions_reject_invalid_selectors() {
+        let err = Regions::new(Some("main["), None).unwrap_err();
+        assert_eq!(err.selector, "main[");
+        assert!(Regions::default().is_everywhere());
+    }
+}
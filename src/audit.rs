@@ -0,0 +1,228 @@
+use crate::page::Fetched;
+use crate::sitemap::{Entry, Media};
+use chrono::{DateTime, NaiveDate, Utc};
+use reqwest::header::HeaderName;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+use url::Url;
+
+const CHANGEFREQS: [&str; 7] = [
+    "always", "hourly", "daily", "weekly", "monthly", "yearly", "never",
+];
+
+/// How far ahead a lastmod may be before it counts as in the future, for
+/// clocks and time zones that are a little off.
+const FUTURE_SLACK: chrono::TimeDelta = chrono::TimeDelta::days(1);
+
+/// Tells crawlers not to index a page, like `<meta name="robots">`.
+const X_ROBOTS_TAG: HeaderName = HeaderName::from_static("x-robots-tag");
+
+/// Why a URL a sitemap lists, or one of its images or videos, does not belong in it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "problem", rename_all = "snake_case")]
+pub enum Problem {
+    /// Answered something other than 200.
+    Status {
+        status: u16,
+    },
+    /// Redirected; the sitemap should list where it ended up.
+    Redirected {
+        to: Url,
+    },
+    /// The page names another URL as its canonical one.
+    NotCanonical {
+        canonical: Url,
+    },
+    /// The page asks not to be indexed, in a `<meta>` tag or `X-Robots-Tag`.
+    NoIndex,
+    MissingLastmod,
+    /// Not a W3C datetime such as `2024-05-01`.
+    InvalidLastmod {
+        lastmod: String,
+    },
+    FutureLastmod {
+        lastmod: String,
+    },
+    /// Older than the run's staleness threshold.
+    StaleLastmod {
+        lastmod: String,
+    },
+    /// Not one of `always`, `hourly`, ... `never`.
+    InvalidChangefreq {
+        changefreq: String,
+    },
+    /// Not a number from 0.0 to 1.0.
+    InvalidPriority {
+        priority: String,
+    },
+    /// An image or video of the entry is sent as something else.
+    WrongContentType {
+        expected: Media,
+        content_type: String,
+    },
+}
+
+/// A URL listed by a sitemap, and what is wrong with it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Issue {
+    pub url: Url,
+    #[serde(flatten)]
+    pub problem: Problem,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Status { status } => write!(f, "answers {}", status),
+            Problem::Redirected { to } => write!(f, "redirects to {}", to),
+            Problem::NotCanonical { canonical } => write!(f, "has canonical {}", canonical),
+            Problem::NoIndex => f.write_str("is noindex"),
+            Problem::MissingLastmod => f.write_str("has no lastmod"),
+            Problem::InvalidLastmod { lastmod } => write!(f, "has invalid lastmod {:?}", lastmod),
+            Problem::FutureLastmod { lastmod } => {
+                write!(f, "has lastmod {} in the future", lastmod)
+            }
+            Problem::StaleLastmod { lastmod } => write!(f, "has not changed since {}", lastmod),
+            Problem::InvalidChangefreq { changefreq } => {
+                write!(f, "has invalid changefreq {:?}", changefreq)
+            }
+            Problem::InvalidPriority { priority } => {
+                write!(f, "has priority {:?} outside 0.0 to 1.0", priority)
+            }
+            Problem::WrongContentType {
+                expected,
+                content_type,
+            } => write!(f, "is sent as {}, not {}", content_type, expected),
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.url, self.problem)
+    }
+}
+
+/// The problems of `loc`, listed by a sitemap and fetched as `page`. Pages
+/// that failed or redirected aren't looked into further.
+pub fn audit(loc: &Url, page: &Fetched) -> Vec<Problem> {
+    if page.url != *loc {
+        return vec![Problem::Redirected {
+            to: page.url.clone(),
+        }];
+    }
+    if page.status != reqwest::StatusCode::OK {
+        return vec![Problem::Status {
+            status: page.status.as_u16(),
+        }];
+    }
+    let document = Html::parse_document(&page.body);
+    let mut problems = Vec::new();
+    if let Some(canonical) = canonical(loc, &document).filter(|canonical| canonical != loc) {
+        problems.push(Problem::NotCanonical { canonical });
+    }
+    let header = (page.headers.get_all(X_ROBOTS_TAG).iter())
+        .filter_map(|value| value.to_str().ok())
+        .any(forbids_indexing);
+    if header || meta_noindex(&document) {
+        problems.push(Problem::NoIndex);
+    }
+    problems
+}
+
+/// The problems of what a sitemap says about `entry`, as of `now`: a missing,
+/// malformed or future `<lastmod>`, one older than `stale_after` when set, and
+/// `<changefreq>` or `<priority>` values the protocol doesn't allow.
+pub fn audit_entry(
+    entry: &Entry,
+    stale_after: Option<Duration>,
+    now: DateTime<Utc>,
+) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    match &entry.lastmod {
+        None => problems.push(Problem::MissingLastmod),
+        Some(lastmod) => match parse_lastmod(lastmod) {
+            None => problems.push(Problem::InvalidLastmod {
+                lastmod: lastmod.clone(),
+            }),
+            Some(date) if date > now + FUTURE_SLACK => problems.push(Problem::FutureLastmod {
+                lastmod: lastmod.clone(),
+            }),
+            Some(date) => {
+                let age = (now - date).to_std().unwrap_or_default();
+                if stale_after.is_some_and(|stale_after| age > stale_after) {
+                    problems.push(Problem::StaleLastmod {
+                        lastmod: lastmod.clone(),
+                    });
+                }
+            }
+        },
+    }
+    if let Some(changefreq) = &entry.changefreq {
+        if !CHANGEFREQS.contains(&changefreq.to_ascii_lowercase().as_str()) {
+            problems.push(Problem::InvalidChangefreq {
+                changefreq: changefreq.clone(),
+            });
+        }
+    }
+    if let Some(priority) = &entry.priority {
+        let valid = priority
+            .parse::<f64>()
+            .is_ok_and(|p| (0.0..=1.0).contains(&p));
+        if !valid {
+            problems.push(Problem::InvalidPriority {
+                priority: priority.clone(),
+            });
+        }
+    }
+    problems
+}
+
+/// Parses a W3C datetime, from a bare year to a full timestamp with a time
+/// zone; the parts left out are taken as their earliest value.
+fn parse_lastmod(lastmod: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(lastmod) {
+        return Some(date.to_utc());
+    }
+    // Seconds are optional in W3C datetimes but not in RFC 3339.
+    if let Ok(date) = DateTime::parse_from_str(lastmod, "%Y-%m-%dT%H:%M%#z") {
+        return Some(date.to_utc());
+    }
+    let date = match lastmod.len() {
+        4 => format!("{}-01-01", lastmod),
+        7 => format!("{}-01", lastmod),
+        _ => lastmod.to_string(),
+    };
+    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok()?;
+    Some(date.and_hms_opt(0, 0, 0)?.and_utc())
+}
+
+/// The `<link rel="canonical">` of the page at `url`, without its fragment.
+fn canonical(url: &Url, document: &Html) -> Option<Url> {
+    let selector = Selector::parse("link[rel~=canonical][href]").expect("selector is valid");
+    let href = document.select(&selector).next()?.value().attr("href")?;
+    let mut canonical = url.join(href.trim()).ok()?;
+    canonical.set_fragment(None);
+    Some(canonical)
+}
+
+/// Whether a `<meta name="robots">`, or one for Google's crawler, says `noindex`.
+fn meta_noindex(document: &Html) -> bool {
+    let selector = Selector::parse("meta[name][content]").expect("selector is valid");
+    document.select(&selector).any(|meta| {
+        let name = meta.value().attr("name").unwrap_or_default();
+        (name.eq_ignore_ascii_case("robots") || name.eq_ignore_ascii_case("googlebot"))
+            && forbids_indexing(meta.value().attr("content").unwrap_or_default())
+    })
+}
+
+/// Whether robots directives such as `"noindex, nofollow"` include `noindex` or `none`.
+fn forbids_indexing(directives: &str) -> bool {
+    directives.split(',').any(|directive| {
+        // `X-Robots-Tag` may name the crawler first, as in `googlebot: noindex`.
+        let directive = directive.rsplit(':').next().unwrap_or_default().trim();
+        directive.eq_ignore_ascii_case("noindex") || directive.eq_ignore_ascii_case("none")
+    })
+}
@@ -0,0 +1,70 @@
+use crate::error::{HtmlFindError, Result};
+use crate::report::Report;
+use std::path::Path;
+
+const LABEL: &str = "links";
+const GREEN: &str = "#4c1";
+const RED: &str = "#e05d44";
+/// Shown when the run stopped before checking everything.
+const GREY: &str = "#9f9f9f";
+
+/// Rough width of `text` in 11px Verdana, the font shields.io badges use.
+fn text_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c {
+            'i' | 'l' | 'j' | '.' | ',' | ':' | ' ' | '!' | '|' | '/' => 4,
+            'm' | 'w' | 'M' | 'W' => 10,
+            _ => 7,
+        })
+        .sum::<usize>()
+        + 10
+}
+
+/// A shields.io-style badge reading e.g. "links | 1432 ok / 3 broken",
+/// green when nothing is broken and red otherwise.
+pub fn render(report: &Report) -> String {
+    let broken = report.broken().count();
+    let ok = report.results.len() - broken;
+    let message = format!("{} ok / {} broken", ok, broken);
+    let color = if report.stopped.is_some() {
+        GREY
+    } else if broken == 0 {
+        GREEN
+    } else {
+        RED
+    };
+    let (label_width, message_width) = (text_width(LABEL), text_width(&message));
+    let width = label_width + message_width;
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{LABEL}: {message}">
+  <title>{LABEL}: {message}</title>
+  <linearGradient id="s" x2="0" y2="100%">
+    <stop offset="0" stop-color="#bbb" stop-opacity=".1"/>
+    <stop offset="1" stop-opacity=".1"/>
+  </linearGradient>
+  <clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+  <g clip-path="url(#r)">
+    <rect width="{label_width}" height="20" fill="#555"/>
+    <rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/>
+    <rect width="{width}" height="20" fill="url(#s)"/>
+  </g>
+  <g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+    <text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{LABEL}</text>
+    <text x="{label_x}" y="14">{LABEL}</text>
+    <text x="{message_x}" y="15" fill="#010101" fill-opacity=".3">{message}</text>
+    <text x="{message_x}" y="14">{message}</text>
+  </g>
+</svg>
+"##,
+        label_x = label_width / 2,
+        message_x = label_width + message_width / 2,
+    )
+}
+
+/// Writes the badge for `report` to `path`.
+pub fn write(path: &Path, report: &Report) -> Result<()> {
+    std::fs::write(path, render(report)).map_err(|source| HtmlFindError::File {
+        path: path.to_path_buf(),
+        source,
+    })
+}
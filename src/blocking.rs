@@ -0,0 +1,52 @@
+use crate::capture::Capture;
+use crate::checker::Options;
+use crate::error::Result;
+use crate::local::LocalSite;
+use crate::report::Report;
+use tokio::runtime::Runtime;
+use url::Url;
+
+/// A [`crate::Checker`] for code that isn't async: each run blocks until its
+/// report is ready, on a Tokio runtime the checker keeps to itself. Runs must
+/// not be started from within an async task, which they would stall.
+pub struct Checker {
+    inner: crate::Checker,
+    runtime: Runtime,
+}
+
+impl Checker {
+    pub fn new(options: Options) -> Result<Checker> {
+        Checker::from_async(crate::Checker::new(options))
+    }
+
+    /// Wraps `checker`, e.g. one given a client or hooks with its `with_` methods.
+    pub fn from_async(checker: crate::Checker) -> Result<Checker> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        Ok(Checker {
+            inner: checker,
+            runtime,
+        })
+    }
+
+    /// See [`crate::Checker::run`].
+    pub fn run(self, urls: &[Url]) -> Result<Report> {
+        self.runtime.block_on(self.inner.run(urls))
+    }
+
+    /// See [`crate::Checker::run_list`].
+    pub fn run_list(self, urls: &[Url]) -> Result<Report> {
+        self.runtime.block_on(self.inner.run_list(urls))
+    }
+
+    /// See [`crate::Checker::run_capture`].
+    pub fn run_capture(self, capture: &Capture) -> Result<Report> {
+        self.runtime.block_on(self.inner.run_capture(capture))
+    }
+
+    /// See [`crate::Checker::run_path`].
+    pub fn run_path(self, site: LocalSite) -> Result<Report> {
+        self.runtime.block_on(self.inner.run_path(site))
+    }
+}
@@ -0,0 +1,133 @@
+use reqwest::header::{HeaderMap, SERVER};
+use reqwest::StatusCode;
+
+/// How much of the body of a possible challenge page is searched for markers.
+pub(crate) const SNIFF_BYTES: usize = 16 * 1024;
+
+/// Text that challenge and block pages carry, with whose pages they are.
+const BODY_MARKERS: &[(&str, &str)] = &[
+    ("challenge-platform", "Cloudflare"),
+    ("cf-chl-", "Cloudflare"),
+    ("cf_chl_", "Cloudflare"),
+    ("Attention Required! | Cloudflare", "Cloudflare"),
+    ("Just a moment...", "Cloudflare"),
+    ("_Incapsula_Resource", "Imperva"),
+    ("captcha-delivery.com", "DataDome"),
+    ("errors.edgesuite.net", "Akamai"),
+    ("/_sec/cp_challenge/", "Akamai"),
+];
+
+/// Headers only bot protection services send, with whose they are.
+const HEADER_MARKERS: &[(&str, &str)] = &[
+    ("cf-mitigated", "Cloudflare"),
+    ("x-datadome", "DataDome"),
+    ("x-sucuri-id", "Sucuri"),
+    ("x-amzn-waf-action", "AWS WAF"),
+];
+
+/// Whether a response with `status` may be a challenge worth looking into.
+pub(crate) fn may_be_challenge(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+    )
+}
+
+/// The bot protection service that likely answered a request with `status`
+/// and `headers` instead of the site, judging by the headers and, when
+/// read, the start of the body: its challenge markers on a 403, or a
+/// Cloudflare `cf-ray` on a 503.
+pub fn protection(
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: Option<&str>,
+) -> Option<&'static str> {
+    if !may_be_challenge(status) {
+        return None;
+    }
+    if let Some((_, by)) = HEADER_MARKERS
+        .iter()
+        .find(|(name, _)| headers.contains_key(*name))
+    {
+        return Some(by);
+    }
+    let server = (headers.get(SERVER))
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if status == StatusCode::SERVICE_UNAVAILABLE && headers.contains_key("cf-ray") {
+        return Some("Cloudflare");
+    }
+    if status == StatusCode::FORBIDDEN && server.starts_with("akamaighost") {
+        return Some("Akamai");
+    }
+    let body = body?;
+    (BODY_MARKERS.iter())
+        .find(|(marker, _)| body.contains(marker))
+        .map(|(_, by)| *by)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        (pairs.iter())
+            .map(|(name, value)| (name.parse().unwrap(), HeaderValue::from_static(value)))
+            .collect()
+    }
+
+    #[test]
+    fn recognizes_challenge_headers() {
+        let cloudflare = headers(&[("cf-mitigated", "challenge")]);
+        assert_eq!(
+            protection(StatusCode::FORBIDDEN, &cloudflare, None),
+            Some("Cloudflare")
+        );
+        let datadome = headers(&[("x-datadome", "protected")]);
+        assert_eq!(
+            protection(StatusCode::TOO_MANY_REQUESTS, &datadome, None),
+            Some("DataDome")
+        );
+        let ray = headers(&[("cf-ray", "8a1b2c3d4e5f-AMS")]);
+        assert_eq!(
+            protection(StatusCode::SERVICE_UNAVAILABLE, &ray, None),
+            Some("Cloudflare")
+        );
+        // A plain 403 through Cloudflare is the site's own answer.
+        assert_eq!(protection(StatusCode::FORBIDDEN, &ray, None), None);
+        let akamai = headers(&[("server", "AkamaiGHost")]);
+        assert_eq!(
+            protection(StatusCode::FORBIDDEN, &akamai, None),
+            Some("Akamai")
+        );
+    }
+
+    #[test]
+    fn recognizes_challenge_pages() {
+        let none = HeaderMap::new();
+        let page = "<title>Just a moment...</title><script src=\"/cdn-cgi/challenge-platform/x\">";
+        assert_eq!(
+            protection(StatusCode::FORBIDDEN, &none, Some(page)),
+            Some("Cloudflare")
+        );
+        let imperva = "<iframe src=\"/_Incapsula_Resource?x=1\"></iframe>";
+        assert_eq!(
+            protection(StatusCode::FORBIDDEN, &none, Some(imperva)),
+            Some("Imperva")
+        );
+        assert_eq!(
+            protection(StatusCode::FORBIDDEN, &none, Some("<h1>Forbidden</h1>")),
+            None
+        );
+    }
+
+    #[test]
+    fn ignores_other_statuses() {
+        let cloudflare = headers(&[("cf-mitigated", "challenge")]);
+        let page = Some("Just a moment...");
+        assert_eq!(protection(StatusCode::OK, &cloudflare, page), None);
+        assert_eq!(protection(StatusCode::NOT_FOUND, &cloudflare, page), None);
+    }
+}
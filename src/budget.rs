@@ -0,0 +1,95 @@
+use crate::report::CheckResult;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use url::Url;
+
+/// Limits on what a single page may load.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Budget {
+    /// Combined size in bytes of the resources a page loads.
+    pub max_bytes: Option<u64>,
+    /// Number of resources a page loads.
+    pub max_requests: Option<usize>,
+}
+
+/// A page over its budget, with what it loads.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageWeight {
+    pub page: Url,
+    /// Combined size in bytes of the resources whose size is known.
+    pub bytes: u64,
+    /// Resources loaded, the images, scripts and stylesheets the page refers to.
+    pub requests: usize,
+    /// Resources whose size is unknown, as they failed or were sent without
+    /// a `Content-Length`, so `bytes` may fall short.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub unsized_resources: usize,
+}
+
+fn is_zero(count: &usize) -> bool {
+    *count == 0
+}
+
+impl Budget {
+    pub fn is_set(&self) -> bool {
+        self.max_bytes.is_some() || self.max_requests.is_some()
+    }
+
+    /// The pages of `resources`, each with the resources it loads, that go
+    /// over the budget, heaviest first, sized by the `results` of checking them.
+    pub fn over(
+        &self,
+        resources: &HashMap<Url, HashSet<Url>>,
+        results: &[CheckResult],
+    ) -> Vec<PageWeight> {
+        let sizes: HashMap<&Url, u64> = (results.iter())
+            .filter(|result| result.is_ok())
+            .filter_map(|result| Some((&result.url, result.size?)))
+            .collect();
+        let mut over: Vec<PageWeight> = (resources.iter())
+            .map(|(page, resources)| {
+                let known: Vec<u64> = (resources.iter())
+                    .filter_map(|resource| sizes.get(resource).copied())
+                    .collect();
+                PageWeight {
+                    page: page.clone(),
+                    bytes: known.iter().sum(),
+                    requests: resources.len(),
+                    unsized_resources: resources.len() - known.len(),
+                }
+            })
+            .filter(|weight| {
+                self.max_bytes.is_some_and(|max| weight.bytes > max)
+                    || self.max_requests.is_some_and(|max| weight.requests > max)
+            })
+            .collect();
+        over.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.page.cmp(&b.page)));
+        over
+    }
+}
+
+impl fmt::Display for PageWeight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} loads {} resources weighing {}",
+            self.page,
+            self.requests,
+            human_size(self.bytes)
+        )?;
+        if self.unsized_resources > 0 {
+            write!(f, " ({} of unknown size)", self.unsized_resources)?;
+        }
+        Ok(())
+    }
+}
+
+/// `bytes` in kilobytes or megabytes, as page weights are usually given.
+fn human_size(bytes: u64) -> String {
+    match bytes {
+        0..1_000 => format!("{} B", bytes),
+        1_000..1_000_000 => format!("{:.1} kB", bytes as f64 / 1e3),
+        _ => format!("{:.2} MB", bytes as f64 / 1e6),
+    }
+}
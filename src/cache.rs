@@ -0,0 +1,237 @@
+use crate::check::Validators;
+use crate::error::{HtmlFindError, Result};
+use crate::report::CheckResult;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use url::Url;
+
+pub const DEFAULT_CACHE_FILE: &str = ".html_find_cache.json";
+
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long entries carrying validators are kept past their TTL for revalidation.
+const VALIDATOR_RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub status: u16,
+    /// Seconds since the Unix epoch.
+    pub checked_at: u64,
+    #[serde(flatten)]
+    pub validators: Validators,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
+impl CacheEntry {
+    fn age(&self) -> u64 {
+        now().saturating_sub(self.checked_at)
+    }
+}
+
+/// Outcomes of earlier runs, keyed by URL. Only successful checks are kept,
+/// so broken URLs are always checked again. Expired entries with an ETag or
+/// Last-Modified are revalidated instead of being fetched from scratch.
+#[derive(Debug)]
+pub struct Cache {
+    path: PathBuf,
+    ttl: Duration,
+    entries: HashMap<String, CacheEntry>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+impl Cache {
+    /// Reads the cache at `path`. A missing or unreadable file yields an empty cache.
+    pub fn load(path: &Path, ttl: Duration) -> Cache {
+        let entries = std::fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Cache {
+            path: path.to_path_buf(),
+            ttl,
+            entries,
+        }
+    }
+
+    /// Returns the cached outcome for `url` if it was checked within the TTL.
+    pub fn get(&self, url: &Url) -> Option<CheckResult> {
+        let entry = self.entries.get(url.as_str())?;
+        if entry.age() >= self.ttl.as_secs() {
+            return None;
+        }
+        Some(CheckResult {
+            status: StatusCode::from_u16(entry.status).ok(),
+            cached: true,
+            validators: entry.validators.clone(),
+            content_type: entry.content_type.clone(),
+            ..CheckResult::blank(url.clone())
+        })
+    }
+
+    /// Returns the validators stored for `url`, to send along with a fresh check.
+    pub fn validators(&self, url: &Url) -> Option<Validators> {
+        self.entries
+            .get(url.as_str())
+            .map(|entry| entry.validators.clone())
+            .filter(|validators| !validators.is_empty())
+    }
+
+    pub fn insert(&mut self, result: &CheckResult) {
+        if result.cached || !result.is_ok() {
+            return;
+        }
+        let Some(status) = result.status else {
+            return;
+        };
+        let key = result.url.as_str();
+        // A 304 need not repeat the validators it was matched against, nor
+        // the content type.
+        let previous = self.entries.get(key);
+        let validators = match previous {
+            Some(previous) if result.validators.is_empty() => previous.validators.clone(),
+            _ => result.validators.clone(),
+        };
+        let content_type = (result.content_type.clone())
+            .or_else(|| previous.and_then(|previous| previous.content_type.clone()));
+        self.entries.insert(
+            key.to_string(),
+            CacheEntry {
+                status: status.as_u16(),
+                checked_at: now(),
+                validators,
+                content_type,
+            },
+        );
+    }
+
+    /// Writes the cache back, dropping entries that can no longer be used.
+    pub fn save(&mut self) -> Result<()> {
+        let ttl = self.ttl.as_secs();
+        let retention = ttl.max(VALIDATOR_RETENTION.as_secs());
+        self.entries.retain(|_, entry| {
+            let keep_for = if entry.validators.is_empty() {
+                ttl
+            } else {
+                retention
+            };
+            entry.age() <= keep_for
+        });
+        let bytes = serde_json::to_vec(&self.entries).expect("cache entries serialize");
+        std::fs::write(&self.path, bytes).map_err(|source| HtmlFindError::File {
+            path: self.path.clone(),
+            source,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(age: Duration, validators: Validators) -> CacheEntry {
+        CacheEntry {
+            status: 200,
+            checked_at: now() - age.as_secs(),
+            validators,
+            content_type: Some("text/html".to_string()),
+        }
+    }
+
+    fn cache(path: PathBuf, entries: &[(&str, CacheEntry)]) -> Cache {
+        Cache {
+            path,
+            ttl: Duration::from_secs(60 * 60),
+            entries: (entries.iter())
+                .map(|(url, entry)| (url.to_string(), entry.clone()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn entries_expire_after_the_ttl() {
+        let url = Url::parse("https://example.com/").unwrap();
+        let fresh = cache(
+            PathBuf::new(),
+            &[(
+                url.as_str(),
+                entry(Duration::from_secs(60), Validators::default()),
+            )],
+        );
+        let result = fresh.get(&url).unwrap();
+        assert!(result.cached && result.is_ok());
+        assert_eq!(result.content_type.as_deref(), Some("text/html"));
+
+        let stale = cache(
+            PathBuf::new(),
+            &[(
+                url.as_str(),
+                entry(Duration::from_secs(2 * 60 * 60), Validators::default()),
+            )],
+        );
+        assert!(stale.get(&url).is_none());
+        assert!(fresh
+            .get(&Url::parse("https://example.com/other").unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn saving_keeps_expired_entries_with_validators() {
+        let path =
+            std::env::temp_dir().join(format!("html_find_cache_test_{}.json", std::process::id()));
+        let validators = Validators {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+        };
+        let day = Duration::from_secs(24 * 60 * 60);
+        let mut cache = cache(
+            path.clone(),
+            &[
+                (
+                    "https://example.com/fresh",
+                    entry(Duration::ZERO, Validators::default()),
+                ),
+                (
+                    "https://example.com/stale",
+                    entry(day, Validators::default()),
+                ),
+                ("https://example.com/etag", entry(day, validators.clone())),
+                (
+                    "https://example.com/old",
+                    entry(day * 60, validators.clone()),
+                ),
+            ],
+        );
+        cache.save().unwrap();
+        let loaded = Cache::load(&path, Duration::from_secs(60 * 60));
+        std::fs::remove_file(&path).unwrap();
+        let mut kept: Vec<&str> = loaded.entries.keys().map(String::as_str).collect();
+        kept.sort();
+        assert_eq!(
+            kept,
+            ["https://example.com/etag", "https://example.com/fresh"]
+        );
+        let etag = Url::parse("https://example.com/etag").unwrap();
+        assert!(loaded.get(&etag).is_none());
+        assert_eq!(loaded.validators(&etag), Some(validators));
+    }
+
+    #[test]
+    fn only_fresh_successes_are_stored() {
+        let mut cache = cache(PathBuf::new(), &[]);
+        let ok = Url::parse("https://example.com/ok").unwrap();
+        let broken = Url::parse("https://example.com/broken").unwrap();
+        cache.insert(&CheckResult::new(ok.clone(), StatusCode::OK));
+        cache.insert(&CheckResult::new(broken.clone(), StatusCode::NOT_FOUND));
+        assert!(cache.get(&ok).is_some());
+        assert!(cache.get(&broken).is_none());
+    }
+}
@@ -0,0 +1,155 @@
+use crate::page::is_html;
+use crate::report::CheckResult;
+use reqwest::header::CACHE_CONTROL;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use url::Url;
+
+/// Longest a page may be cached for before its updates take too long to show.
+const MAX_PAGE_AGE: u64 = 24 * 60 * 60;
+
+/// Extensions of files served as they are, for those sent without a content type.
+const ASSET_EXTENSIONS: &[&str] = &[
+    "js", "mjs", "css", "png", "jpg", "jpeg", "gif", "webp", "avif", "svg", "ico", "woff", "woff2",
+    "ttf", "otf", "eot", "mp4", "webm", "mp3", "ogg", "wasm", "pdf",
+];
+
+/// Why a URL is cached wrongly for what it is.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "problem", rename_all = "snake_case")]
+pub enum CachingProblem {
+    /// A static asset sent without `Cache-Control`.
+    MissingCacheControl,
+    /// A static asset whose `Cache-Control` keeps it from being reused.
+    NotCached { cache_control: String },
+    /// An asset with a content hash in its name, which never changes, not marked `immutable`.
+    NotImmutable { cache_control: String },
+    /// A page cached for so long that visitors would miss its updates.
+    LongCachedPage { cache_control: String },
+}
+
+/// A checked URL, and what is wrong with how it may be cached.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachingIssue {
+    pub url: Url,
+    #[serde(flatten)]
+    pub problem: CachingProblem,
+}
+
+impl fmt::Display for CachingProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CachingProblem::MissingCacheControl => f.write_str("is an asset without Cache-Control"),
+            CachingProblem::NotCached { cache_control } => {
+                write!(f, "is an asset sent with Cache-Control {:?}", cache_control)
+            }
+            CachingProblem::NotImmutable { cache_control } => write!(
+                f,
+                "has a hashed name but Cache-Control {:?} lacks immutable",
+                cache_control
+            ),
+            CachingProblem::LongCachedPage { cache_control } => {
+                write!(
+                    f,
+                    "is a page cached for long, with Cache-Control {:?}",
+                    cache_control
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for CachingIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.url, self.problem)
+    }
+}
+
+/// What is wrong with how `result`, which must have kept its `Cache-Control`
+/// header, may be cached: static assets should be cached, and for good when
+/// their names are hashed, while pages should not be cached for more than a day.
+pub fn audit_caching(result: &CheckResult) -> Option<CachingProblem> {
+    if !result.is_ok() || result.cached || result.mode.is_some() {
+        return None;
+    }
+    let cache_control = result.headers.get(CACHE_CONTROL.as_str());
+    let directives: Vec<String> = (cache_control.iter())
+        .flat_map(|value| value.split(','))
+        .map(|directive| directive.trim().to_ascii_lowercase())
+        .collect();
+    let has = |name: &str| directives.iter().any(|directive| directive == name);
+    let max_age = (directives.iter())
+        .filter_map(|directive| {
+            let (name, value) = directive.split_once('=')?;
+            if !matches!(name.trim(), "max-age" | "s-maxage") {
+                return None;
+            }
+            value.trim().parse::<u64>().ok()
+        })
+        .max();
+
+    let page = result.content_type.is_some() && is_html(result.content_type.as_deref());
+    if page {
+        let long = has("immutable") || max_age.is_some_and(|age| age > MAX_PAGE_AGE);
+        return long.then(|| CachingProblem::LongCachedPage {
+            cache_control: cache_control.cloned().unwrap_or_default(),
+        });
+    }
+    if !is_asset(result) {
+        return None;
+    }
+    let Some(cache_control) = cache_control.cloned() else {
+        return Some(CachingProblem::MissingCacheControl);
+    };
+    if has("no-store") || has("no-cache") || max_age == Some(0) {
+        return Some(CachingProblem::NotCached { cache_control });
+    }
+    (is_hashed(&result.url) && !has("immutable"))
+        .then_some(CachingProblem::NotImmutable { cache_control })
+}
+
+/// Whether `result` is a script, stylesheet, font or media file, by its
+/// content type or else its extension.
+fn is_asset(result: &CheckResult) -> bool {
+    if let Some(content_type) = &result.content_type {
+        let essence = content_type.split(';').next().unwrap_or_default().trim();
+        let essence = essence.to_ascii_lowercase();
+        return ["image/", "font/", "video/", "audio/"]
+            .iter()
+            .any(|prefix| essence.starts_with(prefix))
+            || matches!(
+                essence.as_str(),
+                "text/css"
+                    | "text/javascript"
+                    | "application/javascript"
+                    | "application/wasm"
+                    | "application/pdf"
+                    | "application/font-woff"
+            );
+    }
+    extension(&result.url).is_some_and(|ext| ASSET_EXTENSIONS.contains(&ext.as_str()))
+}
+
+fn extension(url: &Url) -> Option<String> {
+    let name = url.path_segments()?.next_back()?;
+    let (_, ext) = name.rsplit_once('.')?;
+    Some(ext.to_ascii_lowercase())
+}
+
+/// Whether the file name of `url` carries a content hash, as bundlers add:
+/// a part of at least 8 letters and digits, some of them digits, after a
+/// dot, dash or underscore, as in `app.3f2a9c1b.js`.
+fn is_hashed(url: &Url) -> bool {
+    let Some(name) = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+    else {
+        return false;
+    };
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    stem.split(['.', '-', '_']).skip(1).any(|part| {
+        part.len() >= 8
+            && part.bytes().all(|b| b.is_ascii_alphanumeric())
+            && part.bytes().filter(u8::is_ascii_digit).count() >= 2
+    })
+}
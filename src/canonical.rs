@@ -0,0 +1,148 @@
+use crate::report::CheckResult;
+use select::document::Document;
+use select::predicate::Name;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use url::Url;
+
+/// How a page's canonical URL disagrees with the rest of the site.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "problem", rename_all = "snake_case")]
+pub enum CanonicalProblem {
+    /// The page links to `link`, which names another URL as its canonical one.
+    LinksToVariant { link: Url, canonical: Url },
+    /// The page's canonical URL redirects.
+    CanonicalRedirects { canonical: Url, to: Url },
+    /// The page's canonical URL fails.
+    CanonicalFails { canonical: Url, status: Option<u16> },
+    /// The sitemaps list the page, which names another URL as its canonical one.
+    ListedVariant { canonical: Url },
+    /// The sitemaps leave out the page's canonical URL.
+    CanonicalUnlisted { canonical: Url },
+}
+
+/// A page scanned, and what is inconsistent about its canonical URL.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanonicalIssue {
+    pub page: Url,
+    #[serde(flatten)]
+    pub problem: CanonicalProblem,
+}
+
+impl fmt::Display for CanonicalProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CanonicalProblem::LinksToVariant { link, canonical } => {
+                write!(f, "links to {}, whose canonical is {}", link, canonical)
+            }
+            CanonicalProblem::CanonicalRedirects { canonical, to } => {
+                write!(f, "has canonical {}, which redirects to {}", canonical, to)
+            }
+            CanonicalProblem::CanonicalFails { canonical, status } => match status {
+                Some(status) => {
+                    write!(f, "has canonical {}, which is {}", canonical, status)
+                }
+                None => write!(f, "has canonical {}, which can't be fetched", canonical),
+            },
+            CanonicalProblem::ListedVariant { canonical } => {
+                write!(f, "is in the sitemaps but has canonical {}", canonical)
+            }
+            CanonicalProblem::CanonicalUnlisted { canonical } => {
+                write!(
+                    f,
+                    "has canonical {}, which the sitemaps leave out",
+                    canonical
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for CanonicalIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.page, self.problem)
+    }
+}
+
+/// The `<link rel="canonical">` of a page, resolved against `base_url` and
+/// without its fragment.
+pub fn canonical(base_url: &Url, document: &Document) -> Option<Url> {
+    let href = (document.find(Name("link")))
+        .filter(|n| {
+            n.attr("rel").is_some_and(|rel| {
+                rel.split_ascii_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case("canonical"))
+            })
+        })
+        .find_map(|n| n.attr("href"))?;
+    let mut canonical = base_url.join(href.trim()).ok()?;
+    canonical.set_fragment(None);
+    Some(canonical)
+}
+
+/// Cross-checks the `canonicals` of the pages scanned with the links between
+/// them, `referrers` giving the pages linking to each URL, with how their
+/// canonical URLs fared among `results`, and with the pages the sitemaps
+/// list, when `listed` has any.
+pub fn check_canonicals(
+    canonicals: &BTreeMap<Url, Url>,
+    referrers: &HashMap<Url, HashSet<Url>>,
+    results: &[CheckResult],
+    listed: &HashSet<Url>,
+) -> Vec<CanonicalIssue> {
+    let results: HashMap<&Url, &CheckResult> =
+        results.iter().map(|result| (&result.url, result)).collect();
+    let mut issues = Vec::new();
+    for (page, canonical) in canonicals {
+        let mut issue = |problem| {
+            issues.push(CanonicalIssue {
+                page: page.clone(),
+                problem,
+            })
+        };
+        if let Some(result) = results.get(canonical) {
+            if let Some(to) = &result.redirected_to {
+                issue(CanonicalProblem::CanonicalRedirects {
+                    canonical: canonical.clone(),
+                    to: to.clone(),
+                });
+            } else if !result.is_ok() {
+                issue(CanonicalProblem::CanonicalFails {
+                    canonical: canonical.clone(),
+                    status: result.status.map(|status| status.as_u16()),
+                });
+            }
+        }
+        if !listed.is_empty() {
+            if canonical != page && listed.contains(page) {
+                issue(CanonicalProblem::ListedVariant {
+                    canonical: canonical.clone(),
+                });
+            } else if !listed.contains(canonical) {
+                issue(CanonicalProblem::CanonicalUnlisted {
+                    canonical: canonical.clone(),
+                });
+            }
+        }
+    }
+    for (link, canonical) in canonicals
+        .iter()
+        .filter(|(page, canonical)| page != canonical)
+    {
+        let mut linking: Vec<&Url> = (referrers.get(link).into_iter().flatten())
+            .filter(|page| *page != link)
+            .collect();
+        linking.sort();
+        for page in linking {
+            issues.push(CanonicalIssue {
+                page: page.clone(),
+                problem: CanonicalProblem::LinksToVariant {
+                    link: link.clone(),
+                    canonical: canonical.clone(),
+                },
+            });
+        }
+    }
+    issues
+}
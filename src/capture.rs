@@ -0,0 +1,208 @@
+use crate::error::{HtmlFindError, Result};
+use base64::Engine;
+use flate2::read::{GzDecoder, MultiGzDecoder};
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// URLs and HTML pages recorded in a WARC or HAR file.
+#[derive(Debug)]
+pub struct Capture {
+    /// `file://` URL of the capture, standing in for a start URL.
+    pub source: Url,
+    /// Every URL the capture holds a response for.
+    pub urls: Vec<Url>,
+    pub pages: Vec<CapturedPage>,
+}
+
+#[derive(Debug)]
+pub struct CapturedPage {
+    pub url: Url,
+    pub html: String,
+}
+
+fn invalid(path: &Path, message: impl Into<String>) -> HtmlFindError {
+    HtmlFindError::InvalidCapture {
+        path: path.to_path_buf(),
+        message: message.into(),
+    }
+}
+
+fn is_html(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or("").trim();
+    mime.eq_ignore_ascii_case("text/html") || mime.eq_ignore_ascii_case("application/xhtml+xml")
+}
+
+impl Capture {
+    /// Reads a `.har`, `.warc` or `.warc.gz` file.
+    pub fn load(path: &Path) -> Result<Capture> {
+        let bytes = std::fs::read(path).map_err(|source| HtmlFindError::File {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let absolute = std::path::absolute(path).unwrap_or_else(|_| PathBuf::from(path));
+        let source = Url::from_file_path(&absolute)
+            .map_err(|_| invalid(path, "path cannot be expressed as a file:// URL"))?;
+        let mut capture = Capture {
+            source,
+            urls: vec![],
+            pages: vec![],
+        };
+
+        let name = path.to_string_lossy().to_ascii_lowercase();
+        if name.ends_with(".har") {
+            capture.read_har(path, &bytes)?;
+        } else if name.ends_with(".gz") {
+            let mut warc = vec![];
+            MultiGzDecoder::new(bytes.as_slice())
+                .read_to_end(&mut warc)
+                .map_err(|err| invalid(path, err.to_string()))?;
+            capture.read_warc(path, &warc)?;
+        } else {
+            capture.read_warc(path, &bytes)?;
+        }
+
+        let mut seen = HashSet::new();
+        capture.urls.retain(|url| seen.insert(url.clone()));
+        Ok(capture)
+    }
+
+    fn read_har(&mut self, path: &Path, bytes: &[u8]) -> Result<()> {
+        let har: serde_json::Value =
+            serde_json::from_slice(bytes).map_err(|source| HtmlFindError::Json {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        let entries = har["log"]["entries"]
+            .as_array()
+            .ok_or_else(|| invalid(path, "missing log.entries"))?;
+
+        for entry in entries {
+            let Some(url) = entry["request"]["url"]
+                .as_str()
+                .and_then(|url| Url::parse(url).ok())
+            else {
+                continue;
+            };
+            let content = &entry["response"]["content"];
+            if let (Some(mime), Some(text)) =
+                (content["mimeType"].as_str(), content["text"].as_str())
+            {
+                if is_html(mime) {
+                    let html = if content["encoding"].as_str() == Some("base64") {
+                        base64::engine::general_purpose::STANDARD
+                            .decode(text)
+                            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                            .map_err(|err| invalid(path, format!("{}: {}", url, err)))?
+                    } else {
+                        text.to_string()
+                    };
+                    self.pages.push(CapturedPage {
+                        url: url.clone(),
+                        html,
+                    });
+                }
+            }
+            self.urls.push(url);
+        }
+        Ok(())
+    }
+
+    fn read_warc(&mut self, path: &Path, mut bytes: &[u8]) -> Result<()> {
+        while !bytes.is_empty() {
+            let header_end = find(bytes, b"\r\n\r\n")
+                .ok_or_else(|| invalid(path, "truncated WARC record header"))?;
+            let header = String::from_utf8_lossy(&bytes[..header_end]);
+            if !header.starts_with("WARC/") {
+                return Err(invalid(path, "expected a WARC record"));
+            }
+            let field = |name: &str| {
+                header.lines().skip(1).find_map(|line| {
+                    let (key, value) = line.split_once(':')?;
+                    key.trim()
+                        .eq_ignore_ascii_case(name)
+                        .then(|| value.trim().to_string())
+                })
+            };
+            let length: usize = field("Content-Length")
+                .and_then(|len| len.parse().ok())
+                .ok_or_else(|| invalid(path, "WARC record without Content-Length"))?;
+            let block_start = header_end + 4;
+            let block = bytes
+                .get(block_start..block_start + length)
+                .ok_or_else(|| invalid(path, "truncated WARC record"))?;
+
+            let is_response = field("WARC-Type").as_deref() == Some("response");
+            let target = field("WARC-Target-URI")
+                .map(|uri| uri.trim_matches(|c| c == '<' || c == '>').to_string())
+                .and_then(|uri| Url::parse(&uri).ok());
+            if let (true, Some(url)) = (is_response, target) {
+                if let Some(html) = http_html(block) {
+                    self.pages.push(CapturedPage {
+                        url: url.clone(),
+                        html,
+                    });
+                }
+                self.urls.push(url);
+            }
+
+            bytes = &bytes[block_start + length..];
+            while let Some(rest) = bytes.strip_prefix(b"\r\n") {
+                bytes = rest;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Decodes the body of an archived HTTP response if it is HTML.
+fn http_html(block: &[u8]) -> Option<String> {
+    let header_end = find(block, b"\r\n\r\n")?;
+    let header = String::from_utf8_lossy(&block[..header_end]);
+    let field = |name: &str| {
+        header.lines().skip(1).find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim()
+                .eq_ignore_ascii_case(name)
+                .then(|| value.trim().to_ascii_lowercase())
+        })
+    };
+    if !is_html(&field("content-type")?) {
+        return None;
+    }
+
+    let mut body = block[header_end + 4..].to_vec();
+    if field("transfer-encoding").is_some_and(|te| te.contains("chunked")) {
+        body = dechunk(&body)?;
+    }
+    if field("content-encoding").is_some_and(|ce| ce.contains("gzip")) {
+        let mut decoded = vec![];
+        GzDecoder::new(body.as_slice())
+            .read_to_end(&mut decoded)
+            .ok()?;
+        body = decoded;
+    }
+    Some(String::from_utf8_lossy(&body).into_owned())
+}
+
+fn dechunk(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut out = vec![];
+    loop {
+        let line_end = find(body, b"\r\n")?;
+        let size = String::from_utf8_lossy(&body[..line_end]);
+        let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+        if size == 0 {
+            return Some(out);
+        }
+        let chunk = body.get(line_end + 2..line_end + 2 + size)?;
+        out.extend_from_slice(chunk);
+        body = body.get(line_end + 2 + size + 2..)?;
+    }
+}
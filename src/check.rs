@@ -0,0 +1,560 @@
+use crate::bot;
+use crate::error::{HtmlFindError, Phase};
+use crate::http::Http;
+use crate::report::CheckResult;
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, ACCEPT, ACCEPT_LANGUAGE, CONTENT_LENGTH, CONTENT_RANGE,
+    CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RANGE, RETRY_AFTER,
+    USER_AGENT,
+};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// How long resolving a host or connecting to it may take in the DNS and TCP modes.
+const HOST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Statuses retried unless told otherwise: rate limiting and server errors
+/// that tend to pass.
+pub const DEFAULT_RETRY_ON: [StatusCode; 5] = [
+    StatusCode::TOO_MANY_REQUESTS,
+    StatusCode::INTERNAL_SERVER_ERROR,
+    StatusCode::BAD_GATEWAY,
+    StatusCode::SERVICE_UNAVAILABLE,
+    StatusCode::GATEWAY_TIMEOUT,
+];
+
+/// Sent, as a desktop browser would, when a server turns away the run's own requests.
+const BROWSER_HEADERS: [(HeaderName, &str); 3] = [
+    (
+        USER_AGENT,
+        "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+    ),
+    (
+        ACCEPT,
+        "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,*/*;q=0.8",
+    ),
+    (ACCEPT_LANGUAGE, "en-US,en;q=0.9"),
+];
+
+/// Wait before the first retry, doubled for each one after it.
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Longest a `Retry-After` header is waited for.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// When a checked link is requested again.
+#[derive(Debug, Clone)]
+pub struct Retry {
+    /// Requests made at most after the first.
+    pub retries: u32,
+    /// Statuses worth another request. Requests that get no response at all are always retried.
+    pub on: Arc<[StatusCode]>,
+    /// Whether a link answering 403 Forbidden or 406 Not Acceptable is
+    /// requested once more with the headers a browser sends, as some servers
+    /// turn away anything else.
+    pub as_browser: bool,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Retry {
+            retries: 0,
+            on: DEFAULT_RETRY_ON.into(),
+            as_browser: true,
+        }
+    }
+}
+
+impl Retry {
+    fn wants(&self, sent: &reqwest::Result<Response>) -> bool {
+        match sent {
+            Ok(res) => self.on.contains(&res.status()),
+            Err(_) => true,
+        }
+    }
+
+    /// How long to wait before retry number `retry`, counting from 1: what
+    /// the response's `Retry-After` asks, in seconds, or else the backoff.
+    fn delay(retry: u32, sent: &reqwest::Result<Response>) -> Duration {
+        let retry_after = (sent.as_ref().ok())
+            .and_then(|res| {
+                res.headers()
+                    .get(RETRY_AFTER)?
+                    .to_str()
+                    .ok()?
+                    .trim()
+                    .parse()
+                    .ok()
+            })
+            .map(Duration::from_secs);
+        retry_after.map_or(RETRY_BACKOFF * 2u32.saturating_pow(retry - 1), |wait| {
+            wait.min(MAX_RETRY_AFTER)
+        })
+    }
+}
+
+/// How far checks go.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckMode {
+    /// Only resolve each host linked to.
+    Dns,
+    /// Resolve each host and open a connection to it.
+    Tcp,
+    /// Request each link.
+    #[default]
+    Http,
+}
+
+/// Cache validators a server sent for a resource.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Validators {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        Validators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+/// How much of the body of a checked link to read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Body {
+    /// None of it, unless it is being archived.
+    #[default]
+    Unread,
+    /// Its first bytes only, asked for with a range request, so a large file
+    /// is confirmed to be served without downloading all of it.
+    Sample(u64),
+    /// All of it, for its SHA-256 digest.
+    Digest,
+    /// None of it, not even sent: the link is checked with a HEAD request.
+    Head,
+}
+
+/// The values of the `names` headers sent, keyed by lowercase name. Repeated
+/// headers are joined with commas.
+pub(crate) fn capture_headers(
+    headers: &HeaderMap,
+    names: &[HeaderName],
+) -> BTreeMap<String, String> {
+    (names.iter())
+        .filter_map(|name| {
+            let values: Vec<&str> = (headers.get_all(name).iter())
+                .filter_map(|value| value.to_str().ok())
+                .collect();
+            (!values.is_empty()).then(|| (name.to_string(), values.join(", ")))
+        })
+        .collect()
+}
+
+/// Requests `url`, revalidating with `validators` from an earlier run when given
+/// so an unchanged resource can answer 304 Not Modified, and reading as much
+/// of the body as `body` asks for. The `capture` headers of the response are
+/// kept with the result. Failed requests are made again as `retry` says.
+#[tracing::instrument(name = "check", skip(http, validators, capture, retry), fields(url = %url))]
+pub async fn check_link(
+    http: Http,
+    url: Url,
+    validators: Option<Validators>,
+    body: Body,
+    capture: Arc<[HeaderName]>,
+    retry: Retry,
+) -> CheckResult {
+    let mut request = if body == Body::Head {
+        http.head(&url)
+    } else {
+        http.get(&url)
+    };
+    if let Some(validators) = validators {
+        if let Some(etag) = validators.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = validators.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+    let Some(unranged) = request.try_clone() else {
+        // A URL such as one of another scheme that no request can be made of.
+        let err = (request.build()).expect_err("requests without a body copy unless invalid");
+        return CheckResult::failed(
+            url.clone(),
+            &HtmlFindError::request(&url, Phase::CheckLink)(err),
+        );
+    };
+    let unranged = Some(unranged);
+    if let Body::Sample(bytes @ 1..) = body {
+        request = request.header(RANGE, format!("bytes=0-{}", bytes - 1));
+    }
+
+    let mut started = Instant::now();
+    let mut sent = send(&http, &request, unranged.as_ref()).await;
+    let (mut retries, mut retry_cause) = (0, None);
+    while retries < retry.retries && retry.wants(&sent) {
+        retries += 1;
+        let cause = match &sent {
+            Ok(res) => res.status().to_string(),
+            Err(err) => err.to_string(),
+        };
+        tracing::debug!(retry = retries, "{} got {}, retrying", url, cause);
+        tokio::time::sleep(Retry::delay(retries, &sent)).await;
+        retry_cause = Some(cause);
+        started = Instant::now();
+        sent = send(&http, &request, unranged.as_ref()).await;
+    }
+    let turned_away = |sent: &reqwest::Result<Response>| {
+        (sent.as_ref()).is_ok_and(|res| {
+            matches!(
+                res.status(),
+                StatusCode::FORBIDDEN | StatusCode::NOT_ACCEPTABLE
+            )
+        })
+    };
+    let mut as_browser = false;
+    if retry.as_browser && turned_away(&sent) {
+        // `headers` replaces those --header already set, where `header` would
+        // send both.
+        let browser = (request.try_clone().expect("requests have no body")).headers(
+            (BROWSER_HEADERS.into_iter())
+                .map(|(name, value)| (name, HeaderValue::from_static(value)))
+                .collect(),
+        );
+        let first = Instant::now();
+        let again = http.send(browser).await;
+        if !turned_away(&again) && again.is_ok() {
+            tracing::debug!("{} answered once asked as a browser", url);
+            (sent, started, as_browser) = (again, first, true);
+        }
+    }
+    let elapsed_ms = Some(started.elapsed().as_millis() as u64);
+    let result = match sent {
+        Ok(res) => {
+            let status = res.status();
+            let redirected_to = (res.url() != &url).then(|| res.url().clone());
+            let validators = Validators::from_headers(res.headers());
+            let content_type = (res.headers().get(CONTENT_TYPE))
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+            let headers = capture_headers(res.headers(), &capture);
+            let size = body_size(&res);
+            let mut sha256 = None;
+            let mut blocked = bot::protection(status, res.headers(), None);
+            let read = match body {
+                Body::Unread | Body::Sample(_)
+                    if blocked.is_none() && bot::may_be_challenge(status) =>
+                {
+                    let response_headers = res.headers().clone();
+                    read_start(&http, res, bot::SNIFF_BYTES).await.map(|start| {
+                        let start = String::from_utf8_lossy(&start);
+                        blocked = bot::protection(status, &response_headers, Some(&start));
+                    })
+                }
+                Body::Sample(bytes) if http.warc().is_none() => sample(&http, res, bytes).await,
+                Body::Digest => digest(&http, res).await.map(|digest| {
+                    sha256 = Some(digest);
+                }),
+                // Only reads the body when it is being archived.
+                _ => http.consume(res).await,
+            };
+            let error = read.err().map(|err| err.to_string());
+            CheckResult {
+                error,
+                sha256,
+                headers,
+                validators,
+                redirected_to,
+                elapsed_ms,
+                content_type,
+                size,
+                blocked: blocked.map(str::to_string),
+                ..CheckResult::new(url, status)
+            }
+        }
+        Err(err) => {
+            let error = HtmlFindError::request(&url, Phase::CheckLink)(err);
+            CheckResult {
+                elapsed_ms,
+                ..CheckResult::failed(url, &error)
+            }
+        }
+    };
+    CheckResult {
+        retries,
+        retry_cause,
+        as_browser,
+        ..result
+    }
+}
+
+/// Sends a copy of `request`, or of `unranged` when a range was asked for
+/// and the server can't satisfy it, as an empty file has no first bytes to send.
+async fn send(
+    http: &Http,
+    request: &RequestBuilder,
+    unranged: Option<&RequestBuilder>,
+) -> reqwest::Result<Response> {
+    let copy = |request: &RequestBuilder| request.try_clone().expect("requests have no body");
+    let sent = http.send(copy(request)).await;
+    match (&sent, unranged) {
+        (Ok(res), Some(unranged)) if res.status() == StatusCode::RANGE_NOT_SATISFIABLE => {
+            http.send(copy(unranged)).await
+        }
+        _ => sent,
+    }
+}
+
+/// The size of the whole body of `res`: the total its `Content-Range` gives
+/// when only part of it was sent, or else its `Content-Length`.
+fn body_size(res: &Response) -> Option<u64> {
+    if res.status() == StatusCode::PARTIAL_CONTENT {
+        let range = res.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+        return range.rsplit_once('/')?.1.trim().parse().ok();
+    }
+    // A body read as a stream, as recorded fixtures are, knows no length itself.
+    (res.content_length()).or_else(|| {
+        res.headers()
+            .get(CONTENT_LENGTH)?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()
+    })
+}
+
+/// Reads up to `bytes` of the body of `res`, in case a server that sent the
+/// headers fails to send the file itself.
+async fn sample(http: &Http, mut res: Response, bytes: u64) -> reqwest::Result<()> {
+    let mut read = 0;
+    while read < bytes {
+        match res.chunk().await? {
+            Some(chunk) => {
+                http.count(chunk.len());
+                read += chunk.len() as u64;
+            }
+            None => break,
+        }
+    }
+    Ok(())
+}
+
+/// Up to the first `bytes` of the body of `res`, or all of it when it is
+/// being archived.
+async fn read_start(http: &Http, mut res: Response, bytes: usize) -> reqwest::Result<Vec<u8>> {
+    if http.warc().is_some() {
+        return http
+            .bytes(res)
+            .await
+            .map(|body| body[..body.len().min(bytes)].to_vec());
+    }
+    let mut start = Vec::new();
+    while start.len() < bytes {
+        match res.chunk().await? {
+            Some(chunk) => {
+                http.count(chunk.len());
+                start.extend_from_slice(&chunk);
+            }
+            None => break,
+        }
+    }
+    start.truncate(bytes);
+    Ok(start)
+}
+
+/// The SHA-256 digest of the body of `res` in lowercase hex, read as it
+/// arrives unless it is being archived.
+async fn digest(http: &Http, mut res: Response) -> reqwest::Result<String> {
+    let mut hasher = Sha256::new();
+    if http.warc().is_some() {
+        hasher.update(http.bytes(res).await?);
+    } else {
+        while let Some(chunk) = res.chunk().await? {
+            http.count(chunk.len());
+            hasher.update(&chunk);
+        }
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// Resolves the host of `url` or, in the TCP mode, connects to its port as
+/// well, without sending a request.
+#[tracing::instrument(name = "check", fields(url = %url))]
+pub async fn check_host(url: Url, mode: CheckMode) -> CheckResult {
+    let started = Instant::now();
+    let reached = reach(&url, mode).await;
+    let elapsed_ms = Some(started.elapsed().as_millis() as u64);
+    match reached {
+        Ok(()) => CheckResult {
+            elapsed_ms,
+            ..CheckResult::reached(url, mode)
+        },
+        Err(err) => CheckResult {
+            elapsed_ms,
+            mode: Some(mode),
+            ..CheckResult::failed(url, &err)
+        },
+    }
+}
+
+async fn reach(url: &Url, mode: CheckMode) -> std::result::Result<(), HtmlFindError> {
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return Err(HtmlFindError::MissingHost { url: url.clone() });
+    };
+    // IPv6 addresses are bracketed in URLs but not when resolved.
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let timed_out = || std::io::Error::from(std::io::ErrorKind::TimedOut);
+    if mode == CheckMode::Tcp {
+        let connected =
+            tokio::time::timeout(HOST_TIMEOUT, tokio::net::TcpStream::connect((host, port)));
+        return match connected.await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(source)) => Err(HtmlFindError::Connect {
+                host: host.to_string(),
+                port,
+                source,
+            }),
+            Err(_) => Err(HtmlFindError::Connect {
+                host: host.to_string(),
+                port,
+                source: timed_out(),
+            }),
+        };
+    }
+    let resolved = tokio::time::timeout(HOST_TIMEOUT, tokio::net::lookup_host((host, port)));
+    let source = match resolved
+        .await
+        .map(|addresses| addresses.map(Iterator::count))
+    {
+        Ok(Ok(0)) => std::io::Error::other("no addresses"),
+        Ok(Ok(_)) => return Ok(()),
+        Ok(Err(source)) => source,
+        Err(_) => timed_out(),
+    };
+    Err(HtmlFindError::Resolve {
+        host: host.to_string(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::Client;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn response(status: u16, retry_after: Option<&str>) -> reqwest::Result<Response> {
+        let mut res = http::Response::builder().status(status);
+        if let Some(retry_after) = retry_after {
+            res = res.header(RETRY_AFTER, retry_after);
+        }
+        Ok(Response::from(res.body("").unwrap()))
+    }
+
+    /// Answers the requests made of it with `statuses`, one connection each,
+    /// and asks for no wait before a retry.
+    async fn serve(statuses: &'static [u16]) -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move {
+            for status in statuses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let _ = stream.read(&mut [0; 4096]).await.unwrap();
+                let reply = format!(
+                    "HTTP/1.1 {} {}\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status,
+                    StatusCode::from_u16(*status).unwrap().canonical_reason().unwrap()
+                );
+                stream.write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+        url
+    }
+
+    async fn check(url: Url, retries: u32) -> CheckResult {
+        let http = Http::new(Client::new());
+        let retry = Retry {
+            retries,
+            ..Retry::default()
+        };
+        check_link(http, url, None, Body::Unread, Arc::new([]), retry).await
+    }
+
+    #[test]
+    fn retries_the_statuses_asked_for() {
+        let retry = Retry::default();
+        assert!(retry.wants(&response(503, None)));
+        assert!(retry.wants(&response(429, None)));
+        assert!(!retry.wants(&response(404, None)));
+        let retry = Retry {
+            on: Arc::new([StatusCode::NOT_FOUND]),
+            ..Retry::default()
+        };
+        assert!(retry.wants(&response(404, None)) && !retry.wants(&response(503, None)));
+    }
+
+    #[test]
+    fn waits_as_retry_after_asks_or_backs_off() {
+        let delays: Vec<_> = (1..=3)
+            .map(|retry| Retry::delay(retry, &response(503, None)))
+            .collect();
+        assert_eq!(delays, [500, 1000, 2000].map(Duration::from_millis));
+        assert_eq!(
+            Retry::delay(1, &response(429, Some("3"))),
+            Duration::from_secs(3)
+        );
+        assert_eq!(
+            Retry::delay(1, &response(429, Some("3600"))),
+            MAX_RETRY_AFTER
+        );
+        let date = Some("Wed, 21 Oct 2015 07:28:00 GMT");
+        assert_eq!(Retry::delay(2, &response(429, date)), RETRY_BACKOFF * 2);
+    }
+
+    #[tokio::test]
+    async fn reports_what_led_to_a_retry() {
+        let result = check(serve(&[503, 200]).await, 2).await;
+        assert_eq!(result.status, Some(StatusCode::OK));
+        assert_eq!(result.retries, 1);
+        assert_eq!(
+            result.retry_cause.as_deref(),
+            Some("503 Service Unavailable")
+        );
+    }
+
+    #[tokio::test]
+    async fn stops_retrying_after_the_last_retry() {
+        let result = check(serve(&[503, 502, 200]).await, 1).await;
+        assert_eq!(result.status, Some(StatusCode::BAD_GATEWAY));
+        assert_eq!(result.retries, 1);
+        assert_eq!(
+            result.retry_cause.as_deref(),
+            Some("503 Service Unavailable")
+        );
+    }
+}
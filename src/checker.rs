@@ -0,0 +1,1867 @@
+use crate::audit::{audit, audit_entry, Issue, Problem};
+use crate::budget::Budget;
+use crate::cache::{Cache, DEFAULT_CACHE_TTL};
+use crate::caching::{audit_caching, CachingIssue};
+use crate::canonical::{canonical, check_canonicals};
+use crate::capture::Capture;
+use crate::check::{capture_headers, Body, CheckMode, Retry, Validators};
+use crate::duplicates::duplicate_links;
+use crate::error::{parse_url, HtmlFindError, Phase, Result};
+use crate::expect::{Expectation, ExpectedStatus, Unmet};
+use crate::extract::{Attribute, Extractor, Source};
+use crate::fixtures::Fixtures;
+use crate::grep::{Grep, GrepMatch};
+use crate::har::Har;
+use crate::hooks::Hooks;
+use crate::http::Http;
+use crate::local::LocalSite;
+use crate::page::{fetch, get_base_url, is_html, resources, Fetched, Regions};
+use crate::queue::LinkQueue;
+use crate::rank::page_rank;
+use crate::recipe::Recipe;
+#[cfg(feature = "render")]
+use crate::render::Renderer;
+use crate::report::{CheckResult, Report, StopReason};
+use crate::reporter::{Console, Reporter};
+use crate::resource::{HostChecker, HttpChecker, LocalChecker, ResourceChecker};
+use crate::script;
+use crate::signed::{expiry, Expiring};
+use crate::sitemap::{discover, filter_urls, is_site_root, Coverage, Entry, Media, Sitemap};
+use crate::state::CrawlState;
+use crate::structure::LinkGraph;
+use crate::visited::VisitedSet;
+use crate::warc::Warc;
+use crate::wayback;
+use chrono::Utc;
+use futures::stream::{FuturesUnordered, StreamExt};
+use html_find_core::normalize::rewrite_host;
+use indicatif::{ProgressBar, ProgressStyle};
+use reqwest::header::{HeaderMap, HeaderName, CACHE_CONTROL, CONTENT_TYPE};
+use select::document::Document;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinSet;
+use tokio::time::Interval;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn, Instrument, Span};
+use url::Url;
+
+/// How long in-flight checks may keep running once a run is cancelled.
+pub const DEFAULT_SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+pub const DEFAULT_CONCURRENCY: usize = 32;
+
+pub const DEFAULT_CHECKPOINT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Events a channel given to [`Checker::with_events`] holds before the run
+/// waits for its receiver; a page's links are sent as one event each.
+pub const DEFAULT_EVENTS_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Check `<a href>` targets.
+    pub links: bool,
+    /// Check `<img src>` targets.
+    pub images: bool,
+    /// Resolve relative links against this instead of each page's `<base>`.
+    pub base_url: Option<Url>,
+    /// Only check links in these parts of each page.
+    pub regions: Regions,
+    /// Content fetched pages must have.
+    pub expectations: Vec<Expectation>,
+    /// Statuses URLs must answer with; the first that applies to a URL counts.
+    pub expected_statuses: Vec<ExpectedStatus>,
+    /// Print discovered links instead of checking them.
+    pub listing: Option<Listing>,
+    /// Print a JSON row scraped from each fetched page.
+    pub recipe: Option<Recipe>,
+    /// Print the lines of fetched pages matching this.
+    pub grep: Option<Grep>,
+    /// Load pages in this browser before scanning them, so links their scripts add are found.
+    #[cfg(feature = "render")]
+    pub renderer: Option<Renderer>,
+    /// With a renderer, save screenshots of the pages broken links are on,
+    /// or of broken pages themselves, into this directory.
+    #[cfg(feature = "render")]
+    pub screenshots: Option<PathBuf>,
+    /// Scan the pages internal links lead to as well, crawling the whole site
+    /// instead of only the start pages. Fragments of internal links are dropped.
+    pub follow: bool,
+    /// Fetch the scripts pages load and check the absolute URLs they contain,
+    /// such as API endpoints and assets nothing else links to.
+    pub scan_js: bool,
+    /// Ask for and read only this many bytes of each checked link, so large
+    /// files such as videos are confirmed without downloading them.
+    pub sample_bytes: Option<u64>,
+    /// SHA-256 digests, in lowercase hex, the files at these URLs must have.
+    /// They are downloaded in full, never taken from the cache.
+    pub checksums: HashMap<Url, String>,
+    /// Report signed links, such as S3 presigned URLs, that have expired or
+    /// will within this long.
+    pub expires_within: Option<Duration>,
+    /// When to request a checked link again after it fails.
+    pub retry: Retry,
+    /// A random wait of up to this long before each request.
+    pub jitter: Option<Duration>,
+    /// The order queued links are checked in.
+    pub host_order: HostOrder,
+    /// Once this many requests in a row to a host fail without a response,
+    /// its remaining URLs are skipped rather than requested.
+    pub max_host_failures: Option<usize>,
+    /// Most bytes of response bodies to read in the whole run; once they
+    /// are, `over_bandwidth` says what happens to the rest of it.
+    pub max_bandwidth: Option<u64>,
+    pub over_bandwidth: OverBandwidth,
+    /// Report pages whose images, scripts and stylesheets weigh more, or
+    /// number more, than this. Their sizes aren't cached, so they are always requested.
+    pub budget: Budget,
+    /// Count responses bot protection likely blocked as OK rather than blocked.
+    pub blocked_ok: bool,
+    /// Warn about URLs whose response headers take longer than this to arrive.
+    pub slow_threshold: Option<Duration>,
+    /// Look up the Wayback Machine's closest snapshot of each broken external link.
+    pub wayback: bool,
+    /// How far links are checked. Short of HTTP, each host linked to is
+    /// checked once, as the root URL of its origin.
+    pub check_mode: CheckMode,
+    /// Treat the start URLs as sitemaps (XML, plain text, or RSS/Atom feeds),
+    /// or indexes of them nested to any depth, instead of pages. The sitemaps of
+    /// a site root are found through its robots.txt or at the usual paths.
+    pub sitemap: bool,
+    /// Check each URL the sitemaps list itself, reporting those that don't
+    /// belong in a sitemap, instead of only scanning them.
+    pub audit: bool,
+    /// Also crawl each site from its root, following internal links, and
+    /// compare the pages it reaches with those the sitemaps list, which are
+    /// then not fetched themselves.
+    pub coverage: bool,
+    /// Score the pages scanned by the internal links between them, with
+    /// PageRank, to show those linked to little. Meant for runs that `follow`.
+    pub page_rank: bool,
+    /// Report the pages scanned without links to other internal pages.
+    /// Meant for runs that `follow`.
+    pub dead_ends: bool,
+    /// Report the pages scanned only reached by following this many links
+    /// or more from the start pages. Meant for runs that `follow`.
+    pub deep_after: Option<usize>,
+    /// When auditing, report entries whose `<lastmod>` is older than this.
+    pub stale_after: Option<Duration>,
+    pub shutdown_grace: Duration,
+    /// End the run, aborting in-flight checks, once this much time has passed.
+    pub max_duration: Option<Duration>,
+    /// Maximum number of requests in flight at once, fetches and checks combined.
+    pub concurrency: usize,
+    /// Where to keep outcomes between runs; `None` disables the cache.
+    pub cache_file: Option<PathBuf>,
+    /// How long a cached OK outcome is trusted.
+    pub cache_ttl: Duration,
+    /// Where to checkpoint the crawl so an unfinished run can be resumed.
+    /// The file is removed once a run completes.
+    pub state_file: Option<PathBuf>,
+    /// Continue from `state_file` if it exists instead of starting over.
+    pub resume: bool,
+    pub checkpoint_interval: Duration,
+    /// Log each outcome as it comes in.
+    pub print_progress: bool,
+    /// Write every request and response made during the run to this HAR file.
+    pub har_file: Option<PathBuf>,
+    /// Archive fetched pages and checked resources to this WARC file.
+    pub warc_file: Option<PathBuf>,
+    /// Only find what the run would check, fetching the pages to crawl but
+    /// checking none of their links.
+    pub dry_run: bool,
+    /// Record responses to a directory, or answer requests from one.
+    pub fixtures: Option<Fixtures>,
+    /// Sent with every request.
+    pub headers: HeaderMap,
+    /// Report static assets that browsers can't cache well and pages they
+    /// cache for too long, by their `Cache-Control` header.
+    pub audit_caching: bool,
+    /// Report pages linking to one URL more than this many times, or to
+    /// themselves.
+    pub max_duplicate_links: Option<usize>,
+    /// Report pages whose canonical URL redirects or fails, links to pages
+    /// naming another canonical URL, and, with `coverage`, sitemaps listing
+    /// pages other than the canonical ones. Meant for runs that `follow`.
+    pub audit_canonicals: bool,
+    /// Response headers kept with each result, such as `cache-control`.
+    /// Results are then never taken from the cache, which doesn't have them.
+    pub capture_headers: Vec<HeaderName>,
+    /// Links and pages whose URL matches one of these are skipped.
+    pub exclude: Vec<glob::Pattern>,
+    /// Links to these domains, or their subdomains, are skipped.
+    pub skip_domains: Vec<String>,
+    /// When set, external links are skipped unless they are to one of these
+    /// domains or their subdomains.
+    pub only_domains: Vec<String>,
+    /// Settings for particular hosts, keyed by host name.
+    pub hosts: HashMap<String, HostOptions>,
+    /// Discovered links to a key host are checked against its value instead,
+    /// e.g. to verify production links on staging. Values may carry a port.
+    pub rewrite_hosts: HashMap<String, String>,
+}
+
+/// Settings for one host, on top of the run-wide ones.
+#[derive(Debug, Clone, Default)]
+pub struct HostOptions {
+    /// Sent with requests to the host, replacing same-named run-wide headers.
+    pub headers: HeaderMap,
+    /// Maximum number of requests to the host in flight at once.
+    pub concurrency: Option<usize>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            links: false,
+            images: false,
+            base_url: None,
+            regions: Regions::default(),
+            expectations: Vec::new(),
+            expected_statuses: Vec::new(),
+            listing: None,
+            recipe: None,
+            grep: None,
+            #[cfg(feature = "render")]
+            renderer: None,
+            #[cfg(feature = "render")]
+            screenshots: None,
+            follow: false,
+            scan_js: false,
+            sample_bytes: None,
+            checksums: HashMap::new(),
+            expires_within: None,
+            slow_threshold: None,
+            blocked_ok: false,
+            budget: Budget::default(),
+            retry: Retry::default(),
+            jitter: None,
+            host_order: HostOrder::default(),
+            max_host_failures: None,
+            max_bandwidth: None,
+            over_bandwidth: OverBandwidth::default(),
+            wayback: false,
+            check_mode: CheckMode::Http,
+            sitemap: false,
+            audit: false,
+            coverage: false,
+            page_rank: false,
+            dead_ends: false,
+            deep_after: None,
+            stale_after: None,
+            shutdown_grace: DEFAULT_SHUTDOWN_GRACE,
+            max_duration: None,
+            concurrency: DEFAULT_CONCURRENCY,
+            cache_file: None,
+            cache_ttl: DEFAULT_CACHE_TTL,
+            state_file: None,
+            resume: false,
+            checkpoint_interval: DEFAULT_CHECKPOINT_INTERVAL,
+            print_progress: true,
+            har_file: None,
+            skip_domains: Vec::new(),
+            only_domains: Vec::new(),
+            warc_file: None,
+            dry_run: false,
+            fixtures: None,
+            headers: HeaderMap::new(),
+            audit_caching: false,
+            max_duplicate_links: None,
+            audit_canonicals: false,
+            capture_headers: vec![],
+            exclude: Vec::new(),
+            hosts: HashMap::new(),
+            rewrite_hosts: HashMap::new(),
+        }
+    }
+}
+
+/// How discovered links are printed when they are listed rather than checked.
+#[derive(Debug, Clone, Default)]
+pub struct Listing {
+    /// Drop fragments, so `page#a` and `page#b` are listed once as `page`.
+    pub normalize: bool,
+    /// Only list links to (or away from) the hosts of the start URLs.
+    pub only: Option<Scope>,
+    /// Count the references to each external host into [`Report::domains`]
+    /// instead of printing links.
+    pub domains: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Internal,
+    External,
+}
+
+/// The order links are checked in when more are found than can be checked at once.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HostOrder {
+    /// In the order they were found.
+    Fifo,
+    /// Taking turns between hosts, so no single one gets a burst of requests.
+    #[default]
+    RoundRobin,
+}
+
+/// What a run does once it has read `max_bandwidth` bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverBandwidth {
+    /// Check the remaining links with HEAD requests, which have no body.
+    /// Pages are still fetched.
+    #[default]
+    Head,
+    /// End the run as a deadline would.
+    Stop,
+}
+
+/// Something that happened during a run, sent to [`Checker::with_events`].
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// `link` was found on `page`, whether or not it was seen before.
+    Linked {
+        page: Url,
+        link: Url,
+    },
+    Checked(Box<CheckResult>),
+    /// The row a recipe turned `page` into.
+    Row {
+        page: Url,
+        row: serde_json::Value,
+    },
+    /// A grep match on a page.
+    Matched(GrepMatch),
+    /// A link found when listing them instead of checking.
+    Listed(Url),
+}
+
+/// A document waiting to be fetched.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) enum Job {
+    /// Lists the pages to scan, or, as a sitemap index, more sitemaps.
+    /// Older state files call it `SitemapIndex`.
+    #[serde(alias = "SitemapIndex")]
+    Sitemap(Url),
+    Page(Url),
+    /// A URL from an input list: checked itself, then scanned like a page.
+    Listed(Url),
+    /// A URL a sitemap lists, when auditing: checked and audited, then
+    /// scanned like a page.
+    Entry(Url),
+    /// A script a page loads, when scanning scripts: checked, then searched
+    /// for the URLs it refers to.
+    Script(Url),
+}
+
+impl Job {
+    fn url(&self) -> &Url {
+        match self {
+            Job::Sitemap(url)
+            | Job::Page(url)
+            | Job::Listed(url)
+            | Job::Entry(url)
+            | Job::Script(url) => url,
+        }
+    }
+
+    fn phase(&self) -> Phase {
+        match self {
+            Job::Sitemap(_) => Phase::FetchSitemap,
+            Job::Page(_) | Job::Listed(_) | Job::Entry(_) => Phase::FetchPage,
+            Job::Script(_) => Phase::FetchScript,
+        }
+    }
+}
+
+pub struct Checker {
+    options: Options,
+    /// URLs the run started from.
+    starts: Vec<Url>,
+    cancel: CancellationToken,
+    /// Child of `cancel`, also fired when `max_duration` elapses.
+    stop: CancellationToken,
+    http: Http,
+    permits: Arc<Semaphore>,
+    /// Limits for hosts with their own `concurrency`.
+    host_permits: HashMap<String, Arc<Semaphore>>,
+    viewed: Box<dyn VisitedSet>,
+    frontier: VecDeque<Job>,
+    /// Documents being fetched, kept so checkpoints can requeue them.
+    fetching: Vec<Job>,
+    /// Discovered links without an outcome yet, scheduled or not.
+    pending: HashSet<Url>,
+    tasks: JoinSet<CheckResult>,
+    /// Links to check once fewer than `concurrency` checks are in flight.
+    queue: LinkQueue,
+    checkpoints: Option<Interval>,
+    cache: Option<Cache>,
+    /// The headers each check keeps, shared between them.
+    capture: Arc<[HeaderName]>,
+    /// Requests in a row to each server, by host and port, that failed without a response.
+    host_failures: HashMap<String, usize>,
+    /// Servers given up on after `max_host_failures`, shared with scheduled checks.
+    down_hosts: Arc<Mutex<HashSet<String>>>,
+    /// Set when checking files on disk, whose internal links are looked up there.
+    site: Option<Arc<LocalSite>>,
+    /// Where each local link was found, attached to its result.
+    found_in: HashMap<Url, Vec<String>>,
+    /// What the images and videos of audited sitemap entries should serve.
+    media: HashMap<Url, Media>,
+    /// The pages the sitemaps list, when comparing them with a crawl.
+    listed: HashSet<Url>,
+    /// The canonical URL each page scanned names, when auditing them.
+    canonicals: BTreeMap<Url, Url>,
+    /// The scripts pages load, when scanning them, to fetch rather than check.
+    scripts: HashSet<Url>,
+    /// The resources each page loads, when weighing pages.
+    resources: HashMap<Url, HashSet<Url>>,
+    /// The pages linking to each URL, to rank broken ones by.
+    referrers: HashMap<Url, HashSet<Url>>,
+    /// The page each link was first found on, when taking screenshots.
+    #[cfg(feature = "render")]
+    linked_from: HashMap<Url, Url>,
+    report: Report,
+    /// Broken results in `report`, kept for the progress bar.
+    broken: u64,
+    /// Whether `max_bandwidth` bytes have been read.
+    over_bandwidth: bool,
+    progress: Option<ProgressBar>,
+    events: Option<Sender<Event>>,
+    /// Events waiting for room in `events`, sent between steps of the run.
+    queued_events: VecDeque<Event>,
+    hooks: Option<Box<dyn Hooks>>,
+    /// Tried in order before a link is requested.
+    resource_checkers: Vec<Arc<dyn ResourceChecker>>,
+    extractors: Vec<Box<dyn Extractor>>,
+    /// Given each outcome and the report, a [`Console`] first when printing progress.
+    reporters: Vec<Box<dyn Reporter>>,
+}
+
+impl Checker {
+    pub fn new(options: Options) -> Self {
+        let permits = Arc::new(Semaphore::new(options.concurrency.max(1)));
+        let queue = LinkQueue::new(options.host_order);
+        let blocked_ok = options.blocked_ok;
+        let slow_after_ms = (options.slow_threshold).map(|threshold| threshold.as_millis() as u64);
+        let host_permits = options
+            .hosts
+            .iter()
+            .filter_map(|(host, options)| {
+                let permits = Semaphore::new(options.concurrency?.max(1));
+                Some((host.clone(), Arc::new(permits)))
+            })
+            .collect();
+        let host_headers = options
+            .hosts
+            .iter()
+            .filter(|(_, options)| !options.headers.is_empty())
+            .map(|(host, options)| (host.clone(), options.headers.clone()))
+            .collect();
+        let mut http = Http::default().with_headers(options.headers.clone(), host_headers);
+        if options.har_file.is_some() {
+            http = http.with_har(Har::default());
+        }
+        if let Some(fixtures) = &options.fixtures {
+            http = http.with_fixtures(fixtures.clone());
+        }
+        let cache = options
+            .cache_file
+            .as_deref()
+            .map(|path| Cache::load(path, options.cache_ttl));
+        let mut capture = options.capture_headers.clone();
+        if options.audit_caching && !capture.contains(&CACHE_CONTROL) {
+            capture.push(CACHE_CONTROL);
+        }
+        let capture = capture.into();
+        let mut reporters: Vec<Box<dyn Reporter>> = vec![];
+        if options.print_progress {
+            reporters.push(Box::new(Console));
+        }
+        Checker {
+            options,
+            starts: Vec::new(),
+            cancel: CancellationToken::new(),
+            stop: CancellationToken::new(),
+            http,
+            permits,
+            host_permits,
+            viewed: Box::new(HashSet::new()),
+            frontier: VecDeque::new(),
+            fetching: Vec::new(),
+            pending: HashSet::new(),
+            tasks: JoinSet::new(),
+            queue,
+            checkpoints: None,
+            cache,
+            capture,
+            host_failures: HashMap::new(),
+            down_hosts: Arc::default(),
+            site: None,
+            found_in: HashMap::new(),
+            media: HashMap::new(),
+            listed: HashSet::new(),
+            canonicals: BTreeMap::new(),
+            scripts: HashSet::new(),
+            resources: HashMap::new(),
+            referrers: HashMap::new(),
+            #[cfg(feature = "render")]
+            linked_from: HashMap::new(),
+            report: Report {
+                slow_after_ms,
+                blocked_ok,
+                ..Report::default()
+            },
+            broken: 0,
+            over_bandwidth: false,
+            progress: None,
+            events: None,
+            queued_events: VecDeque::new(),
+            hooks: None,
+            resource_checkers: vec![],
+            extractors: vec![],
+            reporters,
+        }
+    }
+
+    /// Sends requests through `client`, e.g. one configured with a proxy.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.http = self.http.with_client(client);
+        self
+    }
+
+    /// Keeps track of seen URLs in `viewed` instead of in memory.
+    pub fn with_visited(mut self, viewed: impl VisitedSet + 'static) -> Self {
+        self.viewed = Box::new(viewed);
+        self
+    }
+
+    /// Shows discovered, checked and broken counts on `bar` while the run goes.
+    pub fn with_progress(mut self, bar: ProgressBar) -> Self {
+        bar.set_style(
+            ProgressStyle::with_template(
+                "{spinner} [{elapsed_precise}] {wide_bar} {pos}/{len} checked, {msg} (ETA {eta})",
+            )
+            .expect("progress template is valid"),
+        );
+        self.progress = Some(bar);
+        self
+    }
+
+    /// Sends links as they are found and outcomes as they come in to `events`,
+    /// e.g. to show them live. While the channel is full the run waits for
+    /// room, so a slow receiver slows it down. A closed receiver is ignored.
+    pub fn with_events(mut self, events: Sender<Event>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Calls `hooks` as pages are fetched, links are checked and errors happen.
+    pub fn with_hooks(mut self, hooks: impl Hooks + 'static) -> Self {
+        self.hooks = Some(Box::new(hooks));
+        self
+    }
+
+    /// Also sends the run's outcomes to `reporter`.
+    pub fn with_reporter(mut self, reporter: impl Reporter + 'static) -> Self {
+        self.reporters.push(Box::new(reporter));
+        self
+    }
+
+    /// Also checks the links `extractor` finds on each page scanned.
+    pub fn with_extractor(mut self, extractor: impl Extractor + 'static) -> Self {
+        self.extractors.push(Box::new(extractor));
+        self
+    }
+
+    /// Checks the links `checker` handles with it rather than requesting
+    /// them. Checkers added earlier come first.
+    pub fn with_resource_checker(mut self, checker: impl ResourceChecker + 'static) -> Self {
+        self.resource_checkers.push(Arc::new(checker));
+        self
+    }
+
+    /// Uses `cancel` to stop the run early; the report gathered so far is still returned.
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// The token that stops the run early when cancelled, the one given to
+    /// [`Checker::with_cancellation`] or else the checker's own.
+    pub fn cancellation(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// Crawls from each of `urls` in one run, sharing the visited set so a
+    /// link reachable from several of them is checked once.
+    pub async fn run(mut self, urls: &[Url]) -> Result<Report> {
+        self.starts = urls.to_vec();
+        let _deadline = self.start()?;
+        if !self.resume()? {
+            if self.options.sitemap && self.options.coverage {
+                for root in urls.iter().filter_map(|url| url.join("/").ok()) {
+                    if self.viewed.insert(root.as_str())? {
+                        self.report.discovered += 1;
+                        self.frontier.push_back(Job::Listed(root));
+                    }
+                }
+            }
+            for url in urls {
+                if self.options.sitemap && is_site_root(url) {
+                    self.discover(url).await?;
+                    continue;
+                }
+                if !self.viewed.insert(url.as_str())? {
+                    continue;
+                }
+                self.frontier.push_back(if self.options.sitemap {
+                    Job::Sitemap(url.clone())
+                } else if self.options.follow {
+                    self.report.discovered += 1;
+                    Job::Listed(url.clone())
+                } else {
+                    Job::Page(url.clone())
+                });
+            }
+        }
+        self.complete().await
+    }
+
+    /// Queues the sitemaps found for the site at `root`, or records that there are none.
+    async fn discover(&mut self, root: &Url) -> Result<()> {
+        let found = discover(&self.http, root).await;
+        if found.is_empty() {
+            let err = HtmlFindError::NoSitemap { url: root.clone() };
+            self.report.discovered += 1;
+            self.record_result(CheckResult::failed(root.clone(), &err));
+        }
+        for sitemap in found {
+            info!("found sitemap {} for {}", sitemap, root);
+            if self.viewed.insert(sitemap.as_str())? {
+                self.frontier.push_back(Job::Sitemap(sitemap));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks each of `urls` itself. When links or images are enabled, or a recipe
+    /// or grep pattern is set, the URLs are also scanned as pages, sharing the request with the check.
+    pub async fn run_list(mut self, urls: &[Url]) -> Result<Report> {
+        self.starts = urls.to_vec();
+        let _deadline = self.start()?;
+        if !self.resume()? {
+            if self.options.links
+                || self.options.images
+                || self.options.recipe.is_some()
+                || self.options.grep.is_some()
+            {
+                for url in urls {
+                    if self.viewed.insert(url.as_str())? {
+                        self.report.discovered += 1;
+                        self.frontier.push_back(Job::Listed(url.clone()));
+                    }
+                }
+            } else {
+                self.check_links(None, urls.iter().cloned().collect())?;
+            }
+        }
+        self.complete().await
+    }
+
+    /// Checks the URLs recorded in `capture` and the links on its archived
+    /// pages against the live web, without fetching those pages again.
+    pub async fn run_capture(mut self, capture: &Capture) -> Result<Report> {
+        self.starts = vec![capture.source.clone()];
+        let _deadline = self.start()?;
+        if !self.resume()? {
+            self.check_links(None, capture.urls.iter().cloned().collect())?;
+            for page in &capture.pages {
+                self.viewed.insert(page.url.as_str())?;
+                let document = Document::from(page.html.as_str());
+                self.check_page(page.url.clone(), &page.html, &document)?;
+            }
+        }
+        self.complete().await
+    }
+
+    /// Checks the links on the pages of `site`, noting the file and line each
+    /// was found on. Links into the site are looked
+    /// up on disk; everything else is checked over HTTP.
+    pub async fn run_path(mut self, mut site: LocalSite) -> Result<Report> {
+        self.starts = vec![site.base.clone()];
+        let _deadline = self.start()?;
+        let pages = std::mem::take(&mut site.pages);
+        let site = Arc::new(site);
+        // Ahead of the checkers given, as links into the site aren't requested.
+        (self.resource_checkers).insert(0, Arc::new(LocalChecker::new(site.clone())));
+        self.site = Some(site);
+        if !self.resume()? {
+            for page in &pages {
+                let links = (self.options.links.then_some(&page.links).into_iter())
+                    .chain(self.options.images.then_some(&page.images))
+                    .flatten();
+                for link in links {
+                    let found_in = self.found_in.entry(link.clone()).or_default();
+                    found_in.push(page.location(link));
+                }
+            }
+            for page in pages {
+                self.viewed.insert(page.url.as_str())?;
+                self.report.pages.push(page.url.clone());
+                if self.options.links {
+                    self.check_links(Some(&page.url), page.links)?;
+                }
+                if self.options.images {
+                    self.check_links(Some(&page.url), page.images)?;
+                }
+            }
+        }
+        self.complete().await
+    }
+
+    /// Prepares a run. The returned set holds the `max_duration` timer and
+    /// aborts it when dropped.
+    fn start(&mut self) -> Result<JoinSet<()>> {
+        self.stop = self.cancel.child_token();
+        if let Some(path) = &self.options.warc_file {
+            self.http = self.http.clone().with_warc(Warc::create(path)?);
+        }
+        if self.options.state_file.is_some() {
+            let mut interval = tokio::time::interval(self.options.checkpoint_interval);
+            interval.reset();
+            self.checkpoints = Some(interval);
+        }
+        let mut deadline = JoinSet::new();
+        if let Some(max_duration) = self.options.max_duration {
+            let stop = self.stop.clone();
+            deadline.spawn(async move {
+                tokio::time::sleep(max_duration).await;
+                stop.cancel();
+            });
+        }
+        Ok(deadline)
+    }
+
+    /// Works through the frontier and scheduled checks, then writes out
+    /// everything the run produced.
+    #[tracing::instrument(name = "run", skip_all, fields(starts = self.starts.len()))]
+    async fn complete(mut self) -> Result<Report> {
+        self.crawl().await?;
+        self.drain().await?;
+        #[cfg(feature = "render")]
+        self.screenshot_broken().await?;
+        self.find_archived().await;
+        (self.report.over_budget) =
+            (self.options.budget).over(&self.resources, &self.report.results);
+        if let Some(cache) = &mut self.cache {
+            cache.save()?;
+        }
+        if let (Some(har), Some(path)) = (self.http.har(), &self.options.har_file) {
+            har.save(path)?;
+        }
+        if let Some(warc) = self.http.warc() {
+            warc.flush()?;
+        }
+        if let Some(path) = &self.options.state_file {
+            if self.stop.is_cancelled() {
+                self.checkpoint()?;
+            } else {
+                CrawlState::remove(path)?;
+            }
+        }
+
+        let mut reporters = std::mem::take(&mut self.reporters);
+        let report = self.finish();
+        for reporter in &mut reporters {
+            reporter.finish(&report).await?;
+        }
+        Ok(report)
+    }
+
+    /// Restores the state file when resuming, returning whether there was one.
+    fn resume(&mut self) -> Result<bool> {
+        let Some(path) = self
+            .options
+            .state_file
+            .as_deref()
+            .filter(|_| self.options.resume)
+        else {
+            return Ok(false);
+        };
+        let Some(state) = CrawlState::load(path)? else {
+            info!("No state in {}, starting a new crawl", path.display());
+            return Ok(false);
+        };
+        if state.starts != self.starts {
+            warn!(
+                "{} belongs to a crawl of other URLs, starting a new crawl",
+                path.display()
+            );
+            return Ok(false);
+        }
+
+        self.frontier = state.frontier.into();
+        for url in &state.viewed {
+            self.viewed.insert(url)?;
+        }
+        self.report.results = state.results;
+        self.report.discovered = state.discovered;
+        self.report.pages = state.pages;
+        self.report.unmet = state.unmet;
+        self.report.matches = state.matches;
+        self.report.sitemap_issues = state.sitemap_issues;
+        self.report.caching_issues = state.caching_issues;
+        self.report.duplicate_links = state.duplicate_links;
+        self.report.expiring = state.expiring;
+        self.resources = state.resources;
+        self.referrers = state.referrers;
+        self.listed = state.listed.into_iter().collect();
+        self.canonicals = state.canonicals;
+        self.broken = self.report.broken().count() as u64;
+        for link in state.pending {
+            self.schedule(link);
+        }
+        Ok(true)
+    }
+
+    fn checkpoint(&self) -> Result<()> {
+        let Some(path) = &self.options.state_file else {
+            return Ok(());
+        };
+        let state = CrawlState {
+            starts: self.starts.clone(),
+            frontier: self
+                .fetching
+                .iter()
+                .chain(&self.frontier)
+                .cloned()
+                .collect(),
+            pending: self.pending.iter().cloned().collect(),
+            viewed: self.viewed.urls()?,
+            results: self.report.results.clone(),
+            discovered: self.report.discovered,
+            pages: self.report.pages.clone(),
+            unmet: self.report.unmet.clone(),
+            matches: self.report.matches.clone(),
+            sitemap_issues: self.report.sitemap_issues.clone(),
+            caching_issues: self.report.caching_issues.clone(),
+            duplicate_links: self.report.duplicate_links.clone(),
+            expiring: self.report.expiring.clone(),
+            resources: self.resources.clone(),
+            referrers: self.referrers.clone(),
+            listed: self.listed.iter().cloned().collect(),
+            canonicals: self.canonicals.clone(),
+        };
+        state.save(path)
+    }
+
+    /// Fetches the frontier with at most `concurrency` documents in flight,
+    /// checking links as pages arrive.
+    async fn crawl(&mut self) -> Result<()> {
+        let mut fetches = FuturesUnordered::new();
+
+        loop {
+            self.flush_events().await;
+            while fetches.len() < self.options.concurrency.max(1) && !self.stop.is_cancelled() {
+                let Some(job) = self.frontier.pop_front() else {
+                    break;
+                };
+                self.fetching.push(job.clone());
+                let http = self.http.clone();
+                let permits = self.permits_for(job.url());
+                #[cfg(feature = "render")]
+                let renderer =
+                    (self.options.renderer.clone()).filter(|_| job.phase() == Phase::FetchPage);
+                let span = tracing::info_span!("fetch", url = %job.url(), phase = %job.phase());
+                let jitter = jitter(self.options.jitter);
+                fetches.push(
+                    async move {
+                        tokio::time::sleep(jitter).await;
+                        let _permits = permits.acquire().await;
+                        let page = fetch(&http, job.url(), job.phase()).await;
+                        #[cfg(feature = "render")]
+                        let page = match (page, renderer) {
+                            (Ok(page), Some(renderer)) => renderer.render(page).await,
+                            (page, _) => page,
+                        };
+                        (job, page)
+                    }
+                    .instrument(span),
+                );
+            }
+            if fetches.is_empty() {
+                return Ok(());
+            }
+
+            tokio::select! {
+                biased;
+                _ = self.stop.cancelled() => return Ok(()),
+                Some(joined) = self.tasks.join_next() => self.record(joined),
+                Some((job, page)) = fetches.next() => {
+                    self.fetching.retain(|fetching| fetching.url() != job.url());
+                    self.check_bandwidth();
+                    match page {
+                        Ok(page) => {
+                            if let Some(hooks) = &mut self.hooks {
+                                hooks.on_page_fetched(&page);
+                            }
+                            self.handle(job, page)?
+                        }
+                        Err(err) => {
+                            if let Some(hooks) = &mut self.hooks {
+                                hooks.on_error(&err);
+                            }
+                            if !matches!(job, Job::Listed(_) | Job::Entry(_) | Job::Script(_)) {
+                                self.report.discovered += 1;
+                            }
+                            self.record_result(CheckResult::failed(job.url().clone(), &err));
+                        }
+                    }
+                }
+                _ = tick(&mut self.checkpoints) => self.checkpoint()?,
+            }
+        }
+    }
+
+    /// Sitemaps only list URLs on their own host; others are ignored.
+    fn handle(&mut self, job: Job, page: Fetched) -> Result<()> {
+        let _span = tracing::info_span!("parse", url = %job.url()).entered();
+        let (status, body) = (page.status, &page.body);
+        debug!(
+            status = status.as_u16(),
+            bytes = body.len(),
+            "fetched {}",
+            job.url()
+        );
+        match job {
+            // Indexes may list further indexes; each shard is fetched once.
+            Job::Sitemap(url) => match Sitemap::parse(&url, body) {
+                Ok(sitemap) => {
+                    let audited = self.options.audit && !sitemap.index;
+                    // The first entry for each URL, for what the sitemap says
+                    // about the ones not seen before. Keyed as those are, with
+                    // --rewrite-host applied.
+                    let mut entries: HashMap<Url, &Entry> = HashMap::new();
+                    for entry in sitemap.entries.iter().filter(|_| audited) {
+                        if let Ok(loc) = Url::parse(&entry.loc) {
+                            entries.entry(self.rewrite(loc)).or_insert(entry);
+                        }
+                    }
+                    if self.options.coverage && !sitemap.index {
+                        // Left unmarked, so the crawl still follows links to them.
+                        for listed in filter_urls(sitemap.urls(), &url)? {
+                            // Compared with what the crawl finds, which is rewritten.
+                            let listed = self.rewrite(parse_url(&listed)?);
+                            if !self.excluded(listed.as_str()) {
+                                self.listed.insert(listed);
+                            }
+                        }
+                        return Ok(());
+                    }
+                    let now = Utc::now();
+                    let mut media = HashSet::new();
+                    for listed in self.unvisited(filter_urls(sitemap.urls(), &url)?)? {
+                        if let Some(entry) = entries.get(&listed) {
+                            for problem in audit_entry(entry, self.options.stale_after, now) {
+                                self.record_issue(Issue {
+                                    url: listed.clone(),
+                                    problem,
+                                });
+                            }
+                            for (loc, kind) in entry.media() {
+                                let Ok(loc) = url.join(loc) else {
+                                    continue;
+                                };
+                                let loc = self.rewrite(loc);
+                                if let Some(kind) = kind {
+                                    self.media.insert(loc.clone(), kind);
+                                }
+                                media.insert(loc);
+                            }
+                        }
+                        self.frontier.push_back(if sitemap.index {
+                            Job::Sitemap(listed)
+                        } else if self.options.audit {
+                            self.report.discovered += 1;
+                            Job::Entry(listed)
+                        } else {
+                            Job::Page(listed)
+                        });
+                    }
+                    self.check_links(Some(&url), media)?;
+                }
+                Err(err) => {
+                    self.report.discovered += 1;
+                    self.record_result(CheckResult::failed(url, &err));
+                }
+            },
+            Job::Page(url) => self.check_page(url, body, &Document::from(body.as_str()))?,
+            Job::Listed(url) => {
+                let mut result = CheckResult::new(url.clone(), status);
+                result.redirected_to = (page.url != url).then(|| page.url.clone());
+                result.validators = Validators::from_headers(&page.headers);
+                result.content_type = (page.headers.get(CONTENT_TYPE))
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string);
+                result.headers = capture_headers(&page.headers, &self.capture);
+                let mut scan = result.is_ok();
+                if self.options.follow {
+                    // Followed to where it redirects instead, and only pages are
+                    // scanned, not the files they link to.
+                    scan &=
+                        result.redirected_to.is_none() && is_html(result.content_type.as_deref());
+                    if let Some(target) =
+                        (result.redirected_to.clone()).filter(|target| self.is_internal(target))
+                    {
+                        self.check_links(None, HashSet::from([target]))?;
+                    }
+                }
+                self.record_result(result);
+                if scan {
+                    self.check_page(url, body, &Document::from(body.as_str()))?;
+                }
+            }
+            Job::Entry(url) => {
+                let mut result = CheckResult::new(url.clone(), status);
+                result.redirected_to = (page.url != url).then(|| page.url.clone());
+                result.headers = capture_headers(&page.headers, &self.capture);
+                let ok = result.is_ok();
+                self.record_result(result);
+                for problem in audit(&url, &page) {
+                    self.record_issue(Issue {
+                        url: url.clone(),
+                        problem,
+                    });
+                }
+                if ok {
+                    self.check_page(url, body, &Document::from(body.as_str()))?;
+                }
+            }
+            Job::Script(url) => {
+                let mut result = CheckResult::new(url.clone(), status);
+                result.redirected_to = (page.url != url).then(|| page.url.clone());
+                result.validators = Validators::from_headers(&page.headers);
+                result.headers = capture_headers(&page.headers, &self.capture);
+                let ok = result.is_ok();
+                self.record_result(result);
+                if ok {
+                    self.check_links(Some(&url), script::urls(body))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Saves a screenshot of the page each broken URL was found on, or else
+    /// of the URL itself, noting in the report where it went. Each page is
+    /// taken once, however many broken links it has.
+    #[cfg(feature = "render")]
+    async fn screenshot_broken(&mut self) -> Result<()> {
+        let (Some(renderer), Some(dir)) = (&self.options.renderer, &self.options.screenshots)
+        else {
+            return Ok(());
+        };
+        std::fs::create_dir_all(dir).map_err(|source| HtmlFindError::File {
+            path: dir.clone(),
+            source,
+        })?;
+        let mut taken: HashMap<Url, Option<PathBuf>> = HashMap::new();
+        let broken: Vec<Url> = self
+            .report
+            .broken()
+            .map(|result| result.url.clone())
+            .collect();
+        for url in broken {
+            if self.stop.is_cancelled() {
+                break;
+            }
+            let page = self.linked_from.get(&url).unwrap_or(&url).clone();
+            if !taken.contains_key(&page) {
+                let path = dir.join(screenshot_name(taken.len() + 1, &page));
+                let saved = match renderer.screenshot(&page, &path).await {
+                    Ok(()) => Some(path),
+                    Err(err) => {
+                        warn!("{}", err);
+                        if let Some(hooks) = &mut self.hooks {
+                            hooks.on_error(&err);
+                        }
+                        None
+                    }
+                };
+                taken.insert(page.clone(), saved);
+            }
+            if let Some(Some(path)) = taken.get(&page) {
+                self.report.screenshots.insert(url, path.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Notes the closest Wayback Machine snapshot of each broken external
+    /// link, looking up `concurrency` at a time. Failed lookups are only logged.
+    async fn find_archived(&mut self) {
+        if !self.options.wayback {
+            return;
+        }
+        let broken: Vec<(usize, Url)> = (self.report.results.iter().enumerate())
+            .filter(|(_, result)| !result.is_ok() && !self.is_internal(&result.url))
+            .map(|(i, result)| (i, result.url.clone()))
+            .collect();
+        let http = &self.http;
+        let mut lookups = futures::stream::iter(broken)
+            .map(|(i, url)| async move { (i, wayback::closest(http, &url).await) })
+            .buffer_unordered(self.options.concurrency.max(1));
+        let mut found = vec![];
+        loop {
+            tokio::select! {
+                _ = self.stop.cancelled() => break,
+                lookup = lookups.next() => match lookup {
+                    Some((i, Ok(archived))) => found.push((i, archived)),
+                    Some((_, Err(err))) => {
+                        warn!("{}", err);
+                        if let Some(hooks) = &mut self.hooks {
+                            hooks.on_error(&err);
+                        }
+                    }
+                    None => break,
+                },
+            }
+        }
+        drop(lookups);
+        for (i, archived) in found {
+            self.report.results[i].archived = archived;
+        }
+    }
+
+    fn record_issue(&mut self, issue: Issue) {
+        if self.options.print_progress {
+            warn!("{}", issue);
+        }
+        self.report.sitemap_issues.push(issue);
+    }
+
+    fn base_url(&self, page: &Url, document: &Document) -> Result<Url> {
+        match &self.options.base_url {
+            Some(base_url) => Ok(base_url.clone()),
+            None => get_base_url(page, document),
+        }
+    }
+
+    fn unvisited(&mut self, urls: Vec<String>) -> Result<Vec<Url>> {
+        let mut fresh = vec![];
+        for url in urls {
+            let url = self.rewrite(parse_url(&url)?);
+            if !self.excluded(url.as_str()) && self.viewed.insert(url.as_str())? {
+                fresh.push(url);
+            }
+        }
+        Ok(fresh)
+    }
+
+    /// Queues `event` for the run's listener, if it has one.
+    fn emit(&mut self, event: Event) {
+        if self.events.is_some() {
+            self.queued_events.push_back(event);
+        }
+    }
+
+    /// Sends the queued events, waiting while the listener's channel is full.
+    async fn flush_events(&mut self) {
+        let Some(events) = &self.events else {
+            return;
+        };
+        while let Some(event) = self.queued_events.pop_front() {
+            if events.send(event).await.is_err() {
+                self.events = None;
+                self.queued_events.clear();
+                return;
+            }
+        }
+    }
+
+    /// Checks the links on a page; `html` is its source, `document` the same parsed.
+    fn check_page(&mut self, page: Url, html: &str, document: &Document) -> Result<()> {
+        let base_url = self.base_url(&page, document)?;
+        self.check_content(&page, html);
+        if let Some(recipe) = &self.options.recipe {
+            let row = recipe.apply(&page, html);
+            for reporter in &mut self.reporters {
+                reporter.on_row(&page, &row);
+            }
+            self.emit(Event::Row {
+                page: page.clone(),
+                row,
+            });
+        }
+        if let Some(grep) = &self.options.grep {
+            for found in grep.search(&page, html) {
+                for reporter in &mut self.reporters {
+                    reporter.on_match(&found);
+                }
+                self.emit(Event::Matched(found.clone()));
+                self.report.matches.push(found);
+            }
+        }
+        self.report.pages.push(page.clone());
+        if let Some(max) = self.options.max_duplicate_links {
+            if let Some(duplicates) = duplicate_links(&page, &base_url, document, max) {
+                if self.options.print_progress {
+                    warn!("{}", duplicates);
+                }
+                self.report.duplicate_links.push(duplicates);
+            }
+        }
+        let canonical = (canonical(&base_url, document)).filter(|_| self.options.audit_canonicals);
+        if let Some(canonical) = canonical {
+            self.canonicals.insert(page.clone(), canonical.clone());
+            // Given directly, so the link graph stays that of the page's links.
+            self.check_links(None, HashSet::from([canonical]))?;
+        }
+        let source = Source {
+            base_url: &base_url,
+            html,
+            document,
+            regions: &self.options.regions,
+        };
+        let (links, images, scripts) = (
+            self.options
+                .links
+                .then(|| Attribute::LINKS.extract(&source)),
+            self.options
+                .images
+                .then(|| Attribute::IMAGES.extract(&source)),
+            self.options
+                .scan_js
+                .then(|| Attribute::SCRIPTS.extract(&source)),
+        );
+        let extracted: Vec<HashSet<Url>> = (self.extractors.iter())
+            .map(|extractor| extractor.extract(&source))
+            .collect();
+        if let Some(links) = links {
+            self.check_links(Some(&page), links)?;
+        }
+        if let Some(images) = images {
+            self.check_links(Some(&page), images)?;
+        }
+        if let Some(scripts) = scripts {
+            let scripts: HashSet<Url> = scripts.into_iter().map(|url| self.rewrite(url)).collect();
+            self.scripts.extend(scripts.iter().cloned());
+            self.check_links(Some(&page), scripts)?;
+        }
+        for links in extracted {
+            self.check_links(Some(&page), links)?;
+        }
+        if self.options.budget.is_set() {
+            let resources: HashSet<Url> = (resources(&base_url, document).into_iter())
+                .map(|url| self.rewrite(url))
+                .collect();
+            self.resources.insert(page.clone(), resources.clone());
+            self.check_links(Some(&page), resources)?;
+        }
+        Ok(())
+    }
+
+    /// Checks `links`, found on `page` unless they were given directly.
+    fn check_links(&mut self, page: Option<&Url>, links: HashSet<Url>) -> Result<()> {
+        // In URL order, so listings and progress read the same from run to run.
+        let mut links: Vec<Url> = links.into_iter().collect();
+        links.sort();
+        for link in links {
+            let mut link = self.rewrite(link);
+            let script = self.scripts.contains(&link);
+            let follow = self.options.follow && self.is_internal(&link) && !script;
+            if follow || self.options.listing.as_ref().is_some_and(|l| l.normalize) {
+                link.set_fragment(None);
+            }
+            let by_host = self.options.check_mode != CheckMode::Http
+                && self.options.listing.is_none()
+                && matches!(link.scheme(), "http" | "https")
+                && (self.site.as_ref()).is_none_or(|site| site.resolve(&link).is_none());
+            if by_host && !follow && !script {
+                link = link.join("/").unwrap_or(link);
+            }
+            if self.excluded(link.as_str()) || self.skips_domain(&link) {
+                continue;
+            }
+            if self.options.listing.as_ref().is_some_and(|l| l.domains) {
+                // Every reference counts, not just the first sighting of a URL.
+                if let Some(host) = link.host_str().filter(|_| !self.is_internal(&link)) {
+                    *self.report.domains.entry(host.to_string()).or_default() += 1;
+                }
+                continue;
+            }
+            if let Some(page) = page {
+                (self.referrers.entry(link.clone()).or_default()).insert(page.clone());
+            }
+            if let Some(page) = page {
+                self.emit(Event::Linked {
+                    page: page.clone(),
+                    link: link.clone(),
+                });
+            }
+            if !self.viewed.insert(link.as_str())? {
+                continue;
+            }
+            self.report.discovered += 1;
+            self.check_expiry(page, &link);
+            #[cfg(feature = "render")]
+            if let Some(page) = page.filter(|_| self.options.screenshots.is_some()) {
+                self.linked_from.insert(link.clone(), page.clone());
+            }
+            if let Some(listing) = &self.options.listing {
+                let internal = self.is_internal(&link);
+                let wanted = match listing.only {
+                    Some(Scope::Internal) => internal,
+                    Some(Scope::External) => !internal,
+                    None => true,
+                };
+                if wanted {
+                    for reporter in &mut self.reporters {
+                        reporter.on_listed(&link);
+                    }
+                    self.emit(Event::Listed(link));
+                }
+                continue;
+            }
+            if self.options.dry_run {
+                self.report.planned.push(link.clone());
+            }
+            if script {
+                self.frontier.push_back(Job::Script(link));
+                continue;
+            }
+            if follow {
+                self.frontier.push_back(Job::Listed(link));
+                continue;
+            }
+            if self.options.dry_run {
+                continue;
+            }
+            let on_disk = (self.site.as_ref()).is_some_and(|site| site.resolve(&link).is_some());
+            if let Some(cached) = (self.cache.as_ref())
+                .filter(|_| !on_disk)
+                .filter(|_| self.options.check_mode == CheckMode::Http)
+                .filter(|_| self.capture.is_empty())
+                .filter(|_| !self.options.budget.is_set())
+                .filter(|_| !self.options.checksums.contains_key(&link))
+                .and_then(|cache| cache.get(&link))
+            {
+                self.record_result(cached);
+            } else {
+                self.schedule(link);
+            }
+        }
+        Ok(())
+    }
+
+    /// Records `link` when it is a signed URL that has expired or expires
+    /// within the run's window.
+    fn check_expiry(&mut self, page: Option<&Url>, link: &Url) {
+        let Some(window) = self.options.expires_within else {
+            return;
+        };
+        let Some(expires) = expiry(link) else {
+            return;
+        };
+        let now = Utc::now();
+        let window = chrono::TimeDelta::from_std(window).unwrap_or(chrono::TimeDelta::MAX);
+        if expires - now > window {
+            return;
+        }
+        let expiring = Expiring {
+            url: link.clone(),
+            page: page.cloned(),
+            expires,
+            expired: expires <= now,
+        };
+        if self.options.print_progress {
+            warn!("{}", expiring);
+        }
+        self.report.expiring.push(expiring);
+    }
+
+    /// Whether `url` is on the host of a start URL.
+    fn is_internal(&self, url: &Url) -> bool {
+        self.starts
+            .iter()
+            .any(|start| start.host_str() == url.host_str())
+    }
+
+    fn rewrite(&self, url: Url) -> Url {
+        match (url.host_str()).and_then(|host| self.options.rewrite_hosts.get(host)) {
+            Some(to) => rewrite_host(url.clone(), to),
+            None => url,
+        }
+    }
+
+    fn excluded(&self, url: &str) -> bool {
+        self.options
+            .exclude
+            .iter()
+            .any(|pattern| pattern.matches(url))
+    }
+
+    /// Whether `link` is to a skipped domain or, with a list of the only
+    /// ones to check, an external link to none of them.
+    fn skips_domain(&self, link: &Url) -> bool {
+        let Some(host) = link.host_str() else {
+            return false;
+        };
+        let within = |domains: &[String]| {
+            (domains.iter()).any(|domain| {
+                host.strip_suffix(domain.as_str())
+                    .is_some_and(|sub| sub.is_empty() || sub.ends_with('.'))
+            })
+        };
+        let only = &self.options.only_domains;
+        within(&self.options.skip_domains)
+            || (!only.is_empty() && !self.is_internal(link) && !within(only))
+    }
+
+    fn permits_for(&self, url: &Url) -> Permits {
+        Permits {
+            all: self.permits.clone(),
+            host: url
+                .host_str()
+                .and_then(|host| self.host_permits.get(host))
+                .cloned(),
+        }
+    }
+
+    /// Queues a check of `link`; once stopped it is only remembered as pending.
+    fn schedule(&mut self, link: Url) {
+        self.pending.insert(link.clone());
+        if self.stop.is_cancelled() {
+            return;
+        }
+        // Checked under the span of the page the link was found on.
+        self.queue.push(link, Span::current());
+        self.dispatch();
+    }
+
+    /// Starts queued checks, in the run's host order, while fewer than
+    /// `concurrency` are in flight.
+    fn dispatch(&mut self) {
+        while self.tasks.len() < self.options.concurrency.max(1) && !self.stop.is_cancelled() {
+            let Some((link, span)) = self.queue.pop() else {
+                break;
+            };
+            self.spawn_check(link, span);
+        }
+    }
+
+    fn spawn_check(&mut self, link: Url, span: Span) {
+        let permits = self.permits_for(&link);
+        let jitter = jitter(self.options.jitter);
+        let mode = self.options.check_mode;
+        let checker = (self.resource_checkers.iter())
+            .find(|checker| checker.handles(&link))
+            .cloned()
+            .or_else(|| {
+                (mode != CheckMode::Http)
+                    .then(|| Arc::new(HostChecker::new(mode)) as Arc<dyn ResourceChecker>)
+            });
+        if let Some(checker) = checker {
+            self.tasks.spawn(
+                async move {
+                    tokio::time::sleep(jitter).await;
+                    let _permits = permits.acquire().await;
+                    checker.check(link).await
+                }
+                .instrument(span),
+            );
+            self.update_progress();
+            return;
+        }
+        let http = HttpChecker::new(self.http.clone())
+            .with_capture(self.capture.clone())
+            .with_retry(self.options.retry.clone());
+        // A file answering 304 Not Modified can't be verified.
+        let digest = self.options.checksums.contains_key(&link);
+        let validators = (self.cache.as_ref())
+            .filter(|_| !digest && !self.options.budget.is_set())
+            .and_then(|cache| cache.validators(&link));
+        let body = match self.options.sample_bytes {
+            _ if digest => Body::Digest,
+            _ if self.over_bandwidth && self.options.over_bandwidth == OverBandwidth::Head => {
+                Body::Head
+            }
+            Some(bytes) => Body::Sample(bytes),
+            None => Body::Unread,
+        };
+        let down_hosts = self.down_hosts.clone();
+        self.tasks.spawn(
+            async move {
+                tokio::time::sleep(jitter).await;
+                let _permits = permits.acquire().await;
+                if is_down(&down_hosts, &link) {
+                    return CheckResult::failed(link, &HtmlFindError::HostDown);
+                }
+                http.request(link, validators, body).await
+            }
+            .instrument(span),
+        );
+        self.update_progress();
+    }
+
+    /// Counts the requests in a row to the host of `result` that got no
+    /// response, marking it down once `max_host_failures` have.
+    fn count_host_failure(&mut self, result: &CheckResult) {
+        let (Some(max), Some(server)) = (self.options.max_host_failures, server(&result.url))
+        else {
+            return;
+        };
+        if result.mode.is_some() || is_down(&self.down_hosts, &result.url) {
+            return;
+        }
+        let failures = self.host_failures.entry(server.clone()).or_default();
+        if result.status.is_some() || result.error.is_none() {
+            *failures = 0;
+            return;
+        }
+        *failures += 1;
+        if *failures >= max {
+            warn!(
+                "{} failed {} times in a row, skipping its remaining URLs",
+                server, failures
+            );
+            (self.down_hosts.lock().expect("down hosts lock")).insert(server);
+        }
+    }
+
+    /// Acts on `max_bandwidth` the first time the run has read that much.
+    fn check_bandwidth(&mut self) {
+        let Some(max) = self.options.max_bandwidth else {
+            return;
+        };
+        let downloaded = self.http.downloaded();
+        if self.over_bandwidth || downloaded < max {
+            return;
+        }
+        self.over_bandwidth = true;
+        match self.options.over_bandwidth {
+            OverBandwidth::Head => warn!(
+                "read {} of at most {} bytes, checking the remaining links with HEAD requests",
+                downloaded, max
+            ),
+            OverBandwidth::Stop => {
+                warn!(
+                    "read {} of at most {} bytes, stopping the run",
+                    downloaded, max
+                );
+                self.stop.cancel();
+            }
+        }
+    }
+
+    /// Waits for the scheduled checks. Once cancelled, in-flight checks get
+    /// `shutdown_grace` to finish before being aborted; past the deadline they
+    /// are aborted straight away.
+    async fn drain(&mut self) -> Result<()> {
+        loop {
+            self.flush_events().await;
+            tokio::select! {
+                biased;
+                _ = self.stop.cancelled() => break,
+                next = self.tasks.join_next() => match next {
+                    Some(joined) => self.record(joined),
+                    None => return Ok(()),
+                },
+                _ = tick(&mut self.checkpoints) => self.checkpoint()?,
+            }
+        }
+
+        let grace = if self.cancel.is_cancelled() {
+            self.options.shutdown_grace
+        } else {
+            Duration::ZERO
+        };
+        let _ = tokio::time::timeout(grace, async {
+            while let Some(joined) = self.tasks.join_next().await {
+                self.record(joined);
+            }
+        })
+        .await;
+        self.tasks.abort_all();
+        self.flush_events().await;
+        Ok(())
+    }
+
+    /// Records the outcome of a finished check task. A check that panicked
+    /// panics the run too, rather than leaving its link unaccounted for.
+    fn record(&mut self, joined: std::result::Result<CheckResult, tokio::task::JoinError>) {
+        match joined {
+            Ok(result) => self.record_result(result),
+            Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+            // Only aborted at shutdown, when the link stays pending.
+            Err(_) => {}
+        }
+        self.dispatch();
+    }
+
+    fn record_result(&mut self, mut result: CheckResult) {
+        self.check_status(&mut result);
+        self.check_bandwidth();
+        self.count_host_failure(&result);
+        if let Some(found_in) = self.found_in.get(&result.url) {
+            result.found_in = found_in.clone();
+        }
+        self.pending.remove(&result.url);
+        if result.is_broken() {
+            self.broken += 1;
+        }
+        debug!(
+            status = result.status.map(|status| status.as_u16()),
+            elapsed_ms = result.elapsed_ms,
+            cached = result.cached,
+            "checked {}",
+            result.url
+        );
+        let on_disk = self
+            .site
+            .as_ref()
+            .is_some_and(|site| site.resolve(&result.url).is_some());
+        if let Some(cache) = self.cache.as_mut().filter(|_| !on_disk) {
+            cache.insert(&result);
+        }
+        for reporter in &mut self.reporters {
+            reporter.on_result(&result);
+        }
+        if let Some(expected) = self.media.remove(&result.url) {
+            let served = (result.content_type.as_ref()).filter(|_| result.is_ok());
+            if let Some(content_type) = served.filter(|served| !expected.accepts(served)) {
+                self.record_issue(Issue {
+                    url: result.url.clone(),
+                    problem: Problem::WrongContentType {
+                        expected,
+                        content_type: content_type.clone(),
+                    },
+                });
+            }
+        }
+        self.verify_checksum(&result);
+        if self.options.print_progress && self.report.is_slow(&result) {
+            warn!(
+                "{} was slow, taking {} ms",
+                result.url,
+                result.elapsed_ms.unwrap_or_default()
+            );
+        }
+        if let Some(problem) = audit_caching(&result).filter(|_| self.options.audit_caching) {
+            let issue = CachingIssue {
+                url: result.url.clone(),
+                problem,
+            };
+            if self.options.print_progress {
+                warn!("{}", issue);
+            }
+            self.report.caching_issues.push(issue);
+        }
+        if let Some(hooks) = &mut self.hooks {
+            hooks.on_link_checked(&result);
+        }
+        self.emit(Event::Checked(Box::new(result.clone())));
+        self.report.results.push(result);
+        self.update_progress();
+    }
+
+    /// Marks `result` as expected when it answered with the status the run
+    /// expects of its URL, and records it when it answered otherwise.
+    fn check_status(&mut self, result: &mut CheckResult) {
+        let Some(expected) = (self.options.expected_statuses.iter())
+            .find(|expected| expected.applies_to(&result.url))
+        else {
+            return;
+        };
+        if result.status == Some(expected.status) {
+            result.expected = !result.is_ok();
+            return;
+        }
+        let got = match (&result.status, &result.error) {
+            (Some(status), _) => status.to_string(),
+            (None, Some(error)) => error.clone(),
+            (None, None) => "no response".to_string(),
+        };
+        let unmet = Unmet {
+            page: result.url.clone(),
+            expected: format!("status {}, not {}", expected.status, got),
+        };
+        if self.options.print_progress {
+            warn!("{}", unmet);
+        }
+        self.report.unmet.push(unmet);
+    }
+
+    /// Records a file that was served with another digest than expected, or
+    /// whose body couldn't be read to tell.
+    fn verify_checksum(&mut self, result: &CheckResult) {
+        let Some(expected) = self.options.checksums.get(&result.url) else {
+            return;
+        };
+        // A broken link is reported as such already.
+        if !result.is_ok() {
+            return;
+        }
+        let expected = match &result.sha256 {
+            Some(sha256) if sha256 == expected => return,
+            Some(sha256) => format!("SHA-256 {}, not {}", expected, sha256),
+            None => format!("SHA-256 {}, body not read", expected),
+        };
+        let unmet = Unmet {
+            page: result.url.clone(),
+            expected,
+        };
+        if self.options.print_progress {
+            warn!("{}", unmet);
+        }
+        self.report.unmet.push(unmet);
+    }
+
+    fn check_content(&mut self, page: &Url, html: &str) {
+        let mut expectations = self
+            .options
+            .expectations
+            .iter()
+            .filter(|expectation| expectation.applies_to(page))
+            .peekable();
+        if expectations.peek().is_none() {
+            return;
+        }
+        let document = scraper::Html::parse_document(html);
+        for expectation in expectations.filter(|expectation| !expectation.holds(&document)) {
+            let unmet = Unmet {
+                page: page.clone(),
+                expected: expectation.describe(),
+            };
+            if self.options.print_progress {
+                warn!("{}", unmet);
+            }
+            self.report.unmet.push(unmet);
+        }
+    }
+
+    fn update_progress(&self) {
+        if let Some(bar) = &self.progress {
+            bar.set_length(self.report.discovered as u64);
+            bar.set_position(self.report.results.len() as u64);
+            bar.set_message(format!(
+                "{} broken, {} in flight",
+                self.broken,
+                self.tasks.len() + self.fetching.len()
+            ));
+        }
+    }
+
+    fn finish(mut self) -> Report {
+        if let Some(bar) = &self.progress {
+            bar.finish_and_clear();
+        }
+        if self.options.coverage {
+            self.report.sitemap_coverage = Some(Coverage::new(&self.listed, &self.report));
+        }
+        if self.options.audit_canonicals {
+            self.report.canonical_issues = check_canonicals(
+                &self.canonicals,
+                &self.referrers,
+                &self.report.results,
+                &self.listed,
+            );
+        }
+        if self.options.page_rank {
+            self.report.page_rank = page_rank(&self.report.pages, &self.referrers);
+        }
+        if self.options.dead_ends || self.options.deep_after.is_some() {
+            let graph = LinkGraph::new(&self.report.pages, &self.referrers);
+            let dead_ends = match self.options.dead_ends {
+                true => graph.dead_ends(),
+                false => Vec::new(),
+            };
+            let deep_pages = (self.options.deep_after)
+                .map(|depth| graph.deep_pages(&self.starts, depth))
+                .unwrap_or_default();
+            (self.report.dead_ends, self.report.deep_pages) = (dead_ends, deep_pages);
+        }
+        self.report.stopped = if self.cancel.is_cancelled() {
+            Some(StopReason::Interrupted)
+        } else if self.over_bandwidth && self.options.over_bandwidth == OverBandwidth::Stop {
+            Some(StopReason::BandwidthExceeded)
+        } else if self.stop.is_cancelled() {
+            Some(StopReason::DeadlineReached)
+        } else {
+            None
+        };
+        for result in (self.report.results.iter_mut()).filter(|result| !result.is_ok()) {
+            if let Some(referrers) = self.referrers.remove(&result.url) {
+                result.linked_from = referrers.into_iter().collect();
+                result.linked_from.sort();
+            }
+        }
+        self.report
+    }
+}
+
+/// A random wait of up to `max` before a request, so they don't come in bursts.
+fn jitter(max: Option<Duration>) -> Duration {
+    max.map_or(Duration::ZERO, |max| {
+        Duration::from_millis(fastrand::u64(0..=max.as_millis() as u64))
+    })
+}
+
+/// The host and port `url` is requested from.
+fn server(url: &Url) -> Option<String> {
+    Some(format!(
+        "{}:{}",
+        url.host_str()?,
+        url.port_or_known_default()?
+    ))
+}
+
+/// Whether the server of `url` was given up on.
+fn is_down(down_hosts: &Mutex<HashSet<String>>, url: &Url) -> bool {
+    server(url)
+        .is_some_and(|server| (down_hosts.lock().expect("down hosts lock")).contains(&server))
+}
+
+/// The limits a request counts against.
+struct Permits {
+    all: Arc<Semaphore>,
+    host: Option<Arc<Semaphore>>,
+}
+
+impl Permits {
+    /// Waits for the host's permit before the run-wide one, so requests
+    /// queued for a busy host don't tie up permits others could use.
+    async fn acquire(self) -> (Option<OwnedSemaphorePermit>, Option<OwnedSemaphorePermit>) {
+        let host = match self.host {
+            Some(host) => host.acquire_owned().await.ok(),
+            None => None,
+        };
+        (host, self.all.acquire_owned().await.ok())
+    }
+}
+
+/// A file name for the `number`th screenshot, of `page`, that hints at which page it is.
+#[cfg(feature = "render")]
+fn screenshot_name(number: usize, page: &Url) -> String {
+    let slug: String = format!("{}{}", page.host_str().unwrap_or_default(), page.path())
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .take(80)
+        .collect();
+    format!("{:04}-{}.png", number, slug.trim_end_matches('-'))
+}
+
+/// Waits for the next checkpoint, or forever when checkpointing is off.
+async fn tick(interval: &mut Option<Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
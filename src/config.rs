@@ -0,0 +1,373 @@
+use crate::checker::HostOptions;
+use crate::email::Tls;
+use crate::error::{parse_url, HtmlFindError, Result};
+use crate::expect::{Expectation, ExpectedStatus};
+use base64::Engine;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::StatusCode;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use url::Url;
+
+/// Read from the working directory when no `--config` is given.
+pub const DEFAULT_CONFIG_FILE: &str = "html_find.toml";
+
+/// Defaults for a run, read from a TOML file. Command-line flags take precedence.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// Start URL, or a list of them.
+    #[serde(deserialize_with = "one_or_many")]
+    pub url: Vec<String>,
+    /// Resolve relative links against this URL, see `--base-url`.
+    pub base_url: Option<String>,
+    pub links: Option<bool>,
+    pub images: Option<bool>,
+    pub sitemap: Option<bool>,
+    /// CSS selector of the part of each page to check links in, see `--within`.
+    pub within: Option<String>,
+    /// CSS selector of parts of each page to skip, e.g. `"nav, footer"`.
+    pub exclude_selector: Option<String>,
+    /// Glob patterns of URLs to skip, e.g. `"https://twitter.com/*"`.
+    pub exclude: Vec<String>,
+    /// Domains whose links, like those to their subdomains, are never
+    /// checked, e.g. `["twitter.com", "linkedin.com"]`.
+    pub skip_domain: Vec<String>,
+    /// Domains external links are only checked to, with their subdomains.
+    pub only_domain: Vec<String>,
+    pub headers: BTreeMap<String, String>,
+    /// `user:password` sent to the hosts of the start URLs.
+    pub basic_auth: Option<String>,
+    /// Bearer token sent to the hosts of the start URLs.
+    pub token: Option<String>,
+    pub concurrency: Option<usize>,
+    #[serde(deserialize_with = "duration")]
+    pub max_duration: Option<Duration>,
+    /// Where to cache links that checked OK, see `--cache`; without it
+    /// nothing is cached.
+    pub cache_file: Option<PathBuf>,
+    #[serde(deserialize_with = "duration")]
+    pub cache_ttl: Option<Duration>,
+    /// Don't cache even though `cache_file` is set, e.g. in a profile.
+    pub no_cache: Option<bool>,
+    pub json: Option<PathBuf>,
+    pub db: Option<PathBuf>,
+    pub har: Option<PathBuf>,
+    pub warc: Option<PathBuf>,
+    /// URL a JSON summary is posted to after each run, see `--webhook`.
+    pub webhook: Option<String>,
+    /// SVG badge with the run's OK and broken counts.
+    pub badge: Option<PathBuf>,
+    /// Prometheus textfile the run's metrics are written to.
+    pub metrics_file: Option<PathBuf>,
+    /// Prometheus Pushgateway the run's metrics are pushed to.
+    pub pushgateway: Option<String>,
+    /// Addresses the report is mailed to after each run.
+    pub email_to: Vec<String>,
+    /// Server report emails are sent through.
+    pub smtp: SmtpConfig,
+    /// Hosts whose links are checked against another host, e.g.
+    /// `"prod.example.com" = "staging.example.com"`.
+    pub rewrite_host: BTreeMap<String, String>,
+    /// Overrides keyed by host name.
+    pub hosts: BTreeMap<String, HostConfig>,
+    /// Content pages must have, as `[[expect]]` tables.
+    pub expect: Vec<ExpectConfig>,
+    /// Statuses URLs must answer with, keyed by a glob of the URL or, when it
+    /// starts with `/`, of the path, e.g. `"/admin/*" = 401`.
+    pub expect_status: BTreeMap<String, u16>,
+    /// SHA-256 digests files must have, keyed by URL, e.g.
+    /// `"https://example.com/app.zip" = "9f86d0..."`. Those files are downloaded in full.
+    pub checksums: BTreeMap<String, String>,
+    /// Named sets of settings applied over the ones above with `--profile`.
+    pub profiles: BTreeMap<String, Config>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct HostConfig {
+    pub headers: BTreeMap<String, String>,
+    /// `user:password` sent to this host.
+    pub basic_auth: Option<String>,
+    pub token: Option<String>,
+    pub concurrency: Option<usize>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct SmtpConfig {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub tls: Option<Tls>,
+    /// Sender address, e.g. `"Link checker <ci@example.com>"`.
+    pub from: Option<String>,
+}
+
+/// Pages matching `url` must have an element matching `selector`, and
+/// `contains` in its text (or the page's, without a selector).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ExpectConfig {
+    /// Glob pattern of the page URLs to check.
+    pub url: String,
+    pub selector: Option<String>,
+    pub contains: Option<String>,
+}
+
+fn one_or_many<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(d)? {
+        OneOrMany::One(url) => vec![url],
+        OneOrMany::Many(urls) => urls,
+    })
+}
+
+/// Reads a duration such as `"10m"`.
+fn duration<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<Option<Duration>, D::Error> {
+    Option::<String>::deserialize(d)?
+        .map(|text| humantime::parse_duration(&text).map_err(D::Error::custom))
+        .transpose()
+}
+
+/// Builds a header map from name/value pairs.
+pub fn header_map<'a>(headers: impl IntoIterator<Item = (&'a str, &'a str)>) -> Result<HeaderMap> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        let invalid = || HtmlFindError::InvalidHeader {
+            header: name.to_string(),
+        };
+        let name = HeaderName::try_from(name).map_err(|_| invalid())?;
+        let value = HeaderValue::try_from(value).map_err(|_| invalid())?;
+        map.append(name, value);
+    }
+    Ok(map)
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Config> {
+        let text = std::fs::read_to_string(path).map_err(|source| HtmlFindError::File {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        toml::from_str(&text).map_err(|source| HtmlFindError::Config {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Loads [`DEFAULT_CONFIG_FILE`] if the working directory has one.
+    pub fn find() -> Result<Option<Config>> {
+        let path = Path::new(DEFAULT_CONFIG_FILE);
+        if path.is_file() {
+            Config::load(path).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// The settings with profile `name` applied over them. Lists of
+    /// excludes and expectations are combined; anything else the profile sets wins.
+    pub fn profile(mut self, name: &str) -> Result<Config> {
+        let profile = self
+            .profiles
+            .remove(name)
+            .ok_or_else(|| HtmlFindError::UnknownProfile {
+                name: name.to_string(),
+            })?;
+        if !profile.url.is_empty() {
+            self.url = profile.url;
+        }
+        if !profile.email_to.is_empty() {
+            self.email_to = profile.email_to;
+        }
+        let smtp = SmtpConfig {
+            host: profile.smtp.host.or(self.smtp.host.take()),
+            port: profile.smtp.port.or(self.smtp.port),
+            username: profile.smtp.username.or(self.smtp.username.take()),
+            password: profile.smtp.password.or(self.smtp.password.take()),
+            tls: profile.smtp.tls.or(self.smtp.tls),
+            from: profile.smtp.from.or(self.smtp.from.take()),
+        };
+        self.exclude.extend(profile.exclude);
+        self.skip_domain.extend(profile.skip_domain);
+        self.only_domain.extend(profile.only_domain);
+        self.headers.extend(profile.headers);
+        self.rewrite_host.extend(profile.rewrite_host);
+        self.expect.extend(profile.expect);
+        self.expect_status.extend(profile.expect_status);
+        self.checksums.extend(profile.checksums);
+        for (host, config) in profile.hosts {
+            let base = self.hosts.entry(host).or_default();
+            base.headers.extend(config.headers);
+            base.basic_auth = config.basic_auth.or(base.basic_auth.take());
+            base.token = config.token.or(base.token.take());
+            base.concurrency = config.concurrency.or(base.concurrency);
+        }
+        Ok(Config {
+            base_url: profile.base_url.or(self.base_url),
+            links: profile.links.or(self.links),
+            images: profile.images.or(self.images),
+            sitemap: profile.sitemap.or(self.sitemap),
+            within: profile.within.or(self.within),
+            exclude_selector: profile.exclude_selector.or(self.exclude_selector),
+            basic_auth: profile.basic_auth.or(self.basic_auth),
+            token: profile.token.or(self.token),
+            concurrency: profile.concurrency.or(self.concurrency),
+            max_duration: profile.max_duration.or(self.max_duration),
+            cache_file: profile.cache_file.or(self.cache_file),
+            cache_ttl: profile.cache_ttl.or(self.cache_ttl),
+            no_cache: profile.no_cache.or(self.no_cache),
+            json: profile.json.or(self.json),
+            db: profile.db.or(self.db),
+            har: profile.har.or(self.har),
+            warc: profile.warc.or(self.warc),
+            webhook: profile.webhook.or(self.webhook),
+            badge: profile.badge.or(self.badge),
+            metrics_file: profile.metrics_file.or(self.metrics_file),
+            pushgateway: profile.pushgateway.or(self.pushgateway),
+            smtp,
+            ..self
+        })
+    }
+
+    pub fn expectations(&self) -> Result<Vec<Expectation>> {
+        self.expect
+            .iter()
+            .map(|expect| {
+                Expectation::new(
+                    &expect.url,
+                    expect.selector.as_deref(),
+                    expect.contains.clone(),
+                )
+            })
+            .collect()
+    }
+
+    pub fn expected_statuses(&self) -> Result<Vec<ExpectedStatus>> {
+        (self.expect_status.iter())
+            .map(|(url, &status)| {
+                let status =
+                    StatusCode::from_u16(status).map_err(|_| HtmlFindError::InvalidStatus {
+                        url: url.clone(),
+                        status,
+                    })?;
+                ExpectedStatus::new(url, status)
+            })
+            .collect()
+    }
+
+    /// The expected digests, in lowercase hex, by URL.
+    pub fn checksums(&self) -> Result<HashMap<Url, String>> {
+        (self.checksums.iter())
+            .map(|(url, checksum)| {
+                let valid = checksum.len() == 64 && checksum.bytes().all(|b| b.is_ascii_hexdigit());
+                if !valid {
+                    return Err(HtmlFindError::InvalidChecksum {
+                        url: url.clone(),
+                        checksum: checksum.clone(),
+                    });
+                }
+                Ok((parse_url(url)?, checksum.to_ascii_lowercase()))
+            })
+            .collect()
+    }
+
+    pub fn headers(&self) -> Result<HeaderMap> {
+        header_map(self.headers.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+    }
+
+    /// Per-host options. `basic-auth` and `token` are added for the hosts
+    /// of `starts` that don't set their own.
+    pub fn hosts(&self, starts: &[Url]) -> Result<HashMap<String, HostOptions>> {
+        let mut hosts = self.hosts.clone();
+        if self.basic_auth.is_some() || self.token.is_some() {
+            for host in starts.iter().filter_map(Url::host_str) {
+                let config = hosts.entry(host.to_string()).or_default();
+                if config.basic_auth.is_none() && config.token.is_none() {
+                    config.basic_auth = self.basic_auth.clone();
+                    config.token = self.token.clone();
+                }
+            }
+        }
+        hosts
+            .into_iter()
+            .map(|(host, config)| {
+                let mut headers =
+                    header_map(config.headers.iter().map(|(k, v)| (k.as_str(), v.as_str())))?;
+                let authorization = match (&config.basic_auth, &config.token) {
+                    (Some(auth), _) => Some(format!(
+                        "Basic {}",
+                        base64::engine::general_purpose::STANDARD.encode(auth)
+                    )),
+                    (None, Some(token)) => Some(format!("Bearer {}", token)),
+                    (None, None) => None,
+                };
+                if let Some(authorization) = authorization {
+                    let mut value = HeaderValue::try_from(authorization).map_err(|_| {
+                        HtmlFindError::InvalidHeader {
+                            header: AUTHORIZATION.to_string(),
+                        }
+                    })?;
+                    value.set_sensitive(true);
+                    headers.insert(AUTHORIZATION, value);
+                }
+                let options = HostOptions {
+                    headers,
+                    concurrency: config.concurrency,
+                };
+                Ok((host.to_ascii_lowercase(), options))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_durations() {
+        let config: Config = toml::from_str(
+            r#"
+            url = "https://example.com/"
+            max-duration = "1h 30m"
+            cache-ttl = "90s"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.url, ["https://example.com/"]);
+        assert_eq!(config.max_duration, Some(Duration::from_secs(90 * 60)));
+        assert_eq!(config.cache_ttl, Some(Duration::from_secs(90)));
+        assert!(toml::from_str::<Config>("max-duration = \"soon\"").is_err());
+    }
+
+    #[test]
+    fn profiles_override_and_extend() {
+        let config: Config = toml::from_str(
+            r#"
+            url = ["https://example.com/", "https://example.org/"]
+            exclude = ["*/a"]
+            concurrency = 4
+            [profiles.ci]
+            exclude = ["*/b"]
+            concurrency = 16
+            "#,
+        )
+        .unwrap();
+        let ci = config.profile("ci").unwrap();
+        assert_eq!(ci.url.len(), 2);
+        assert_eq!(ci.exclude, ["*/a", "*/b"]);
+        assert_eq!(ci.concurrency, Some(16));
+        assert!(Config::default().profile("nightly").is_err());
+    }
+}
@@ -0,0 +1,256 @@
+use crate::diff::Baseline;
+use crate::error::{HtmlFindError, Result};
+use crate::report::{Report, StopReason};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use url::Url;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    start_url TEXT NOT NULL,
+    started_at INTEGER NOT NULL,
+    finished_at INTEGER NOT NULL,
+    stopped TEXT,
+    discovered INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS pages (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    url TEXT NOT NULL,
+    PRIMARY KEY (run_id, url)
+);
+CREATE TABLE IF NOT EXISTS resources (
+    id INTEGER PRIMARY KEY,
+    url TEXT NOT NULL UNIQUE
+);
+CREATE TABLE IF NOT EXISTS outcomes (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    resource_id INTEGER NOT NULL REFERENCES resources(id),
+    status INTEGER,
+    error TEXT,
+    ok INTEGER NOT NULL,
+    cached INTEGER NOT NULL,
+    blocked INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (run_id, resource_id)
+);
+CREATE INDEX IF NOT EXISTS outcomes_resource ON outcomes(resource_id);
+";
+
+/// History of runs in an SQLite database, one row per run, scanned page and
+/// checked resource.
+pub struct ResultsDb {
+    path: PathBuf,
+    conn: Connection,
+}
+
+/// Runs are matched up by their start URLs, stored space-separated.
+fn run_key(starts: &[Url]) -> String {
+    starts.iter().map(Url::as_str).collect::<Vec<_>>().join(" ")
+}
+
+fn unix_secs(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+impl ResultsDb {
+    /// Opens or creates the database at `path`.
+    pub fn open(path: &Path) -> Result<ResultsDb> {
+        let db_error = |source| HtmlFindError::Db {
+            path: path.to_path_buf(),
+            source,
+        };
+        let conn = Connection::open(path).map_err(db_error)?;
+        conn.execute_batch(SCHEMA).map_err(db_error)?;
+        // Databases from before blocked outcomes were told apart lack the column.
+        if conn
+            .prepare("SELECT blocked FROM outcomes LIMIT 0")
+            .is_err()
+        {
+            conn.execute_batch(
+                "ALTER TABLE outcomes ADD COLUMN blocked INTEGER NOT NULL DEFAULT 0",
+            )
+            .map_err(db_error)?;
+        }
+        Ok(ResultsDb {
+            path: path.to_path_buf(),
+            conn,
+        })
+    }
+
+    /// Stores `report` as a new run and returns its id.
+    pub fn record_run(
+        &mut self,
+        starts: &[Url],
+        started_at: SystemTime,
+        report: &Report,
+    ) -> Result<i64> {
+        let error = |source| HtmlFindError::Db {
+            path: self.path.clone(),
+            source,
+        };
+        let tx = self.conn.transaction().map_err(error)?;
+        let stopped = report.stopped.map(|reason| match reason {
+            StopReason::Interrupted => "interrupted",
+            StopReason::DeadlineReached => "deadline",
+            StopReason::BandwidthExceeded => "bandwidth",
+        });
+        tx.execute(
+            "INSERT INTO runs (start_url, started_at, finished_at, stopped, discovered)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                run_key(starts),
+                unix_secs(started_at),
+                unix_secs(SystemTime::now()),
+                stopped,
+                report.discovered as i64
+            ],
+        )
+        .map_err(error)?;
+        let run_id = tx.last_insert_rowid();
+
+        {
+            let mut page = tx
+                .prepare("INSERT OR IGNORE INTO pages (run_id, url) VALUES (?1, ?2)")
+                .map_err(error)?;
+            for url in &report.pages {
+                page.execute(params![run_id, url.as_str()]).map_err(error)?;
+            }
+
+            let mut resource = tx
+                .prepare("INSERT OR IGNORE INTO resources (url) VALUES (?1)")
+                .map_err(error)?;
+            let mut resource_id = tx
+                .prepare("SELECT id FROM resources WHERE url = ?1")
+                .map_err(error)?;
+            let mut outcome = tx
+                .prepare(
+                    "INSERT OR REPLACE INTO outcomes (run_id, resource_id, status, error, ok, cached, blocked)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                )
+                .map_err(error)?;
+            for result in &report.results {
+                resource.execute([result.url.as_str()]).map_err(error)?;
+                let id: i64 = resource_id
+                    .query_row([result.url.as_str()], |row| row.get(0))
+                    .map_err(error)?;
+                outcome
+                    .execute(params![
+                        run_id,
+                        id,
+                        result.status.map(|status| status.as_u16()),
+                        result.error,
+                        result.is_ok(),
+                        result.cached,
+                        result.blocked.is_some()
+                    ])
+                    .map_err(error)?;
+            }
+        }
+
+        tx.commit().map_err(error)?;
+        Ok(run_id)
+    }
+
+    /// Id of the most recent run from `starts`, if any.
+    pub fn latest_run(&self, starts: &[Url]) -> Result<Option<i64>> {
+        self.conn
+            .query_row(
+                "SELECT id FROM runs WHERE start_url = ?1 ORDER BY id DESC LIMIT 1",
+                [run_key(starts)],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|source| self.error(source))
+    }
+
+    /// Outcomes recorded for `run_id`.
+    pub fn baseline(&self, run_id: i64) -> Result<Baseline> {
+        let mut baseline = Baseline::default();
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT resources.url, outcomes.ok FROM outcomes
+                 JOIN resources ON resources.id = outcomes.resource_id
+                 WHERE outcomes.run_id = ?1",
+            )
+            .map_err(|source| self.error(source))?;
+        let rows = stmt
+            .query_map([run_id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|source| self.error(source))?;
+        for row in rows {
+            let (url, ok) = row.map_err(|source| self.error(source))?;
+            baseline.insert(url, ok);
+        }
+        Ok(baseline)
+    }
+
+    /// Broken counts of the `window` runs from `starts` before `run_id`, newest
+    /// first. Like [`Report::broken`], they leave out links bot protection blocked.
+    pub fn broken_history(&self, starts: &[Url], run_id: i64, window: usize) -> Result<Vec<u64>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT COUNT(outcomes.ok) FILTER (WHERE outcomes.ok = 0 AND outcomes.blocked = 0) FROM runs
+                 LEFT JOIN outcomes ON outcomes.run_id = runs.id
+                 WHERE runs.start_url = ?1 AND runs.id < ?2
+                 GROUP BY runs.id ORDER BY runs.id DESC LIMIT ?3",
+            )
+            .map_err(|source| self.error(source))?;
+        let counts = stmt
+            .query_map(params![run_key(starts), run_id, window as i64], |row| {
+                row.get::<_, i64>(0)
+            })
+            .and_then(|rows| rows.map(|count| count.map(|c| c as u64)).collect())
+            .map_err(|source| self.error(source));
+        counts
+    }
+
+    fn error(&self, source: rusqlite::Error) -> HtmlFindError {
+        HtmlFindError::Db {
+            path: self.path.clone(),
+            source,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::CheckResult;
+    use reqwest::StatusCode;
+
+    fn url(path: &str) -> Url {
+        Url::parse("https://example.com/")
+            .unwrap()
+            .join(path)
+            .unwrap()
+    }
+
+    #[test]
+    fn broken_history_leaves_out_blocked_links() {
+        let path =
+            std::env::temp_dir().join(format!("html_find_db_test_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut db = ResultsDb::open(&path).unwrap();
+        let starts = [url("/")];
+        let blocked = CheckResult {
+            blocked: Some("Cloudflare".to_string()),
+            ..CheckResult::new(url("/walled"), StatusCode::FORBIDDEN)
+        };
+        let report = Report {
+            results: vec![
+                CheckResult::new(url("/"), StatusCode::OK),
+                CheckResult::new(url("/gone"), StatusCode::NOT_FOUND),
+                blocked,
+            ],
+            ..Report::default()
+        };
+        let run = db.record_run(&starts, SystemTime::now(), &report).unwrap();
+        let history = db.broken_history(&starts, run + 1, 5).unwrap();
+        assert_eq!(history, [report.broken().count() as u64]);
+        assert_eq!(history, [1]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
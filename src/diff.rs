@@ -0,0 +1,68 @@
+use crate::report::{CheckResult, Report};
+use std::collections::HashMap;
+
+/// Whether each URL was OK in an earlier run.
+#[derive(Debug, Default)]
+pub struct Baseline {
+    ok: HashMap<String, bool>,
+}
+
+impl Baseline {
+    pub fn insert(&mut self, url: String, ok: bool) {
+        self.ok.insert(url, ok);
+    }
+
+    pub fn was_ok(&self, url: &str) -> Option<bool> {
+        self.ok.get(url).copied()
+    }
+}
+
+impl From<&Report> for Baseline {
+    fn from(report: &Report) -> Self {
+        let mut baseline = Baseline::default();
+        for result in &report.results {
+            baseline.insert(result.url.as_str().to_string(), result.is_ok());
+        }
+        baseline
+    }
+}
+
+/// What changed between a baseline and a new run.
+#[derive(Debug, Default)]
+pub struct Diff<'a> {
+    /// Broken now, but OK or unknown in the baseline.
+    pub newly_broken: Vec<&'a CheckResult>,
+    /// OK now, but broken in the baseline.
+    pub newly_fixed: Vec<&'a CheckResult>,
+}
+
+impl<'a> Diff<'a> {
+    pub fn new(baseline: &Baseline, report: &'a Report) -> Self {
+        let mut diff = Diff::default();
+        for result in &report.results {
+            let was_ok = baseline.was_ok(result.url.as_str());
+            match (was_ok, result.is_ok()) {
+                (Some(false), true) => diff.newly_fixed.push(result),
+                (Some(true) | None, false) => diff.newly_broken.push(result),
+                _ => {}
+            }
+        }
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.newly_broken.is_empty() && self.newly_fixed.is_empty()
+    }
+
+    pub fn print(&self) {
+        println!("Newly broken: {}", self.newly_broken.len());
+        let color = console::colors_enabled();
+        for result in &self.newly_broken {
+            println!("  {}", result.row(color));
+        }
+        println!("Newly fixed: {}", self.newly_fixed.len());
+        for result in &self.newly_fixed {
+            println!("  {}", result.row(color));
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use select::document::Document;
+use select::predicate::Name;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use url::Url;
+
+/// A page linking to the same URL over and over, or to itself, which most
+/// often means a template repeats a link it shouldn't.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateLinks {
+    pub page: Url,
+    /// The URLs linked more often than allowed, and how often each is.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repeated: Vec<(Url, usize)>,
+    /// How many links lead back to the page itself. Links to a part of
+    /// it, such as `#top`, aren't counted.
+    #[serde(default)]
+    pub self_links: usize,
+}
+
+impl fmt::Display for DuplicateLinks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut problems: Vec<String> = (self.repeated.iter())
+            .map(|(url, times)| format!("links to {} {} times", url, times))
+            .collect();
+        if self.self_links > 0 {
+            let plural = if self.self_links == 1 { "" } else { "s" };
+            problems.push(format!("has {} link{} to itself", self.self_links, plural));
+        }
+        write!(f, "{} {}", self.page, problems.join(", "))
+    }
+}
+
+/// Counts the `<a href>` links of `page`, resolved against `base_url`, and
+/// reports it when it links to one URL more than `max` times or to itself.
+pub fn duplicate_links(
+    page: &Url,
+    base_url: &Url,
+    document: &Document,
+    max: usize,
+) -> Option<DuplicateLinks> {
+    let base_parser = Url::options().base_url(Some(base_url));
+    let mut page = page.clone();
+    page.set_fragment(None);
+    let mut counts: BTreeMap<Url, usize> = BTreeMap::new();
+    let mut self_links = 0;
+    for link in (document.find(Name("a")))
+        .filter_map(|n| n.attr("href"))
+        .filter_map(|href| base_parser.parse(href).ok())
+    {
+        if link == page {
+            self_links += 1;
+        }
+        *counts.entry(link).or_default() += 1;
+    }
+    let mut repeated: Vec<(Url, usize)> = (counts.into_iter())
+        .filter(|(url, times)| *times > max && *url != page)
+        .collect();
+    repeated.sort_by_key(|(_, times)| std::cmp::Reverse(*times));
+    (!repeated.is_empty() || self_links > 0).then_some(DuplicateLinks {
+        page,
+        repeated,
+        self_links,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn reports_repeated_and_self_links() {
+        let page = url("https://example.com/docs/");
+        let document = Document::from(
+            r##"<a href="/docs/">Docs</a>
+            <a href="#top">Top</a>
+            <a href="a">A</a><a href="/docs/a">A</a><a href="https://example.com/docs/a">A</a>
+            <a href="b">B</a><a href="b">B</a>
+            <a>no href</a>"##,
+        );
+        let found = duplicate_links(&page, &page, &document, 2).unwrap();
+        assert_eq!(found.repeated, [(url("https://example.com/docs/a"), 3)]);
+        assert_eq!(found.self_links, 1);
+        assert_eq!(
+            found.to_string(),
+            "https://example.com/docs/ links to https://example.com/docs/a 3 times, \
+             has 1 link to itself"
+        );
+    }
+
+    #[test]
+    fn passes_pages_within_the_limit() {
+        let page = url("https://example.com/#intro");
+        let document = Document::from(r##"<a href="#top">Top</a><a href="/a">A</a>"##);
+        assert!(duplicate_links(&page, &page, &document, 1).is_none());
+    }
+}
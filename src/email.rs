@@ -0,0 +1,104 @@
+use crate::error::{HtmlFindError, Result};
+use crate::report::Report;
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Deserialize;
+use url::Url;
+
+/// How the connection to the SMTP server is secured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Tls {
+    /// Upgrade a plain connection with STARTTLS, usually on port 587.
+    #[default]
+    Starttls,
+    /// TLS from the start, usually on port 465.
+    Tls,
+    /// No encryption, e.g. for a relay on localhost.
+    None,
+}
+
+/// Format of the report attached to an email.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Csv,
+    Html,
+}
+
+/// Where and how report emails are sent.
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub host: String,
+    /// The default port of `tls` when not set.
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub tls: Tls,
+    pub from: String,
+    pub to: Vec<String>,
+    pub format: Format,
+}
+
+fn failed(err: impl ToString) -> HtmlFindError {
+    HtmlFindError::Email {
+        message: err.to_string(),
+    }
+}
+
+impl Email {
+    /// Mails the counts for a run from `urls`, with the full report attached.
+    pub async fn send(&self, urls: &[Url], report: &Report) -> Result<()> {
+        let broken = report.broken().count();
+        let starts: Vec<String> = urls.iter().map(Url::to_string).collect();
+        let subject = format!("{} broken links on {}", broken, starts.join(", "));
+        let text = format!(
+            "Checked {} URLs: {} OK, {} broken\n",
+            report.results.len(),
+            report.results.len() - broken,
+            broken
+        );
+        let attachment = match self.format {
+            Format::Csv => Attachment::new("report.csv".to_string()).body(
+                report.to_csv(),
+                ContentType::parse("text/csv").map_err(failed)?,
+            ),
+            Format::Html => Attachment::new("report.html".to_string())
+                .body(report.to_html(), ContentType::TEXT_HTML),
+        };
+
+        let mut message = Message::builder()
+            .from(self.from.parse::<Mailbox>().map_err(failed)?)
+            .subject(subject);
+        for to in &self.to {
+            message = message.to(to.parse::<Mailbox>().map_err(failed)?);
+        }
+        let message = message
+            .multipart(
+                MultiPart::mixed()
+                    .singlepart(SinglePart::plain(text))
+                    .singlepart(attachment),
+            )
+            .map_err(failed)?;
+
+        let mut transport = match self.tls {
+            Tls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.host),
+            Tls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&self.host),
+            Tls::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+                &self.host,
+            )),
+        }
+        .map_err(failed)?;
+        if let Some(port) = self.port {
+            transport = transport.port(port);
+        }
+        if let Some(username) = &self.username {
+            let password = self.password.clone().unwrap_or_default();
+            transport = transport.credentials(Credentials::new(username.clone(), password));
+        }
+        transport.build().send(message).await.map_err(failed)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+use crate::report::Failure;
+use html_find_core::page::InvalidSelector;
+use std::fmt;
+use std::path::PathBuf;
+use url::Url;
+
+/// Stage of a run in which an error occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    FetchPage,
+    FetchSitemap,
+    FetchScript,
+    CheckLink,
+    QueryArchive,
+    Notify,
+    PushMetrics,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Phase::FetchPage => "fetching page",
+            Phase::FetchSitemap => "fetching sitemap",
+            Phase::FetchScript => "fetching script",
+            Phase::CheckLink => "checking link",
+            Phase::QueryArchive => "looking up archived copies of",
+            Phase::Notify => "notifying",
+            Phase::PushMetrics => "pushing metrics to",
+        })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HtmlFindError {
+    #[error("{phase} {url} failed: {source}")]
+    Request {
+        url: Url,
+        phase: Phase,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    #[error("invalid URL {url:?}: {source}")]
+    InvalidUrl {
+        url: String,
+        #[source]
+        source: url::ParseError,
+    },
+
+    #[error("decompressing {url} failed: {source}")]
+    Decompress {
+        url: Url,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("{url} is not a valid sitemap: {message}")]
+    InvalidSitemap { url: Url, message: String },
+
+    #[error("found no sitemap for {url} in robots.txt or at /sitemap.xml or /sitemap_index.xml")]
+    NoSitemap { url: Url },
+
+    #[error("{url} has no host")]
+    MissingHost { url: Url },
+
+    #[error("skipped (host down)")]
+    HostDown,
+
+    #[error("resolving {host} failed: {source}")]
+    Resolve {
+        host: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("connecting to {host} port {port} failed: {source}")]
+    Connect {
+        host: String,
+        port: u16,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("{}: {source}", path.display())]
+    File {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("{}: {source}", path.display())]
+    Json {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("{}: {source}", path.display())]
+    Db {
+        path: PathBuf,
+        #[source]
+        source: rusqlite::Error,
+    },
+
+    #[error("invalid glob {pattern:?}: {source}")]
+    InvalidGlob {
+        pattern: String,
+        #[source]
+        source: glob::PatternError,
+    },
+
+    #[error("{}: {source}", path.display())]
+    Config {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+
+    #[error("invalid selector {selector:?}: {message}")]
+    InvalidSelector { selector: String, message: String },
+
+    #[error("parsing HTML failed: {message}")]
+    Parse { message: String },
+
+    #[error("no profile {name:?} in the config")]
+    UnknownProfile { name: String },
+
+    #[error("invalid header {header:?}")]
+    InvalidHeader { header: String },
+
+    #[error("invalid SHA-256 checksum {checksum:?} for {url}, expected 64 hex digits")]
+    InvalidChecksum { url: String, checksum: String },
+
+    #[error("invalid expected status {status} for {url:?}")]
+    InvalidStatus { url: String, status: u16 },
+
+    #[error("{}: {message}", path.display())]
+    InvalidCapture { path: PathBuf, message: String },
+
+    #[error("invalid job: {message}")]
+    InvalidRequest { message: String },
+
+    #[error("sending email failed: {message}")]
+    Email { message: String },
+
+    #[cfg(feature = "otlp")]
+    #[error("exporting traces failed: {message}")]
+    Telemetry { message: String },
+
+    #[cfg(feature = "render")]
+    #[error("rendering {url} failed: {message}")]
+    Render { url: Url, message: String },
+
+    #[cfg(feature = "render")]
+    #[error("starting Chrome failed: {message}")]
+    Browser { message: String },
+
+    #[error("building the HTTP client failed: {0}")]
+    Client(#[source] reqwest::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("check task failed: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+impl HtmlFindError {
+    /// What kind of failure this is, for a URL it kept from being checked.
+    pub(crate) fn failure(&self) -> Failure {
+        match self {
+            HtmlFindError::Request { source, .. } if source.is_timeout() => Failure::Timeout,
+            HtmlFindError::Request { source, .. } if source.is_connect() => {
+                // The connector reports failed lookups as connect errors too.
+                let mut cause = std::error::Error::source(source);
+                while let Some(err) = cause {
+                    if err.to_string().starts_with("dns error") {
+                        return Failure::Dns;
+                    }
+                    cause = err.source();
+                }
+                Failure::Connect
+            }
+            HtmlFindError::Resolve { source, .. } | HtmlFindError::Connect { source, .. }
+                if source.kind() == std::io::ErrorKind::TimedOut =>
+            {
+                Failure::Timeout
+            }
+            HtmlFindError::Resolve { .. } => Failure::Dns,
+            HtmlFindError::Connect { .. } => Failure::Connect,
+            _ => Failure::Other,
+        }
+    }
+
+    pub(crate) fn request(url: &Url, phase: Phase) -> impl FnOnce(reqwest::Error) -> Self + '_ {
+        move |source| HtmlFindError::Request {
+            url: url.clone(),
+            phase,
+            source,
+        }
+    }
+}
+
+impl From<InvalidSelector> for HtmlFindError {
+    fn from(InvalidSelector { selector, message }: InvalidSelector) -> Self {
+        HtmlFindError::InvalidSelector { selector, message }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, HtmlFindError>;
+
+/// Parses `input` as an absolute URL, keeping the offending string on failure.
+pub fn parse_url(input: &str) -> Result<Url> {
+    Url::parse(input).map_err(|source| HtmlFindError::InvalidUrl {
+        url: input.to_string(),
+        source,
+    })
+}
@@ -0,0 +1,113 @@
+use crate::error::{HtmlFindError, Result};
+use reqwest::StatusCode;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use url::Url;
+
+/// Content a page must have, beyond answering 200.
+#[derive(Debug, Clone)]
+pub struct Expectation {
+    /// Pages whose URL matches this are checked.
+    url: glob::Pattern,
+    /// Must match at least one element; kept with its source for messages.
+    selector: Option<(String, Selector)>,
+    /// Text the page, or an element `selector` matches, must contain.
+    contains: Option<String>,
+}
+
+/// The status URLs matching a glob must answer with, such as 401 for an
+/// admin area or 410 for removed pages. A glob starting with `/` matches the
+/// path, any other the whole URL.
+#[derive(Debug, Clone)]
+pub struct ExpectedStatus {
+    url: glob::Pattern,
+    pub status: StatusCode,
+}
+
+impl ExpectedStatus {
+    pub fn new(url: &str, status: StatusCode) -> Result<Self> {
+        let url = glob::Pattern::new(url).map_err(|source| HtmlFindError::InvalidGlob {
+            pattern: url.to_string(),
+            source,
+        })?;
+        Ok(ExpectedStatus { url, status })
+    }
+
+    pub fn applies_to(&self, url: &Url) -> bool {
+        if self.url.as_str().starts_with('/') {
+            self.url.matches(url.path())
+        } else {
+            self.url.matches(url.as_str())
+        }
+    }
+}
+
+/// A page that did not meet an [`Expectation`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Unmet {
+    pub page: Url,
+    pub expected: String,
+}
+
+impl fmt::Display for Unmet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (expected {})", self.page, self.expected)
+    }
+}
+
+impl Expectation {
+    /// Parses the URL glob and CSS selector of an expectation.
+    pub fn new(url: &str, selector: Option<&str>, contains: Option<String>) -> Result<Self> {
+        let url = glob::Pattern::new(url).map_err(|source| HtmlFindError::InvalidGlob {
+            pattern: url.to_string(),
+            source,
+        })?;
+        let selector = selector
+            .map(|selector| {
+                Selector::parse(selector)
+                    .map(|parsed| (selector.to_string(), parsed))
+                    .map_err(|err| HtmlFindError::InvalidSelector {
+                        selector: selector.to_string(),
+                        message: err.to_string(),
+                    })
+            })
+            .transpose()?;
+        Ok(Expectation {
+            url,
+            selector,
+            contains,
+        })
+    }
+
+    pub fn applies_to(&self, page: &Url) -> bool {
+        self.url.matches(page.as_str())
+    }
+
+    /// Whether `document` has the expected content.
+    pub fn holds(&self, document: &Html) -> bool {
+        let contains = |text: String| {
+            self.contains
+                .as_deref()
+                .is_none_or(|needle| text.contains(needle))
+        };
+        match &self.selector {
+            Some((_, selector)) => document
+                .select(selector)
+                .any(|element| contains(element.text().collect())),
+            None => contains(document.root_element().text().collect()),
+        }
+    }
+
+    /// What the expectation asks for, e.g. `"h1" containing "Welcome"`.
+    pub fn describe(&self) -> String {
+        let target = match &self.selector {
+            Some((selector, _)) => format!("{:?}", selector),
+            None => "page".to_string(),
+        };
+        match &self.contains {
+            Some(text) => format!("{} containing {:?}", target, text),
+            None => format!("{} to match", target),
+        }
+    }
+}
@@ -0,0 +1,257 @@
+use crate::error::{HtmlFindError, Phase, Result};
+use crate::http::Http;
+use crate::page::get_text;
+use scraper::{ElementRef, Html, Selector};
+use skyscraper::xpath::grammar::data_model::{Node, XpathItem};
+use skyscraper::xpath::grammar::{NonTreeXpathNode, XpathItemTreeNode, XpathItemTreeNodeData};
+use skyscraper::xpath::{Xpath, XpathItemTree};
+use std::fmt::{self, Write};
+use url::Url;
+
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// What to take from each matching element.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Extract {
+    /// The element's outer HTML.
+    #[default]
+    Html,
+    /// Its text content with whitespace collapsed.
+    Text,
+    /// The value of an attribute; elements without it are skipped.
+    Attr(String),
+}
+
+/// A condition on the number of matches, e.g. for checking a page in CI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assertion {
+    Exists,
+    Absent,
+    Count(usize),
+}
+
+impl Assertion {
+    pub fn holds(&self, matches: usize) -> bool {
+        match self {
+            Assertion::Exists => matches > 0,
+            Assertion::Absent => matches == 0,
+            Assertion::Count(count) => matches == *count,
+        }
+    }
+}
+
+impl fmt::Display for Assertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Assertion::Exists => f.write_str("at least one match"),
+            Assertion::Absent => f.write_str("no matches"),
+            Assertion::Count(1) => f.write_str("exactly 1 match"),
+            Assertion::Count(count) => write!(f, "exactly {} matches", count),
+        }
+    }
+}
+
+/// A CSS selector or an XPath expression.
+pub enum Query {
+    Css(Selector),
+    Xpath(Xpath),
+}
+
+fn invalid(query: &str, message: impl ToString) -> HtmlFindError {
+    HtmlFindError::InvalidSelector {
+        selector: query.to_string(),
+        message: message.to_string(),
+    }
+}
+
+pub(crate) fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Writes `node` and its descendants back out as HTML.
+fn outer_html(node: &XpathItemTreeNode, tree: &XpathItemTree, out: &mut String) {
+    match node.data {
+        XpathItemTreeNodeData::ElementNode(element) => {
+            let _ = write!(out, "<{}", element.name);
+            for attribute in &element.attributes {
+                let _ = write!(out, " {}", attribute);
+            }
+            out.push('>');
+            for child in node.children(tree) {
+                outer_html(&child, tree, out);
+            }
+            if !VOID_ELEMENTS.contains(&element.name.as_str()) {
+                let _ = write!(out, "</{}>", element.name);
+            }
+        }
+        XpathItemTreeNodeData::TextNode(text) => out.push_str(&text.content),
+        XpathItemTreeNodeData::DocumentNode(_) => {
+            for child in node.children(tree) {
+                outer_html(&child, tree, out);
+            }
+        }
+        _ => {
+            let _ = write!(out, "{}", node);
+        }
+    }
+}
+
+impl Query {
+    /// Parses a CSS selector such as `article h2 a`.
+    pub fn css(selector: &str) -> Result<Query> {
+        Selector::parse(selector)
+            .map(Query::Css)
+            .map_err(|err| invalid(selector, err))
+    }
+
+    /// Parses an XPath expression such as `//a[contains(text(), 'Next')]`.
+    pub fn xpath(expression: &str) -> Result<Query> {
+        skyscraper::xpath::parse(expression)
+            .map(Query::Xpath)
+            .map_err(|err| invalid(expression, err))
+    }
+}
+
+impl Extract {
+    fn apply(&self, element: ElementRef) -> Option<String> {
+        match self {
+            Extract::Html => Some(element.html()),
+            Extract::Text => Some(collapse_whitespace(&element.text().collect::<String>())),
+            Extract::Attr(name) => element.value().attr(name).map(str::to_string),
+        }
+    }
+
+    /// Attribute nodes give their value and atomic results are printed as
+    /// they are, except when an attribute is wanted.
+    fn apply_xpath(&self, item: &XpathItem, tree: &XpathItemTree) -> Option<String> {
+        let wants_attr = matches!(self, Extract::Attr(_));
+        match item {
+            XpathItem::Node(Node::TreeNode(node)) => match (node.data, self) {
+                (XpathItemTreeNodeData::ElementNode(element), Extract::Attr(name)) => {
+                    element.get_attribute(name).map(str::to_string)
+                }
+                (_, Extract::Attr(_)) => None,
+                (_, Extract::Text) => Some(collapse_whitespace(&node.all_text(tree))),
+                (_, Extract::Html) => {
+                    let mut html = String::new();
+                    outer_html(node, tree, &mut html);
+                    Some(html)
+                }
+            },
+            XpathItem::Node(Node::NonTreeNode(NonTreeXpathNode::AttributeNode(attribute))) => {
+                (!wants_attr).then(|| attribute.value.clone())
+            }
+            XpathItem::AnyAtomicType(value) => (!wants_attr).then(|| value.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Takes `extract` from each match of `query` in `html`, in document order.
+pub fn select(html: &str, query: &Query, extract: &Extract) -> Result<Vec<String>> {
+    match query {
+        Query::Css(selector) => Ok(Html::parse_document(html)
+            .select(selector)
+            .filter_map(|element| extract.apply(element))
+            .collect()),
+        Query::Xpath(xpath) => {
+            let document = skyscraper::html::parse(html).map_err(|err| HtmlFindError::Parse {
+                message: err.to_string(),
+            })?;
+            let tree = XpathItemTree::from(&document);
+            let items = xpath
+                .apply(&tree)
+                .map_err(|err| invalid(&xpath.to_string(), err))?;
+            Ok(items
+                .iter()
+                .filter_map(|item| extract.apply_xpath(item, &tree))
+                .collect())
+        }
+    }
+}
+
+/// Fetches `url` and selects from it.
+pub async fn find(http: &Http, url: &Url, query: &Query, extract: &Extract) -> Result<Vec<String>> {
+    let html = get_text(http, url, Phase::FetchPage).await?;
+    select(&html, query, extract)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<html><body>
+        <article><h2><a href="/one">First   post</a></h2><img src="/one.png"></article>
+        <article><h2><a href="/two">Second post</a></h2></article>
+        <p><a>no href</a></p>
+    </body></html>"#;
+
+    #[test]
+    fn css_selects_html_text_and_attributes() {
+        let query = Query::css("article h2 a").unwrap();
+        assert_eq!(
+            select(PAGE, &query, &Extract::Html).unwrap(),
+            [
+                r#"<a href="/one">First   post</a>"#,
+                r#"<a href="/two">Second post</a>"#
+            ]
+        );
+        assert_eq!(
+            select(PAGE, &query, &Extract::Text).unwrap(),
+            ["First post", "Second post"]
+        );
+        let hrefs = select(
+            PAGE,
+            &Query::css("a").unwrap(),
+            &Extract::Attr("href".into()),
+        );
+        assert_eq!(hrefs.unwrap(), ["/one", "/two"]);
+    }
+
+    #[test]
+    fn css_rejects_invalid_selectors() {
+        let err = Query::css("article[").err().unwrap();
+        assert!(
+            matches!(err, HtmlFindError::InvalidSelector { selector, .. } if selector == "article[")
+        );
+    }
+
+    #[test]
+    fn xpath_selects_elements_attributes_and_values() {
+        let links = Query::xpath("//article//a[contains(text(), 'Second')]").unwrap();
+        assert_eq!(
+            select(PAGE, &links, &Extract::Html).unwrap(),
+            [r#"<a href="/two">Second post</a>"#]
+        );
+        assert_eq!(
+            select(PAGE, &links, &Extract::Text).unwrap(),
+            ["Second post"]
+        );
+        let hrefs = Query::xpath("//a").unwrap();
+        let hrefs = select(PAGE, &hrefs, &Extract::Attr("href".into())).unwrap();
+        assert_eq!(hrefs, ["/one", "/two"]);
+        let images = Query::xpath("//img/@src").unwrap();
+        assert_eq!(select(PAGE, &images, &Extract::Html).unwrap(), ["/one.png"]);
+        assert!(select(PAGE, &images, &Extract::Attr("src".into()))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn xpath_rejects_invalid_expressions() {
+        let err = Query::xpath("(").err().unwrap();
+        assert!(matches!(err, HtmlFindError::InvalidSelector { selector, .. } if selector == "("));
+    }
+
+    #[test]
+    fn assertions_hold_on_counts() {
+        assert!(Assertion::Exists.holds(2) && !Assertion::Exists.holds(0));
+        assert!(Assertion::Absent.holds(0) && !Assertion::Absent.holds(1));
+        assert!(Assertion::Count(2).holds(2) && !Assertion::Count(2).holds(3));
+        assert_eq!(Assertion::Count(1).to_string(), "exactly 1 match");
+        assert_eq!(Assertion::Count(3).to_string(), "exactly 3 matches");
+    }
+}
@@ -0,0 +1,285 @@
+use crate::error::{HtmlFindError, Result};
+use crate::report::Report;
+use pulldown_cmark::{Event, Parser, Tag};
+use reqwest::header::LOCATION;
+use reqwest::{Client, StatusCode};
+use scraper::{Html, Selector};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// Most hops followed when resolving a chain of permanent redirects.
+const MAX_HOPS: usize = 10;
+
+/// A local file with its links replaced.
+#[derive(Debug)]
+pub struct FileFix {
+    pub path: PathBuf,
+    before: String,
+    after: String,
+}
+
+/// Where each link of `report` that redirected now permanently lives: the
+/// end of its chain of 301 and 308 responses, asked again with `client`,
+/// which must not follow redirects itself.
+pub async fn permanent_redirects(client: &Client, report: &Report) -> HashMap<Url, Url> {
+    let mut moved = HashMap::new();
+    let redirected = (report.results.iter()).filter(|result| result.redirected_to.is_some());
+    for result in redirected {
+        if let Some(target) = permanent_target(client, &result.url).await {
+            moved.insert(result.url.clone(), target);
+        }
+    }
+    moved
+}
+
+async fn permanent_target(client: &Client, url: &Url) -> Option<Url> {
+    let mut at = url.clone();
+    for _ in 0..MAX_HOPS {
+        let res = client.get(at.as_str()).send().await.ok()?;
+        if !matches!(
+            res.status(),
+            StatusCode::MOVED_PERMANENTLY | StatusCode::PERMANENT_REDIRECT
+        ) {
+            break;
+        }
+        let location = res.headers().get(LOCATION)?.to_str().ok()?;
+        at = at.join(location).ok()?;
+    }
+    (at != *url).then_some(at)
+}
+
+/// The archived copy of each broken link of `report` that was looked up.
+pub fn archived(report: &Report) -> HashMap<Url, Url> {
+    (report.broken())
+        .filter_map(|result| Some((result.url.clone(), result.archived.clone()?)))
+        .collect()
+}
+
+/// The changes `replacements` make to the files of `pages`, given by their
+/// URL and path. Links are replaced where they are written, keeping their
+/// fragment; files left as they were are omitted.
+pub fn plan(pages: &[(Url, PathBuf)], replacements: &HashMap<Url, Url>) -> Result<Vec<FileFix>> {
+    let mut fixes = vec![];
+    for (url, path) in pages {
+        let before = std::fs::read_to_string(path).map_err(|source| HtmlFindError::File {
+            path: path.clone(),
+            source,
+        })?;
+        let mut after = before.clone();
+        let links: BTreeMap<String, Url> = written_links(url, path, &before).into_iter().collect();
+        for (written, link) in links {
+            let mut unfragmented = link.clone();
+            unfragmented.set_fragment(None);
+            let Some(mut target) = (replacements.get(&link))
+                .or_else(|| replacements.get(&unfragmented))
+                .cloned()
+            else {
+                continue;
+            };
+            if target.fragment().is_none() {
+                target.set_fragment(link.fragment());
+            }
+            after = replace_delimited(&after, &written, target.as_str());
+        }
+        if after != before {
+            fixes.push(FileFix {
+                path: path.clone(),
+                before,
+                after,
+            });
+        }
+    }
+    Ok(fixes)
+}
+
+/// The link targets of the page at `url`, as written in its source and resolved.
+fn written_links(url: &Url, path: &Path, source: &str) -> Vec<(String, Url)> {
+    let mut written = vec![];
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown"))
+    {
+        for event in Parser::new(source) {
+            match event {
+                Event::Start(Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. }) => {
+                    written.push(dest_url.to_string());
+                }
+                Event::Html(html) | Event::InlineHtml(html) => {
+                    written.extend(html_links(&Html::parse_fragment(&html)));
+                }
+                _ => {}
+            }
+        }
+        return (written.into_iter())
+            .filter_map(|link| Some((link.clone(), url.join(&link).ok()?)))
+            .collect();
+    }
+    let document = Html::parse_document(source);
+    let base = Selector::parse("base[href]").expect("selector is valid");
+    let base = (document.select(&base).next())
+        .and_then(|base| url.join(base.value().attr("href")?).ok())
+        .unwrap_or_else(|| url.clone());
+    (html_links(&document).into_iter())
+        .filter_map(|link| Some((link.clone(), base.join(&link).ok()?)))
+        .collect()
+}
+
+/// The `href` of each `<a>` and `src` of each `<img>` in `document`.
+fn html_links(document: &Html) -> Vec<String> {
+    let selector = Selector::parse("a[href], img[src]").expect("selector is valid");
+    (document.select(&selector))
+        .filter_map(|element| {
+            let element = element.value();
+            element.attr("href").or(element.attr("src"))
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+/// `text` with each whole occurrence of `from`, as an attribute value or
+/// Markdown destination would be delimited, replaced by `to`.
+fn replace_delimited(text: &str, from: &str, to: &str) -> String {
+    let opens = |head: &str| {
+        let mut before = head.chars().rev();
+        match before.next() {
+            Some('"' | '\'' | '(' | '=') => true,
+            // A `(<destination>)` or an autolink, which is absolute, but not a
+            // tag such as `</a>` that a relative link happens to spell.
+            Some('<') => before.next() == Some('(') || from.contains(':'),
+            _ => false,
+        }
+    };
+    let closes = |c: char| matches!(c, '"' | '\'' | ')' | '>') || c.is_whitespace();
+    let mut replaced = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find(from) {
+        let (head, tail) = rest.split_at(at);
+        let after = &tail[from.len()..];
+        let whole = opens(head) && after.chars().next().is_none_or(closes);
+        replaced.push_str(head);
+        replaced.push_str(if whole { to } else { from });
+        rest = after;
+    }
+    replaced.push_str(rest);
+    replaced
+}
+
+impl FileFix {
+    /// The changed lines, before and after, in the style of a unified diff.
+    pub fn diff(&self) -> String {
+        let mut diff = format!("--- {path}\n+++ {path}\n", path = self.path.display());
+        // Replacing links never adds or removes lines.
+        let lines = self.before.lines().zip(self.after.lines()).enumerate();
+        for (number, (before, after)) in lines.filter(|(_, (before, after))| before != after) {
+            diff.push_str(&format!(
+                "@@ -{line} +{line} @@\n-{}\n+{}\n",
+                before,
+                after,
+                line = number + 1
+            ));
+        }
+        diff
+    }
+
+    pub fn write(&self) -> Result<()> {
+        std::fs::write(&self.path, &self.after).map_err(|source| HtmlFindError::File {
+            path: self.path.clone(),
+            source,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(path: &str) -> Url {
+        Url::parse("https://example.com/")
+            .unwrap()
+            .join(path)
+            .unwrap()
+    }
+
+    fn page(name: &str, contents: &str) -> (Url, PathBuf) {
+        let path =
+            std::env::temp_dir().join(format!("html_find_fix_{}_{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        (url("/docs/"), path)
+    }
+
+    #[test]
+    fn replace_delimited_leaves_longer_links_and_tags_alone() {
+        assert_eq!(
+            replace_delimited(r#"<a href="/a">/a</a> <a href="/a/b">"#, "/a", "/new"),
+            r#"<a href="/new">/a</a> <a href="/a/b">"#
+        );
+        assert_eq!(
+            replace_delimited("[A](/a) [B](</a>)", "/a", "/new"),
+            "[A](/new) [B](</new>)"
+        );
+        assert_eq!(
+            replace_delimited(r#"<a href="a">x</a>"#, "a", "b"),
+            r#"<a href="b">x</a>"#
+        );
+        assert_eq!(
+            replace_delimited(
+                "<https://example.com/a>",
+                "https://example.com/a",
+                "https://example.com/b"
+            ),
+            "<https://example.com/b>"
+        );
+    }
+
+    #[test]
+    fn plan_replaces_links_as_written_keeping_fragments() {
+        let (at, path) = page(
+            "page.html",
+            "<base href=\"/\">\n<a href=\"old#intro\">Old</a>\n<img src=\"gone.png\">\n<a href=\"kept\">Kept</a>\n",
+        );
+        let replacements = HashMap::from([
+            (url("/old"), url("/new")),
+            (
+                url("/gone.png"),
+                url("https://web.archive.org/web/2020/https://example.com/gone.png"),
+            ),
+        ]);
+        let fixes = plan(&[(at, path.clone())], &replacements).unwrap();
+        assert_eq!(fixes.len(), 1);
+        assert_eq!(
+            fixes[0].diff(),
+            format!(
+                "--- {path}\n+++ {path}\n\
+                 @@ -2 +2 @@\n-<a href=\"old#intro\">Old</a>\n+<a href=\"https://example.com/new#intro\">Old</a>\n\
+                 @@ -3 +3 @@\n-<img src=\"gone.png\">\n\
+                 +<img src=\"https://web.archive.org/web/2020/https://example.com/gone.png\">\n",
+                path = path.display()
+            )
+        );
+        fixes[0].write().unwrap();
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .contains("example.com/new#intro"));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn plan_reads_markdown_destinations_and_skips_unchanged_files() {
+        let (at, path) = page(
+            "page.md",
+            "See [the guide](guide) and <a href=\"guide\">it</a>.\n",
+        );
+        let replacements = HashMap::from([(url("/docs/guide"), url("/docs/guide/"))]);
+        let fixes = plan(&[(at.clone(), path.clone())], &replacements).unwrap();
+        assert_eq!(
+            fixes[0].after,
+            "See [the guide](https://example.com/docs/guide/) and \
+             <a href=\"https://example.com/docs/guide/\">it</a>.\n"
+        );
+        assert!(plan(&[(at, path.clone())], &HashMap::new())
+            .unwrap()
+            .is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,175 @@
+use base64::Engine;
+use futures::StreamExt;
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH, RANGE,
+};
+use reqwest::{Body, Client, Request, Response, ResponseBuilderExt, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+use url::Url;
+
+/// Responses kept on disk, one file per request, so runs can be repeated
+/// offline with the same outcome. Requests are told apart by their method,
+/// URL, and any `Range`, `If-None-Match` and `If-Modified-Since` they send.
+#[derive(Debug, Clone)]
+pub enum Fixtures {
+    /// Makes requests as usual, writing each response to the directory with
+    /// as much of its body as was read, none for a `HEAD` request or one
+    /// whose body was left unread.
+    Record(PathBuf),
+    /// Answers requests from the directory without sending them. Requests
+    /// never recorded, or that failed when they were, get 404 Not Found.
+    Replay(PathBuf),
+}
+
+/// A response as written to disk.
+#[derive(Debug, Serialize, Deserialize)]
+struct Recorded {
+    method: String,
+    url: Url,
+    /// Where the request ended up after redirects.
+    final_url: Url,
+    status: u16,
+    headers: Vec<(String, String)>,
+    /// Base64, as bodies needn't be text. Only what was read of it when
+    /// recorded, so it may be cut short.
+    body: String,
+}
+
+impl Fixtures {
+    /// Sends `request` with `client`, or answers it from disk.
+    pub(crate) async fn send(
+        &self,
+        client: &Client,
+        request: Request,
+    ) -> reqwest::Result<Response> {
+        match self {
+            Fixtures::Record(dir) => {
+                let file = fixture_file(dir, &request);
+                let (method, url) = (request.method().to_string(), request.url().clone());
+                let res = client.execute(request).await?;
+                let (final_url, status) = (res.url().clone(), res.status());
+                let headers = res.headers().clone();
+                let mut recording = Recording {
+                    file,
+                    recorded: Recorded {
+                        method,
+                        url,
+                        final_url: final_url.clone(),
+                        status: status.as_u16(),
+                        headers: (headers.iter())
+                            .filter_map(|(name, value)| {
+                                Some((name.to_string(), value.to_str().ok()?.to_string()))
+                            })
+                            .collect(),
+                        body: String::new(),
+                    },
+                    body: Vec::new(),
+                };
+                // Written once the caller is done with the body, however much of it they read.
+                let body = res.bytes_stream().map(move |chunk| {
+                    if let Ok(chunk) = &chunk {
+                        recording.body.extend_from_slice(chunk);
+                    }
+                    chunk
+                });
+                Ok(response(
+                    &final_url,
+                    status,
+                    headers,
+                    Body::wrap_stream(body),
+                ))
+            }
+            Fixtures::Replay(dir) => {
+                let file = fixture_file(dir, &request);
+                let recorded = (std::fs::read(&file).ok())
+                    .and_then(|json| serde_json::from_slice::<Recorded>(&json).ok());
+                let Some(recorded) = recorded else {
+                    warn!(
+                        "{} {} has no recorded response",
+                        request.method(),
+                        request.url()
+                    );
+                    let headers = HeaderMap::new();
+                    return Ok(response(
+                        request.url(),
+                        StatusCode::NOT_FOUND,
+                        headers,
+                        vec![],
+                    ));
+                };
+                let headers = (recorded.headers.iter())
+                    .filter_map(|(name, value)| {
+                        let name = HeaderName::from_bytes(name.as_bytes()).ok()?;
+                        Some((name, HeaderValue::from_str(value).ok()?))
+                    })
+                    .collect();
+                let status = StatusCode::from_u16(recorded.status).unwrap_or(StatusCode::NOT_FOUND);
+                let body = (base64::engine::general_purpose::STANDARD)
+                    .decode(&recorded.body)
+                    .unwrap_or_default();
+                Ok(response(&recorded.final_url, status, headers, body))
+            }
+        }
+    }
+}
+
+/// The file a response to `request` is kept in, named after its method,
+/// URL, and the range and validators it sends, if any.
+fn fixture_file(dir: &Path, request: &Request) -> PathBuf {
+    let mut key = Sha256::new();
+    key.update(request.method().as_str());
+    key.update(" ");
+    key.update(request.url().as_str());
+    for name in [RANGE, IF_NONE_MATCH, IF_MODIFIED_SINCE] {
+        if let Some(value) = request.headers().get(&name) {
+            key.update(" ");
+            key.update(name.as_str());
+            key.update(": ");
+            key.update(value.as_bytes());
+        }
+    }
+    let name: String = (key.finalize().iter())
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    dir.join(format!("{}.json", name))
+}
+
+/// A response being recorded, written to its file when dropped with the
+/// part of the body read by then.
+struct Recording {
+    file: PathBuf,
+    recorded: Recorded,
+    body: Vec<u8>,
+}
+
+impl Drop for Recording {
+    fn drop(&mut self) {
+        self.recorded.body = base64::engine::general_purpose::STANDARD.encode(&self.body);
+        if let Err(err) = write(&self.file, &self.recorded) {
+            warn!("recording {} failed: {}", self.file.display(), err);
+        }
+    }
+}
+
+fn write(file: &Path, recorded: &Recorded) -> std::io::Result<()> {
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_vec_pretty(recorded).expect("fixtures serialize");
+    std::fs::write(file, json)
+}
+
+fn response(url: &Url, status: StatusCode, headers: HeaderMap, body: impl Into<Body>) -> Response {
+    let mut builder = http::Response::builder().status(status).url(url.clone());
+    if let Some(all) = builder.headers_mut() {
+        all.extend(headers);
+    }
+    Response::from(
+        builder
+            .body(body)
+            .expect("a response from its parts builds"),
+    )
+}
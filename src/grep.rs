@@ -0,0 +1,101 @@
+use regex::Regex;
+use scraper::{Html, Node};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use url::Url;
+
+/// Characters of context kept on either side of a match in a long line.
+const CONTEXT: usize = 60;
+
+/// A regular expression searched for in every fetched page.
+#[derive(Debug, Clone)]
+pub struct Grep {
+    pub regex: Regex,
+    /// Search the text a reader would see instead of the HTML source.
+    pub text: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrepMatch {
+    pub page: Url,
+    /// Line in the HTML source; not known when searching text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// The matching line, shortened around the match when long.
+    pub context: String,
+}
+
+/// `page:line: context`, the way grep prints matches.
+impl fmt::Display for GrepMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.page, line, self.context),
+            None => write!(f, "{}: {}", self.page, self.context),
+        }
+    }
+}
+
+/// The part of `line` around `start..end`, trimmed.
+fn context(line: &str, start: usize, end: usize) -> String {
+    let from = line.floor_char_boundary(start.saturating_sub(CONTEXT));
+    let to = line.ceil_char_boundary(end + CONTEXT);
+    let mut context = String::new();
+    if from > 0 {
+        context.push('…');
+    }
+    context.push_str(line[from..to].trim());
+    if to < line.len() {
+        context.push('…');
+    }
+    context
+}
+
+/// The text of `document` outside scripts and styles, one line per text node line.
+fn visible_lines(document: &Html) -> Vec<String> {
+    document
+        .root_element()
+        .descendants()
+        .filter(|node| {
+            !node.ancestors().any(|ancestor| {
+                matches!(ancestor.value(), Node::Element(element)
+                    if matches!(element.name(), "script" | "style" | "noscript" | "template"))
+            })
+        })
+        .filter_map(|node| node.value().as_text().map(|text| text.to_string()))
+        .flat_map(|text| {
+            text.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+impl Grep {
+    /// Every match in the page at `url`, one per matching line.
+    pub fn search(&self, url: &Url, html: &str) -> Vec<GrepMatch> {
+        let lines: Vec<(Option<usize>, String)> = if self.text {
+            visible_lines(&Html::parse_document(html))
+                .into_iter()
+                .map(|line| (None, line))
+                .collect()
+        } else {
+            html.lines()
+                .enumerate()
+                .map(|(at, line)| (Some(at + 1), line.to_string()))
+                .collect()
+        };
+        lines
+            .into_iter()
+            .filter_map(|(line, text)| {
+                let found = self.regex.find(&text)?;
+                Some(GrepMatch {
+                    page: url.clone(),
+                    line,
+                    context: context(&text, found.start(), found.end()),
+                })
+            })
+            .collect()
+    }
+}
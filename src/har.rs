@@ -0,0 +1,307 @@
+use crate::error::{HtmlFindError, Result};
+use reqwest::header::HeaderMap;
+use reqwest::{Method, Response};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use url::Url;
+
+/// Collects requests and responses in HTTP Archive (HAR 1.2) form.
+#[derive(Debug, Clone, Default)]
+pub struct Har {
+    entries: Arc<Mutex<Vec<Entry>>>,
+}
+
+#[derive(Debug, Serialize)]
+struct Archive<'a> {
+    log: Log<'a>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Log<'a> {
+    version: &'static str,
+    creator: Creator,
+    entries: &'a [Entry],
+}
+
+#[derive(Debug, Serialize)]
+struct Creator {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    started_date_time: String,
+    time: f64,
+    request: Request,
+    response: ResponseEntry,
+    cache: Cache,
+    timings: Timings,
+    #[serde(rename = "_error", skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Request {
+    method: String,
+    url: String,
+    http_version: String,
+    cookies: Vec<NameValue>,
+    headers: Vec<NameValue>,
+    query_string: Vec<NameValue>,
+    headers_size: i64,
+    body_size: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResponseEntry {
+    status: u16,
+    status_text: String,
+    http_version: String,
+    cookies: Vec<NameValue>,
+    headers: Vec<NameValue>,
+    content: Content,
+    #[serde(rename = "redirectURL")]
+    redirect_url: String,
+    headers_size: i64,
+    body_size: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Content {
+    size: i64,
+    mime_type: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Cache {}
+
+#[derive(Debug, Clone, Serialize)]
+struct Timings {
+    send: f64,
+    wait: f64,
+    receive: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct NameValue {
+    name: String,
+    value: String,
+}
+
+/// Values marked sensitive, such as credentials, are left out.
+fn name_values(headers: &HeaderMap) -> Vec<NameValue> {
+    headers
+        .iter()
+        .map(|(name, value)| NameValue {
+            name: name.to_string(),
+            value: if value.is_sensitive() {
+                "[redacted]".to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            },
+        })
+        .collect()
+}
+
+impl Har {
+    /// Adds one exchange; `elapsed` runs until the response headers arrived.
+    pub fn record(
+        &self,
+        method: &Method,
+        url: &Url,
+        request_headers: &HeaderMap,
+        started: SystemTime,
+        elapsed: Duration,
+        response: std::result::Result<&Response, &reqwest::Error>,
+    ) {
+        let millis = elapsed.as_secs_f64() * 1000.0;
+        let (response, error) = match response {
+            Ok(res) => {
+                let headers = res.headers();
+                (
+                    ResponseEntry {
+                        status: res.status().as_u16(),
+                        status_text: res.status().canonical_reason().unwrap_or("").to_string(),
+                        http_version: format!("{:?}", res.version()),
+                        cookies: vec![],
+                        headers: name_values(headers),
+                        content: Content {
+                            size: res.content_length().map_or(-1, |len| len as i64),
+                            mime_type: headers
+                                .get(reqwest::header::CONTENT_TYPE)
+                                .and_then(|v| v.to_str().ok())
+                                .unwrap_or("")
+                                .to_string(),
+                        },
+                        redirect_url: if res.url() == url {
+                            String::new()
+                        } else {
+                            res.url().to_string()
+                        },
+                        headers_size: -1,
+                        body_size: res.content_length().map_or(-1, |len| len as i64),
+                    },
+                    None,
+                )
+            }
+            Err(err) => (
+                ResponseEntry {
+                    status: 0,
+                    status_text: String::new(),
+                    http_version: String::new(),
+                    cookies: vec![],
+                    headers: vec![],
+                    content: Content {
+                        size: 0,
+                        mime_type: String::new(),
+                    },
+                    redirect_url: String::new(),
+                    headers_size: -1,
+                    body_size: -1,
+                },
+                Some(err.to_string()),
+            ),
+        };
+
+        let entry = Entry {
+            started_date_time: humantime::format_rfc3339_millis(started).to_string(),
+            time: millis,
+            request: Request {
+                method: method.to_string(),
+                url: url.to_string(),
+                http_version: "HTTP/1.1".to_string(),
+                cookies: vec![],
+                headers: name_values(request_headers),
+                query_string: url
+                    .query_pairs()
+                    .map(|(name, value)| NameValue {
+                        name: name.into_owned(),
+                        value: value.into_owned(),
+                    })
+                    .collect(),
+                headers_size: -1,
+                body_size: 0,
+            },
+            response,
+            cache: Cache {},
+            timings: Timings {
+                send: 0.0,
+                wait: millis,
+                receive: 0.0,
+            },
+            error,
+        };
+        self.entries.lock().expect("HAR lock").push(entry);
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let entries = self.entries.lock().expect("HAR lock");
+        let archive = Archive {
+            log: Log {
+                version: "1.2",
+                creator: Creator {
+                    name: env!("CARGO_PKG_NAME"),
+                    version: env!("CARGO_PKG_VERSION"),
+                },
+                entries: &entries,
+            },
+        };
+        let bytes = serde_json::to_vec_pretty(&archive).expect("HAR serializes");
+        std::fs::write(path, bytes).map_err(|source| HtmlFindError::File {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+    use reqwest::ResponseBuilderExt;
+    use serde_json::{json, Value};
+
+    fn saved(har: &Har, name: &str) -> Value {
+        let path = std::env::temp_dir().join(format!("html_find_{}_{}", std::process::id(), name));
+        har.save(&path).unwrap();
+        let log = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        std::fs::remove_file(path).unwrap();
+        log
+    }
+
+    #[test]
+    fn records_exchanges_with_credentials_redacted() {
+        let url = Url::parse("https://example.com/search?q=links").unwrap();
+        let mut headers = HeaderMap::new();
+        let mut token = HeaderValue::from_static("Bearer secret");
+        token.set_sensitive(true);
+        headers.insert(AUTHORIZATION, token);
+        let response = http::Response::builder()
+            .status(200)
+            .url(Url::parse("https://example.com/results?q=links").unwrap())
+            .header(CONTENT_TYPE, "text/html")
+            .body("<p>found</p>")
+            .unwrap();
+        let response = Response::from(response);
+        let started = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let har = Har::default();
+        har.record(
+            &Method::GET,
+            &url,
+            &headers,
+            started,
+            Duration::from_millis(250),
+            Ok(&response),
+        );
+
+        let log = saved(&har, "exchange.har");
+        assert_eq!(log["log"]["version"], "1.2");
+        let entry = &log["log"]["entries"][0];
+        assert_eq!(entry["startedDateTime"], "2023-11-14T22:13:20.000Z");
+        assert_eq!(entry["time"], 250.0);
+        assert_eq!(
+            entry["request"]["headers"],
+            json!([{"name": "authorization", "value": "[redacted]"}])
+        );
+        assert_eq!(
+            entry["request"]["queryString"],
+            json!([{"name": "q", "value": "links"}])
+        );
+        assert_eq!(entry["response"]["status"], 200);
+        assert_eq!(entry["response"]["statusText"], "OK");
+        assert_eq!(entry["response"]["content"]["mimeType"], "text/html");
+        assert_eq!(
+            entry["response"]["redirectURL"],
+            "https://example.com/results?q=links"
+        );
+        assert!(entry.get("_error").is_none());
+    }
+
+    #[tokio::test]
+    async fn records_failed_requests_with_their_error() {
+        let url = Url::parse("http://127.0.0.1:9/").unwrap();
+        let err = reqwest::get(url.as_str()).await.unwrap_err();
+        let har = Har::default();
+        har.record(
+            &Method::HEAD,
+            &url,
+            &HeaderMap::new(),
+            SystemTime::now(),
+            Duration::ZERO,
+            Err(&err),
+        );
+
+        let entry = &saved(&har, "failed.har")["log"]["entries"][0];
+        assert_eq!(entry["request"]["method"], "HEAD");
+        assert_eq!(entry["response"]["status"], 0);
+        assert_eq!(entry["_error"], err.to_string());
+    }
+}
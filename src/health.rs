@@ -0,0 +1,86 @@
+use crate::report::{CheckResult, Failure};
+use std::collections::BTreeMap;
+use url::Url;
+
+/// The column headings of [`HostHealth`], in the order they are printed.
+const COLUMNS: [&str; 8] = [
+    "2xx", "3xx", "4xx", "5xx", "timeout", "dns", "connect", "other",
+];
+
+/// How the URLs of one host fared, by status class and by failure.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct HostHealth {
+    /// Answered with a success status, or reached without a request.
+    pub success: usize,
+    /// Redirected, whatever they ended up at, or answered with a redirect.
+    pub redirected: usize,
+    pub client_error: usize,
+    pub server_error: usize,
+    pub timeout: usize,
+    pub dns: usize,
+    pub connect: usize,
+    /// Failed otherwise, or answered with an informational status.
+    pub other: usize,
+}
+
+impl HostHealth {
+    fn add(&mut self, result: &CheckResult) {
+        let count = match (result.status, result.failure) {
+            _ if result.redirected_to.is_some() => &mut self.redirected,
+            (Some(status), _) if status.is_success() => &mut self.success,
+            (Some(status), _) if status.is_redirection() => &mut self.redirected,
+            (Some(status), _) if status.is_client_error() => &mut self.client_error,
+            (Some(status), _) if status.is_server_error() => &mut self.server_error,
+            (None, Some(Failure::Timeout)) => &mut self.timeout,
+            (None, Some(Failure::Dns)) => &mut self.dns,
+            (None, Some(Failure::Connect)) => &mut self.connect,
+            (None, None) if result.error.is_none() => &mut self.success,
+            _ => &mut self.other,
+        };
+        *count += 1;
+    }
+
+    fn columns(&self) -> [usize; 8] {
+        [
+            self.success,
+            self.redirected,
+            self.client_error,
+            self.server_error,
+            self.timeout,
+            self.dns,
+            self.connect,
+            self.other,
+        ]
+    }
+}
+
+/// The host `url` is counted under, with its port when it names one.
+fn host(url: &Url) -> String {
+    match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        (None, _) => format!("({})", url.scheme()),
+    }
+}
+
+/// How the URLs of each host in `results` fared.
+pub fn host_health(results: &[CheckResult]) -> BTreeMap<String, HostHealth> {
+    let mut hosts: BTreeMap<String, HostHealth> = BTreeMap::new();
+    for result in results {
+        hosts.entry(host(&result.url)).or_default().add(result);
+    }
+    hosts
+}
+
+/// Prints `hosts` as a table, a row per host.
+pub fn print_table(hosts: &BTreeMap<String, HostHealth>) {
+    let width = (hosts.keys().map(String::len)).max().unwrap_or(0).max(4);
+    let headings: Vec<String> = (COLUMNS.iter()).map(|c| format!("{:>7}", c)).collect();
+    println!("  {:<width$} {}", "host", headings.join(" "), width = width);
+    for (host, health) in hosts {
+        let counts: Vec<String> = (health.columns().iter())
+            .map(|count| format!("{:>7}", count))
+            .collect();
+        println!("  {:<width$} {}", host, counts.join(" "), width = width);
+    }
+}
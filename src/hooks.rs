@@ -0,0 +1,21 @@
+use crate::error::HtmlFindError;
+use crate::page::Fetched;
+use crate::report::CheckResult;
+
+/// Callbacks a run makes as it goes, given to [`Checker::with_hooks`], e.g.
+/// to drive a progress display or keep outcomes somewhere of their own.
+/// Each does nothing unless implemented. The run waits for them, so they
+/// should return quickly.
+///
+/// [`Checker::with_hooks`]: crate::Checker::with_hooks
+pub trait Hooks: Send {
+    /// A page, sitemap or script was fetched, before its links are checked.
+    fn on_page_fetched(&mut self, _page: &Fetched) {}
+
+    /// A URL was checked, taken from the cache, or couldn't be fetched.
+    fn on_link_checked(&mut self, _result: &CheckResult) {}
+
+    /// Something failed without ending the run, such as fetching a page or
+    /// looking up an archived copy.
+    fn on_error(&mut self, _error: &HtmlFindError) {}
+}
@@ -0,0 +1,184 @@
+use crate::fixtures::Fixtures;
+use crate::har::Har;
+use crate::warc::Warc;
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Method, Request, RequestBuilder, Response};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+use url::Url;
+
+/// The client every fetch and check goes through, recording exchanges when asked to.
+#[derive(Debug, Clone, Default)]
+pub struct Http {
+    client: Client,
+    /// Sent with every request.
+    headers: Arc<HeaderMap>,
+    /// Sent with requests to a host, replacing same-named `headers`.
+    host_headers: Arc<HashMap<String, HeaderMap>>,
+    har: Option<Har>,
+    warc: Option<Warc>,
+    fixtures: Option<Fixtures>,
+    /// Bytes of response bodies read so far, shared by every clone.
+    downloaded: Arc<AtomicU64>,
+}
+
+impl Http {
+    pub fn new(client: Client) -> Self {
+        Http {
+            client,
+            headers: Arc::default(),
+            host_headers: Arc::default(),
+            har: None,
+            warc: None,
+            fixtures: None,
+            downloaded: Arc::default(),
+        }
+    }
+
+    /// Sends requests through `client` from now on.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Adds `headers` to every request, and `host_headers` to those for a given host.
+    pub fn with_headers(
+        mut self,
+        headers: HeaderMap,
+        host_headers: HashMap<String, HeaderMap>,
+    ) -> Self {
+        self.headers = Arc::new(headers);
+        self.host_headers = Arc::new(host_headers);
+        self
+    }
+
+    /// Records responses to, or answers requests from, `fixtures`.
+    pub fn with_fixtures(mut self, fixtures: Fixtures) -> Self {
+        self.fixtures = Some(fixtures);
+        self
+    }
+
+    /// Records every exchange into `har`.
+    pub fn with_har(mut self, har: Har) -> Self {
+        self.har = Some(har);
+        self
+    }
+
+    pub fn har(&self) -> Option<&Har> {
+        self.har.as_ref()
+    }
+
+    /// Archives every response whose body is read into `warc`.
+    pub fn with_warc(mut self, warc: Warc) -> Self {
+        self.warc = Some(warc);
+        self
+    }
+
+    pub fn warc(&self) -> Option<&Warc> {
+        self.warc.as_ref()
+    }
+
+    /// Bytes of response bodies read so far, by this client and its clones.
+    pub fn downloaded(&self) -> u64 {
+        self.downloaded.load(Ordering::Relaxed)
+    }
+
+    /// Counts `bytes` of a body read without going through this client.
+    pub(crate) fn count(&self, bytes: usize) {
+        self.downloaded.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn get(&self, url: &Url) -> RequestBuilder {
+        self.request(Method::GET, url)
+    }
+
+    pub fn head(&self, url: &Url) -> RequestBuilder {
+        self.request(Method::HEAD, url)
+    }
+
+    fn request(&self, method: Method, url: &Url) -> RequestBuilder {
+        let mut request = self.client.request(method, url.as_ref());
+        if !self.headers.is_empty() {
+            request = request.headers((*self.headers).clone());
+        }
+        if let Some(headers) = url.host_str().and_then(|host| self.host_headers.get(host)) {
+            request = request.headers(headers.clone());
+        }
+        request
+    }
+
+    pub async fn send(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        let execute = |client: Client, request: Request| async move {
+            match &self.fixtures {
+                Some(fixtures) => fixtures.send(&client, request).await,
+                None => client.execute(request).await,
+            }
+        };
+        let (client, request) = request.build_split();
+        let request = request?;
+        let Some(har) = &self.har else {
+            return execute(client, request).await;
+        };
+        let method = request.method().clone();
+        let url = request.url().clone();
+        let headers = request.headers().clone();
+
+        let started = SystemTime::now();
+        let timer = Instant::now();
+        let res = execute(client, request).await;
+        har.record(
+            &method,
+            &url,
+            &headers,
+            started,
+            timer.elapsed(),
+            res.as_ref(),
+        );
+        res
+    }
+
+    /// Reads the raw body of `res`, archiving the response when a WARC is set.
+    pub async fn bytes(&self, res: Response) -> reqwest::Result<Bytes> {
+        let body = match &self.warc {
+            Some(warc) => self.archive(warc, res).await?,
+            None => res.bytes().await?,
+        };
+        self.count(body.len());
+        Ok(body)
+    }
+
+    /// Reads the body of `res`, archiving the response when a WARC is set.
+    pub async fn text(&self, res: Response) -> reqwest::Result<String> {
+        let Some(warc) = &self.warc else {
+            let text = res.text().await?;
+            self.count(text.len());
+            return Ok(text);
+        };
+        let body = self.archive(warc, res).await?;
+        self.count(body.len());
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+
+    /// Reads and archives the body of `res` when a WARC is set, otherwise
+    /// leaves it unread.
+    pub async fn consume(&self, res: Response) -> reqwest::Result<()> {
+        if let Some(warc) = &self.warc {
+            let body = self.archive(warc, res).await?;
+            self.count(body.len());
+        }
+        Ok(())
+    }
+
+    async fn archive(&self, warc: &Warc, res: Response) -> reqwest::Result<Bytes> {
+        let url = res.url().clone();
+        let version = res.version();
+        let status = res.status();
+        let headers = res.headers().clone();
+        let body = res.bytes().await?;
+        warc.record_response(&url, version, status, &headers, &body);
+        Ok(body)
+    }
+}
@@ -0,0 +1,65 @@
+mod error;
+mod queue;
+mod state;
+
+pub mod audit;
+pub mod badge;
+pub mod blocking;
+pub mod bot;
+pub mod budget;
+pub mod cache;
+pub mod caching;
+pub mod canonical;
+pub mod capture;
+pub mod check;
+pub mod checker;
+pub mod config;
+pub mod db;
+pub mod diff;
+pub mod duplicates;
+pub mod email;
+pub mod expect;
+pub mod find;
+pub mod fix;
+pub mod fixtures;
+pub mod grep;
+pub mod har;
+pub mod health;
+pub mod hooks;
+pub mod http;
+pub mod local;
+pub mod logging;
+pub mod metrics;
+pub mod notify;
+pub mod page;
+pub mod plan;
+pub mod rank;
+pub mod recipe;
+pub mod redirects;
+#[cfg(feature = "render")]
+pub mod render;
+pub mod report;
+pub mod reporter;
+pub mod resource;
+pub mod script;
+pub mod server;
+pub mod signed;
+pub mod sitemap;
+pub mod stream;
+pub mod structure;
+#[cfg(feature = "otlp")]
+pub mod telemetry;
+pub mod trend;
+pub mod tui;
+pub mod visited;
+pub mod warc;
+pub mod wayback;
+
+pub use checker::{Checker, Event, HostOptions, Options};
+pub use error::{parse_url, HtmlFindError, Phase, Result};
+pub use hooks::Hooks;
+pub use html_find_core::extract;
+pub use report::{CheckResult, Report, StopReason};
+pub use stream::CheckStream;
+/// Re-exported for [`Checker::with_cancellation`], so embedders need not depend on tokio-util.
+pub use tokio_util::sync::CancellationToken;
@@ -0,0 +1,395 @@
+use crate::error::{HtmlFindError, Result};
+use crate::extract::{markdown_targets, Attribute, Extractor, Source};
+use crate::page::Regions;
+use select::document::Document;
+use select::predicate::Name;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use url::Url;
+
+/// HTML and Markdown files on disk, such as the output of a static site generator.
+#[derive(Debug)]
+pub struct LocalSite {
+    /// Directory the site is read from.
+    pub root: PathBuf,
+    /// URL `root` is served at; a `file://` URL of `root` unless one is given.
+    pub base: Url,
+    pub pages: Vec<LocalPage>,
+}
+
+/// A parsed file with its links resolved.
+#[derive(Debug)]
+pub struct LocalPage {
+    /// `dir/` rather than `dir/index.html`, matching how such pages are linked.
+    pub url: Url,
+    pub path: PathBuf,
+    /// `<a href>` targets.
+    pub links: HashSet<Url>,
+    /// `<img src>` targets.
+    pub images: HashSet<Url>,
+    /// Line each link first appears on, for Markdown files.
+    pub lines: HashMap<Url, usize>,
+}
+
+impl LocalPage {
+    /// `file:line` of `link`, or just the file when the line is unknown.
+    pub fn location(&self, link: &Url) -> String {
+        let path = std::env::current_dir()
+            .ok()
+            .and_then(|dir| self.path.strip_prefix(dir).ok())
+            .unwrap_or(&self.path);
+        match self.lines.get(link) {
+            Some(line) => format!("{}:{}", path.display(), line),
+            None => path.display().to_string(),
+        }
+    }
+}
+
+fn file_error(path: &Path) -> impl FnOnce(std::io::Error) -> HtmlFindError + '_ {
+    move |source| HtmlFindError::File {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+}
+
+fn is_markdown(path: &Path) -> bool {
+    has_extension(path, &["md", "markdown"])
+}
+
+fn is_page(path: &Path) -> bool {
+    has_extension(path, &["html", "htm"]) || is_markdown(path)
+}
+
+/// Adds the HTML and Markdown files under `dir` to `files`, in name order.
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let mut entries = std::fs::read_dir(dir)
+        .map_err(file_error(dir))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()
+        .map_err(file_error(dir))?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            walk(&path, files)?;
+        } else if is_page(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The directories of `pattern` before its first wildcard.
+fn glob_root(pattern: &str) -> PathBuf {
+    Path::new(pattern)
+        .components()
+        .take_while(|component| match component {
+            Component::Normal(name) => !name.to_string_lossy().contains(['*', '?', '[']),
+            _ => true,
+        })
+        .collect()
+}
+
+/// Collects the link and image targets of CommonMark `text`, including
+/// those in raw HTML, noting the line each first appears on.
+fn read_markdown(text: &str, url: Url, path: &Path) -> LocalPage {
+    let mut page = LocalPage {
+        url,
+        path: path.to_path_buf(),
+        links: HashSet::new(),
+        images: HashSet::new(),
+        lines: HashMap::new(),
+    };
+    for target in markdown_targets(text, &page.url) {
+        page.lines.entry(target.url.clone()).or_insert(target.line);
+        if target.image {
+            page.images.insert(target.url);
+        } else {
+            page.links.insert(target.url);
+        }
+    }
+    page
+}
+
+fn read_page(file: &Path, url: Url, regions: &Regions) -> Result<LocalPage> {
+    let bytes = std::fs::read(file).map_err(file_error(file))?;
+    let html = String::from_utf8_lossy(&bytes);
+    if is_markdown(file) {
+        return Ok(read_markdown(&html, url, file));
+    }
+    let document = Document::from(html.as_ref());
+    // Relative links resolve against the page itself, as a browser would.
+    let base_url = match document.find(Name("base")).find_map(|n| n.attr("href")) {
+        Some(href) => url.join(href).map_err(|source| HtmlFindError::InvalidUrl {
+            url: href.to_string(),
+            source,
+        })?,
+        None => url.clone(),
+    };
+    let source = Source {
+        base_url: &base_url,
+        html: &html,
+        document: &document,
+        regions,
+    };
+    Ok(LocalPage {
+        links: Attribute::LINKS.extract(&source),
+        images: Attribute::IMAGES.extract(&source),
+        lines: HashMap::new(),
+        url,
+        path: file.to_path_buf(),
+    })
+}
+
+impl LocalSite {
+    /// Reads the HTML or Markdown file at `path`, or every such file below it when it is
+    /// a directory. Pages get URLs under `base_url` when one is given, and links are
+    /// taken from the `regions` of HTML files.
+    pub fn load(path: &Path, base_url: Option<Url>, regions: &Regions) -> Result<LocalSite> {
+        let absolute = std::path::absolute(path).map_err(file_error(path))?;
+        let mut files = vec![];
+        let root = if absolute.is_dir() {
+            walk(&absolute, &mut files)?;
+            absolute
+        } else {
+            std::fs::metadata(&absolute).map_err(file_error(path))?;
+            files.push(absolute.clone());
+            absolute
+                .parent()
+                .map_or_else(PathBuf::new, Path::to_path_buf)
+        };
+        Self::read(root, files, base_url, regions)
+    }
+
+    /// Reads the files matching `pattern`, e.g. `dist/**/*.html`. The site
+    /// root is the part of the pattern before its first wildcard.
+    pub fn glob(pattern: &str, base_url: Option<Url>, regions: &Regions) -> Result<LocalSite> {
+        let invalid = |source| HtmlFindError::InvalidGlob {
+            pattern: pattern.to_string(),
+            source,
+        };
+        let mut files = vec![];
+        for entry in glob::glob(pattern).map_err(invalid)? {
+            let path = entry.map_err(|err| HtmlFindError::File {
+                path: err.path().to_path_buf(),
+                source: err.into(),
+            })?;
+            if path.is_file() {
+                files.push(std::path::absolute(&path).map_err(file_error(&path))?);
+            }
+        }
+        let root = glob_root(pattern);
+        let root = std::path::absolute(&root).map_err(file_error(&root))?;
+        Self::read(root, files, base_url, regions)
+    }
+
+    /// Parses `files` across the available cores.
+    fn read(
+        root: PathBuf,
+        files: Vec<PathBuf>,
+        base_url: Option<Url>,
+        regions: &Regions,
+    ) -> Result<LocalSite> {
+        let root_url = Url::from_directory_path(&root).map_err(|_| HtmlFindError::File {
+            path: root.clone(),
+            source: std::io::Error::other("path cannot be expressed as a file:// URL"),
+        })?;
+        let mut base = base_url.unwrap_or_else(|| root_url.clone());
+        if !base.path().ends_with('/') {
+            base.set_path(&format!("{}/", base.path()));
+        }
+
+        let urls = files
+            .iter()
+            .map(|file| {
+                let mut rel = Url::from_file_path(file)
+                    .ok()
+                    .and_then(|url| url.path().strip_prefix(root_url.path()).map(String::from))
+                    .unwrap_or_default();
+                if rel == "index.html" || rel.ends_with("/index.html") {
+                    rel.truncate(rel.len() - "index.html".len());
+                }
+                base.join(&rel).unwrap_or_else(|_| base.clone())
+            })
+            .collect::<Vec<_>>();
+
+        let workers = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk = files.len().div_ceil(workers).max(1);
+        let pages = std::thread::scope(|scope| {
+            let readers = files
+                .chunks(chunk)
+                .zip(urls.chunks(chunk))
+                .map(|(files, urls)| {
+                    scope.spawn(move || {
+                        files
+                            .iter()
+                            .zip(urls)
+                            .map(|(file, url)| read_page(file, url.clone(), regions))
+                            .collect::<Result<Vec<_>>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+            readers
+                .into_iter()
+                .map(|reader| reader.join().expect("page reader panicked"))
+                .collect::<Result<Vec<_>>>()
+        })?;
+
+        Ok(LocalSite {
+            root,
+            base,
+            pages: pages.into_iter().flatten().collect(),
+        })
+    }
+
+    /// The file `url` points at, when it is under `base` or another `file://` URL.
+    pub fn resolve(&self, url: &Url) -> Option<PathBuf> {
+        let same_origin = url.scheme() == self.base.scheme()
+            && url.host_str() == self.base.host_str()
+            && url.port_or_known_default() == self.base.port_or_known_default();
+        if same_origin && url.path().starts_with(self.base.path()) {
+            let rel = &url.path()[self.base.path().len()..];
+            let mut file = Url::from_directory_path(&self.root).ok()?;
+            file.set_path(&format!("{}{}", file.path(), rel));
+            return file.to_file_path().ok();
+        }
+        if url.scheme() == "file" {
+            return url.to_file_path().ok();
+        }
+        None
+    }
+
+    /// Whether a server would find something at `path`: the file itself, a
+    /// directory's `index.html`, or `path.html` for an extensionless link.
+    pub fn exists(path: &Path) -> bool {
+        path.is_file()
+            || path.join("index.html").is_file()
+            || (path.extension().is_none() && path.with_extension("html").is_file())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A site under the temp dir, written from `(path, contents)` pairs.
+    fn site(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("html_find_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for (path, contents) in files {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        root
+    }
+
+    fn base() -> Option<Url> {
+        Some(Url::parse("https://example.com/docs").unwrap())
+    }
+
+    #[test]
+    fn load_serves_pages_at_the_base_url() {
+        let root = site(
+            "local_load",
+            &[
+                (
+                    "index.html",
+                    r#"<a href="guide/">Guide</a><img src="logo.png">"#,
+                ),
+                (
+                    "guide/index.html",
+                    r#"<base href="/docs/"><a href="api.html">API</a>"#,
+                ),
+                ("guide/notes.txt", "not a page"),
+            ],
+        );
+        let site = LocalSite::load(&root, base(), &Regions::default()).unwrap();
+        let url = |path| {
+            Url::parse("https://example.com/docs/")
+                .unwrap()
+                .join(path)
+                .unwrap()
+        };
+        assert_eq!(site.base, url(""));
+        let pages: Vec<_> = (site.pages.iter())
+            .map(|page| (page.url.clone(), page.links.clone(), page.images.clone()))
+            .collect();
+        assert_eq!(
+            pages,
+            [
+                (
+                    url("guide/"),
+                    [url("api.html")].into_iter().collect(),
+                    HashSet::new()
+                ),
+                (
+                    url(""),
+                    [url("guide/")].into_iter().collect(),
+                    [url("logo.png")].into_iter().collect()
+                ),
+            ]
+        );
+        assert_eq!(site.resolve(&url("guide/")), Some(root.join("guide/")));
+        assert_eq!(
+            site.resolve(&Url::parse("https://other.example/docs/").unwrap()),
+            None
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn markdown_pages_note_the_line_of_each_link() {
+        let root = site(
+            "local_markdown",
+            &[(
+                "README.md",
+                "# Docs\n\n[Guide](guide.md) and [again](guide.md)\n\n![Logo](logo.png)\n",
+            )],
+        );
+        let site = LocalSite::load(&root.join("README.md"), None, &Regions::default()).unwrap();
+        let page = &site.pages[0];
+        let guide = site.base.join("guide.md").unwrap();
+        let logo = site.base.join("logo.png").unwrap();
+        assert_eq!(page.links, [guide.clone()].into_iter().collect());
+        assert_eq!(page.images, [logo.clone()].into_iter().collect());
+        assert!(page.location(&guide).ends_with("README.md:3"));
+        assert!(page.location(&logo).ends_with("README.md:5"));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn exists_finds_indexes_and_extensionless_pages() {
+        let root = site("local_exists", &[("a/index.html", ""), ("b.html", "")]);
+        assert!(LocalSite::exists(&root.join("a")));
+        assert!(LocalSite::exists(&root.join("b")));
+        assert!(LocalSite::exists(&root.join("b.html")));
+        assert!(!LocalSite::exists(&root.join("c")));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn glob_reads_matches_below_the_pattern_root() {
+        let root = site(
+            "local_glob",
+            &[("dist/a.html", ""), ("dist/b/c.html", ""), ("d.html", "")],
+        );
+        assert_eq!(glob_root("dist/**/*.html"), PathBuf::from("dist"));
+        let pattern = format!("{}/dist/**/*.html", root.display());
+        let site = LocalSite::glob(&pattern, base(), &Regions::default()).unwrap();
+        let mut urls: Vec<_> = site
+            .pages
+            .iter()
+            .map(|page| page.url.path().to_string())
+            .collect();
+        urls.sort();
+        assert_eq!(urls, ["/docs/a.html", "/docs/b/c.html"]);
+        assert!(LocalSite::glob("dist/[", None, &Regions::default()).is_err());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
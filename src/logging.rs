@@ -0,0 +1,111 @@
+use std::fmt;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::{SubscriberInitExt, TryInitError};
+use tracing_subscriber::{Layer, Registry};
+
+/// How diagnostics are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Just the message and its fields, one line each.
+    #[default]
+    Text,
+    /// One JSON object per line with the level, timestamp and fields.
+    Json,
+}
+
+/// Writes the message and fields of events, without levels or span context.
+struct Plain;
+
+impl<S, N> FormatEvent<S, N> for Plain
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
+
+/// Writes the message as is, so the colors of outcome rows survive, and
+/// other fields as `name=value` after it.
+struct PlainFields;
+
+impl<'w> FormatFields<'w> for PlainFields {
+    fn format_fields<R: RecordFields>(&self, writer: Writer<'w>, fields: R) -> fmt::Result {
+        let mut visitor = PlainVisitor {
+            writer,
+            message: None,
+            rest: String::new(),
+        };
+        fields.record(&mut visitor);
+        let PlainVisitor {
+            mut writer,
+            message,
+            rest,
+        } = visitor;
+        write!(writer, "{}{}", message.unwrap_or_default(), rest)
+    }
+}
+
+struct PlainVisitor<'w> {
+    writer: Writer<'w>,
+    message: Option<String>,
+    rest: String,
+}
+
+impl Visit for PlainVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = Some(format!("{:?}", value));
+        } else {
+            self.rest
+                .push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+}
+
+/// A layer that sees every span and event, such as a trace exporter's.
+pub type SpanLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// Logs events up to `level` to stderr, so stdout carries only the report.
+/// Colors follow [`console::colors_enabled_stderr`].
+/// Dependencies only log warnings unless `level` is `TRACE`. `spans` gets
+/// everything, whatever the level.
+pub fn init(
+    level: LevelFilter,
+    format: LogFormat,
+    spans: Option<SpanLayer>,
+) -> Result<(), TryInitError> {
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_ansi(console::colors_enabled_stderr());
+    let layer = match format {
+        LogFormat::Text => layer.fmt_fields(PlainFields).event_format(Plain).boxed(),
+        LogFormat::Json => layer.json().with_span_list(false).boxed(),
+    };
+    let others = if level == LevelFilter::TRACE {
+        level
+    } else {
+        level.min(LevelFilter::WARN)
+    };
+    let filter = Targets::new()
+        .with_target(env!("CARGO_CRATE_NAME"), level)
+        .with_default(others);
+    tracing_subscriber::registry()
+        .with(spans)
+        .with(layer.with_filter(filter))
+        .try_init()
+}
@@ -0,0 +1,174 @@
+use crate::error::{HtmlFindError, Phase, Result};
+use crate::report::Report;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use url::Url;
+
+/// Upper bounds of the check duration histogram, in seconds.
+const BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+#[derive(Default)]
+struct HostCounts {
+    checked: usize,
+    broken: usize,
+}
+
+/// Escapes a label value for the Prometheus text format.
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+fn host_gauge<'a>(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    values: impl Iterator<Item = (&'a str, usize)>,
+) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    for (host, value) in values {
+        let _ = writeln!(out, "{}{{host=\"{}\"}} {}", name, label(host), value);
+    }
+}
+
+/// The run's counts, check durations and per-host counts in the Prometheus
+/// text exposition format. `duration` is how long the run took.
+pub fn render(report: &Report, duration: Duration) -> String {
+    let mut out = String::new();
+    let broken = report.broken().count();
+    let redirected = (report.results.iter())
+        .filter(|result| result.redirected_to.is_some())
+        .count();
+    gauge(
+        &mut out,
+        "html_find_links_checked",
+        "URLs checked in the last run.",
+        report.results.len(),
+    );
+    gauge(
+        &mut out,
+        "html_find_links_broken",
+        "URLs found broken in the last run.",
+        broken,
+    );
+    gauge(
+        &mut out,
+        "html_find_links_redirected",
+        "URLs that redirected in the last run.",
+        redirected,
+    );
+    gauge(
+        &mut out,
+        "html_find_links_discovered",
+        "Distinct URLs found on scanned pages in the last run.",
+        report.discovered,
+    );
+    gauge(
+        &mut out,
+        "html_find_pages_scanned",
+        "Pages whose links were extracted in the last run.",
+        report.pages.len(),
+    );
+    gauge(
+        &mut out,
+        "html_find_run_duration_seconds",
+        "How long the last run took.",
+        duration.as_secs_f64(),
+    );
+    let finished = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    gauge(
+        &mut out,
+        "html_find_last_run_timestamp_seconds",
+        "When the last run finished.",
+        finished.as_secs(),
+    );
+
+    let elapsed: Vec<f64> = (report.results.iter())
+        .filter_map(|result| result.elapsed_ms)
+        .map(|ms| ms as f64 / 1000.0)
+        .collect();
+    let name = "html_find_check_duration_seconds";
+    let _ = writeln!(out, "# HELP {} Time until each checked URL answered.", name);
+    let _ = writeln!(out, "# TYPE {} histogram", name);
+    for bound in BUCKETS {
+        let count = elapsed.iter().filter(|&&secs| secs <= *bound).count();
+        let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, count);
+    }
+    let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, elapsed.len());
+    let _ = writeln!(out, "{}_sum {}", name, elapsed.iter().sum::<f64>());
+    let _ = writeln!(out, "{}_count {}", name, elapsed.len());
+
+    let mut hosts: BTreeMap<&str, HostCounts> = BTreeMap::new();
+    for result in &report.results {
+        let counts = hosts
+            .entry(result.url.host_str().unwrap_or(""))
+            .or_default();
+        counts.checked += 1;
+        if !result.is_ok() {
+            counts.broken += 1;
+        }
+    }
+    host_gauge(
+        &mut out,
+        "html_find_host_links_checked",
+        "URLs checked in the last run, by host.",
+        hosts.iter().map(|(host, counts)| (*host, counts.checked)),
+    );
+    host_gauge(
+        &mut out,
+        "html_find_host_links_broken",
+        "URLs found broken in the last run, by host.",
+        hosts.iter().map(|(host, counts)| (*host, counts.broken)),
+    );
+    out
+}
+
+/// Writes the metrics for node_exporter's textfile collector, replacing `path`
+/// in one step so the collector never reads half a file.
+pub fn write_textfile(path: &Path, metrics: &str) -> Result<()> {
+    let file_error = |source| HtmlFindError::File {
+        path: path.to_path_buf(),
+        source,
+    };
+    let partial = path.with_extension("prom.tmp");
+    std::fs::write(&partial, metrics).map_err(file_error)?;
+    std::fs::rename(&partial, path).map_err(file_error)
+}
+
+/// Replaces the metrics of `job` on a Prometheus Pushgateway.
+pub async fn push(
+    client: &reqwest::Client,
+    gateway: &Url,
+    job: &str,
+    metrics: String,
+) -> Result<()> {
+    let mut url = gateway.clone();
+    url.path_segments_mut()
+        .map_err(|_| HtmlFindError::MissingHost {
+            url: gateway.clone(),
+        })?
+        .pop_if_empty()
+        .extend(["metrics", "job", job]);
+    client
+        .put(url.clone())
+        .header(reqwest::header::CONTENT_TYPE, "text/plain; version=0.0.4")
+        .body(metrics)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map(drop)
+        .map_err(HtmlFindError::request(&url, Phase::PushMetrics))
+}
@@ -0,0 +1,144 @@
+use crate::error::{HtmlFindError, Phase, Result};
+use crate::report::{CheckResult, Report, StopReason};
+use serde::Serialize;
+use std::str::FromStr;
+use url::Url;
+
+/// What a run found, as posted to a webhook.
+#[derive(Debug, Serialize)]
+pub struct Summary<'a> {
+    pub urls: &'a [Url],
+    pub checked: usize,
+    pub ok: usize,
+    pub broken: usize,
+    pub discovered: usize,
+    pub pages: usize,
+    pub stopped: Option<StopReason>,
+    /// Each broken result, when asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub findings: Option<Vec<&'a CheckResult>>,
+}
+
+impl<'a> Summary<'a> {
+    pub fn new(urls: &'a [Url], report: &'a Report, findings: bool) -> Summary<'a> {
+        let broken = report.broken().count();
+        Summary {
+            urls,
+            checked: report.results.len(),
+            ok: report.results.len() - broken,
+            broken,
+            discovered: report.discovered,
+            pages: report.pages.len(),
+            stopped: report.stopped,
+            findings: findings.then(|| report.broken().collect()),
+        }
+    }
+}
+
+/// Broken links listed in a chat message; the rest are only counted.
+const TOP_OFFENDERS: usize = 5;
+
+/// Discord rejects longer messages.
+const DISCORD_LIMIT: usize = 2000;
+
+/// Chat service a notification is formatted for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Service {
+    Slack,
+    Discord,
+}
+
+/// An incoming webhook of a chat service, given as `slack:<url>` or `discord:<url>`.
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    pub service: Service,
+    pub webhook: Url,
+}
+
+impl FromStr for Notifier {
+    type Err = String;
+
+    fn from_str(spec: &str) -> std::result::Result<Self, String> {
+        let (service, webhook) = spec
+            .split_once(':')
+            .ok_or_else(|| format!("expected SERVICE:URL, got {:?}", spec))?;
+        let service = match service.to_ascii_lowercase().as_str() {
+            "slack" => Service::Slack,
+            "discord" => Service::Discord,
+            other => {
+                return Err(format!(
+                    "unknown service {:?}, expected slack or discord",
+                    other
+                ))
+            }
+        };
+        let webhook = Url::parse(webhook).map_err(|err| err.to_string())?;
+        Ok(Notifier { service, webhook })
+    }
+}
+
+impl Notifier {
+    /// Posts a short summary naming the worst of `broken`: those linked from the
+    /// most places first. `newly` says they broke since a baseline.
+    pub async fn send(
+        &self,
+        client: &reqwest::Client,
+        urls: &[Url],
+        report: &Report,
+        broken: &[&CheckResult],
+        newly: bool,
+    ) -> Result<()> {
+        let mut offenders = broken.to_vec();
+        offenders.sort_by(|a, b| {
+            (b.found_in.len().cmp(&a.found_in.len())).then_with(|| a.url.cmp(&b.url))
+        });
+        let starts: Vec<String> = urls.iter().map(Url::to_string).collect();
+        let mut message = format!(
+            "{} {}broken of {} links checked on {}",
+            broken.len(),
+            if newly { "newly " } else { "" },
+            report.results.len(),
+            starts.join(", ")
+        );
+        for result in offenders.iter().take(TOP_OFFENDERS) {
+            message.push_str(&format!("\n• {}", result));
+        }
+        if offenders.len() > TOP_OFFENDERS {
+            message.push_str(&format!("\n…and {} more", offenders.len() - TOP_OFFENDERS));
+        }
+        let body = match self.service {
+            Service::Slack => serde_json::json!({ "text": message }),
+            Service::Discord => {
+                if message.len() > DISCORD_LIMIT {
+                    let end = message.floor_char_boundary(DISCORD_LIMIT - '…'.len_utf8());
+                    message.truncate(end);
+                    message.push('…');
+                }
+                serde_json::json!({ "content": message })
+            }
+        };
+        post_json(client, &self.webhook, &body).await
+    }
+}
+
+/// POSTs `summary` as JSON to `webhook`, failing unless it answers with a 2xx status.
+pub async fn post_webhook(
+    client: &reqwest::Client,
+    webhook: &Url,
+    summary: &Summary<'_>,
+) -> Result<()> {
+    post_json(client, webhook, summary).await
+}
+
+async fn post_json(client: &reqwest::Client, webhook: &Url, body: &impl Serialize) -> Result<()> {
+    let body = serde_json::to_vec(body).expect("notification serializes");
+    client
+        .post(webhook.clone())
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map(drop)
+        .map_err(HtmlFindError::request(webhook, Phase::Notify))
+}
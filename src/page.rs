@@ -0,0 +1,95 @@
+use crate::error::{parse_url, HtmlFindError, Phase, Result};
+use crate::http::Http;
+use flate2::read::GzDecoder;
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use select::document::Document;
+use select::predicate::Name;
+use std::io::Read;
+use url::{Position, Url};
+
+pub use html_find_core::page::{extract_links, resources, Regions};
+
+/// The first bytes of every gzip file.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+pub fn get_base_url(url: &Url, doc: &Document) -> Result<Url> {
+    let base_tag_href = doc.find(Name("base")).filter_map(|n| n.attr("href")).next();
+    base_tag_href.map_or_else(|| parse_url(&url[..Position::BeforePath]), parse_url)
+}
+
+pub async fn get_document(http: &Http, url: &Url, phase: Phase) -> Result<Document> {
+    let res = get_text(http, url, phase).await?;
+    let document = Document::from(res.as_str());
+    Ok(document)
+}
+
+/// Fetches the body of `url`. Unlike a `Document`, the result is `Send`.
+pub async fn get_text(http: &Http, url: &Url, phase: Phase) -> Result<String> {
+    get_page(http, url, phase).await.map(|(_, body)| body)
+}
+
+/// A fetched document, with where it ended up after redirects.
+#[derive(Debug, Clone)]
+pub struct Fetched {
+    pub status: StatusCode,
+    pub url: Url,
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+/// Fetches `url`, returning its status along with the body.
+pub async fn get_page(http: &Http, url: &Url, phase: Phase) -> Result<(StatusCode, String)> {
+    fetch(http, url, phase)
+        .await
+        .map(|fetched| (fetched.status, fetched.body))
+}
+
+/// Fetches `url`. Bodies sent with `Content-Encoding: gzip` are decoded by
+/// the client; sitemaps that are gzip files themselves, such as
+/// `sitemap.xml.gz`, are decompressed here.
+pub async fn fetch(http: &Http, url: &Url, phase: Phase) -> Result<Fetched> {
+    let res = http
+        .send(http.get(url))
+        .await
+        .map_err(HtmlFindError::request(url, phase))?;
+    let status = res.status();
+    let (final_url, headers) = (res.url().clone(), res.headers().clone());
+    let fetched = |body| Fetched {
+        status,
+        url: final_url,
+        headers,
+        body,
+    };
+    if phase != Phase::FetchSitemap {
+        let body = http
+            .text(res)
+            .await
+            .map_err(HtmlFindError::request(url, phase))?;
+        return Ok(fetched(body));
+    }
+    let body = http
+        .bytes(res)
+        .await
+        .map_err(HtmlFindError::request(url, phase))?;
+    if !body.starts_with(&GZIP_MAGIC) {
+        return Ok(fetched(String::from_utf8_lossy(&body).into_owned()));
+    }
+    let mut xml = String::new();
+    GzDecoder::new(&body[..])
+        .read_to_string(&mut xml)
+        .map_err(|source| HtmlFindError::Decompress {
+            url: url.clone(),
+            source,
+        })?;
+    Ok(fetched(xml))
+}
+
+/// Whether a response sent as `content_type` is a page, taking an unlabeled one as one.
+pub fn is_html(content_type: Option<&str>) -> bool {
+    content_type.is_none_or(|content_type| {
+        let essence = content_type.split(';').next().unwrap_or_default().trim();
+        essence.eq_ignore_ascii_case("text/html")
+            || essence.eq_ignore_ascii_case("application/xhtml+xml")
+    })
+}
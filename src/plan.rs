@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+use url::Url;
+
+/// Extensions of each type of link, by the last part of the path.
+const TYPES: &[(&str, &[&str])] = &[
+    (
+        "page",
+        &["html", "htm", "xhtml", "php", "asp", "aspx", "jsp"],
+    ),
+    (
+        "image",
+        &[
+            "png", "jpg", "jpeg", "gif", "svg", "webp", "avif", "ico", "bmp",
+        ],
+    ),
+    ("script", &["js", "mjs"]),
+    ("stylesheet", &["css"]),
+    ("font", &["woff", "woff2", "ttf", "otf", "eot"]),
+    ("media", &["mp4", "webm", "mov", "mp3", "ogg", "wav"]),
+    (
+        "document",
+        &[
+            "pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "txt", "csv",
+        ],
+    ),
+    ("archive", &["zip", "gz", "tgz", "tar", "7z", "rar"]),
+    ("data", &["json", "xml", "rss", "atom"]),
+];
+
+/// The type of thing `url` most likely is, judging by its extension: a
+/// path without one is taken as a page, and a link of a scheme other than
+/// `http` or `https` goes by the scheme, such as `mailto`.
+pub fn link_type(url: &Url) -> &str {
+    if !matches!(url.scheme(), "http" | "https") {
+        return url.scheme();
+    }
+    let name = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back());
+    let Some((_, extension)) = name.and_then(|name| name.rsplit_once('.')) else {
+        return "page";
+    };
+    (TYPES.iter())
+        .find(|(_, extensions)| (extensions.iter()).any(|e| e.eq_ignore_ascii_case(extension)))
+        .map_or("other", |(kind, _)| kind)
+}
+
+/// What a dry run found it would check, counted by host and by type.
+#[derive(Debug, Default)]
+pub struct Plan {
+    pub total: usize,
+    pub hosts: BTreeMap<String, usize>,
+    pub types: BTreeMap<String, usize>,
+}
+
+impl Plan {
+    pub fn new(links: &[Url]) -> Plan {
+        let mut plan = Plan {
+            total: links.len(),
+            ..Plan::default()
+        };
+        for link in links {
+            let host = link.host_str().unwrap_or("(no host)").to_string();
+            *plan.hosts.entry(host).or_default() += 1;
+            *plan.types.entry(link_type(link).to_string()).or_default() += 1;
+        }
+        plan
+    }
+
+    /// Prints the counts, the largest first.
+    pub fn print(&self) {
+        println!("Would check {} URLs", self.total);
+        for (heading, counts) in [("By host:", &self.hosts), ("By type:", &self.types)] {
+            let mut counts: Vec<(&String, &usize)> = counts.iter().collect();
+            counts.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+            println!("{}", heading);
+            for (name, count) in counts {
+                println!("{:>6}  {}", count, name);
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::checker::HostOrder;
+use std::collections::{HashMap, VecDeque};
+use tracing::Span;
+use url::Url;
+
+/// A link waiting to be checked, with the span of the page it was found on.
+type Queued = (Url, Span);
+
+/// Links waiting for a free slot to be checked in.
+#[derive(Debug, Default)]
+pub(crate) struct LinkQueue {
+    order: HostOrder,
+    /// Every queued link in the order it came, when taken in that order.
+    fifo: VecDeque<Queued>,
+    /// Hosts with queued links, the next one to take from first.
+    hosts: VecDeque<String>,
+    by_host: HashMap<String, VecDeque<Queued>>,
+}
+
+impl LinkQueue {
+    pub fn new(order: HostOrder) -> Self {
+        LinkQueue {
+            order,
+            ..LinkQueue::default()
+        }
+    }
+
+    pub fn push(&mut self, link: Url, span: Span) {
+        if self.order == HostOrder::Fifo {
+            self.fifo.push_back((link, span));
+            return;
+        }
+        let host = link.host_str().unwrap_or_default().to_string();
+        let queued = self.by_host.entry(host.clone()).or_default();
+        if queued.is_empty() {
+            self.hosts.push_back(host);
+        }
+        queued.push_back((link, span));
+    }
+
+    /// The next link to check: the oldest one, or the oldest of the host
+    /// whose turn it is, which then goes to the back of the line.
+    pub fn pop(&mut self) -> Option<Queued> {
+        if self.order == HostOrder::Fifo {
+            return self.fifo.pop_front();
+        }
+        let host = self.hosts.pop_front()?;
+        let queued = self.by_host.get_mut(&host)?;
+        let link = queued.pop_front();
+        if queued.is_empty() {
+            self.by_host.remove(&host);
+        } else {
+            self.hosts.push_back(host);
+        }
+        link
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(order: HostOrder, links: &[&str]) -> Vec<String> {
+        let mut queue = LinkQueue::new(order);
+        for link in links {
+            queue.push(Url::parse(link).unwrap(), Span::none());
+        }
+        std::iter::from_fn(|| queue.pop())
+            .map(|(link, _)| link.to_string())
+            .collect()
+    }
+
+    const LINKS: [&str; 5] = [
+        "https://a.example/1",
+        "https://a.example/2",
+        "https://a.example/3",
+        "https://b.example/1",
+        "https://c.example/1",
+    ];
+
+    #[test]
+    fn fifo_keeps_the_order_links_came_in() {
+        assert_eq!(drain(HostOrder::Fifo, &LINKS), LINKS);
+    }
+
+    #[test]
+    fn round_robin_takes_turns_between_hosts() {
+        assert_eq!(
+            drain(HostOrder::RoundRobin, &LINKS),
+            [
+                "https://a.example/1",
+                "https://b.example/1",
+                "https://c.example/1",
+                "https://a.example/2",
+                "https://a.example/3",
+            ]
+        );
+    }
+
+    #[test]
+    fn round_robin_puts_a_returning_host_at_the_back() {
+        let mut queue = LinkQueue::new(HostOrder::RoundRobin);
+        let push =
+            |queue: &mut LinkQueue, link| queue.push(Url::parse(link).unwrap(), Span::none());
+        push(&mut queue, "https://a.example/1");
+        push(&mut queue, "https://b.example/1");
+        assert_eq!(queue.pop().unwrap().0.as_str(), "https://a.example/1");
+        push(&mut queue, "https://a.example/2");
+        push(&mut queue, "https://c.example/1");
+        let rest: Vec<_> = std::iter::from_fn(|| queue.pop())
+            .map(|(link, _)| link.to_string())
+            .collect();
+        assert_eq!(
+            rest,
+            [
+                "https://b.example/1",
+                "https://a.example/2",
+                "https://c.example/1"
+            ]
+        );
+    }
+}
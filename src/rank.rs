@@ -0,0 +1,132 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use url::Url;
+
+/// How much of a page's equity its links pass on; the remainder is spread
+/// over every page, as for a reader jumping to one at random.
+const DAMPING: f64 = 0.85;
+
+/// Rounds of passing equity along links, at most.
+const MAX_ROUNDS: usize = 100;
+
+/// Scores within this of the last round's count as settled.
+const SETTLED: f64 = 1e-9;
+
+/// The link equity of each of `pages`, by PageRank over the links between
+/// them, `referrers` giving the pages linking to each URL. Scores are scaled
+/// so that they average 1: a page well below that is linked to little from
+/// elsewhere on the site. Links of a page to itself don't count, and a page
+/// without links spreads its equity over every page.
+pub fn page_rank(pages: &[Url], referrers: &HashMap<Url, HashSet<Url>>) -> BTreeMap<Url, f64> {
+    let pages: Vec<&Url> = {
+        let mut unique: Vec<&Url> = pages.iter().collect();
+        unique.sort();
+        unique.dedup();
+        unique
+    };
+    let index: HashMap<&Url, usize> = (pages.iter()).enumerate().map(|(i, p)| (*p, i)).collect();
+    let n = pages.len();
+    if n == 0 {
+        return BTreeMap::new();
+    }
+    // The links between pages, as the pages each one links to.
+    let mut links: Vec<Vec<usize>> = vec![vec![]; n];
+    for (target, sources) in referrers {
+        let Some(&to) = index.get(target) else {
+            continue;
+        };
+        for source in sources {
+            if let Some(&from) = index.get(source).filter(|&&from| from != to) {
+                links[from].push(to);
+            }
+        }
+    }
+
+    let mut scores = vec![1.0 / n as f64; n];
+    for _ in 0..MAX_ROUNDS {
+        let dangling: f64 = (links.iter().zip(&scores))
+            .filter(|(out, _)| out.is_empty())
+            .map(|(_, score)| score)
+            .sum();
+        let base = (1.0 - DAMPING + DAMPING * dangling) / n as f64;
+        let mut next = vec![base; n];
+        for (from, out) in links.iter().enumerate() {
+            for &to in out {
+                next[to] += DAMPING * scores[from] / out.len() as f64;
+            }
+        }
+        let change: f64 = (next.iter().zip(&scores)).map(|(a, b)| (a - b).abs()).sum();
+        scores = next;
+        if change < SETTLED {
+            break;
+        }
+    }
+    (pages.into_iter().zip(scores))
+        .map(|(page, score)| (page.clone(), score * n as f64))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(path: &str) -> Url {
+        Url::parse("https://example.com/")
+            .unwrap()
+            .join(path)
+            .unwrap()
+    }
+
+    /// The pages linking to each URL, from `(from, to)` links.
+    fn referrers(links: &[(&str, &str)]) -> HashMap<Url, HashSet<Url>> {
+        let mut referrers: HashMap<Url, HashSet<Url>> = HashMap::new();
+        for (from, to) in links {
+            referrers.entry(url(to)).or_default().insert(url(from));
+        }
+        referrers
+    }
+
+    #[test]
+    fn scores_average_one() {
+        let pages = [url("/"), url("/a"), url("/b")];
+        let ranks = page_rank(
+            &pages,
+            &referrers(&[("/", "/a"), ("/", "/b"), ("/a", "/"), ("/b", "/")]),
+        );
+        let total: f64 = ranks.values().sum();
+        assert!((total - 3.0).abs() < 1e-6);
+        assert!(ranks[&url("/")] > ranks[&url("/a")]);
+        assert!((ranks[&url("/a")] - ranks[&url("/b")]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn under_linked_pages_score_lowest() {
+        let pages = [url("/"), url("/a"), url("/b"), url("/orphan")];
+        let ranks = page_rank(
+            &pages,
+            &referrers(&[
+                ("/", "/a"),
+                ("/", "/b"),
+                ("/a", "/b"),
+                ("/b", "/"),
+                ("/orphan", "/"),
+                // Self-links and links to pages not scanned don't count.
+                ("/orphan", "/orphan"),
+                ("/a", "/gone"),
+            ]),
+        );
+        assert_eq!(ranks.len(), 4);
+        let lowest = (ranks.iter())
+            .min_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(page, _)| page);
+        assert_eq!(lowest, Some(&url("/orphan")));
+        assert!(ranks[&url("/b")] > ranks[&url("/a")]);
+    }
+
+    #[test]
+    fn no_pages_no_ranks() {
+        assert!(page_rank(&[], &HashMap::new()).is_empty());
+        let ranks = page_rank(&[url("/"), url("/")], &HashMap::new());
+        assert_eq!(ranks.len(), 1);
+        assert!((ranks[&url("/")] - 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,56 @@
+use crate::error::{HtmlFindError, Result};
+use crate::find::collapse_whitespace;
+use scraper::{Html, Selector};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+use url::Url;
+
+/// Named CSS selectors read from each scraped page, e.g. `title = "h1"`.
+#[derive(Debug, Clone)]
+pub struct Recipe {
+    fields: Vec<(String, Selector)>,
+}
+
+impl Recipe {
+    /// Reads a TOML file mapping field names to selectors.
+    pub fn load(path: &Path) -> Result<Recipe> {
+        let text = std::fs::read_to_string(path).map_err(|source| HtmlFindError::File {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let fields: BTreeMap<String, String> =
+            toml::from_str(&text).map_err(|source| HtmlFindError::Config {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        let fields = fields
+            .into_iter()
+            .map(|(name, selector)| {
+                Selector::parse(&selector)
+                    .map(|parsed| (name, parsed))
+                    .map_err(|err| HtmlFindError::InvalidSelector {
+                        selector: selector.clone(),
+                        message: err.to_string(),
+                    })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Recipe { fields })
+    }
+
+    /// One row for the page at `url`: its URL and the text of the first element
+    /// each selector matches, `null` when none does.
+    pub fn apply(&self, url: &Url, html: &str) -> Value {
+        let document = Html::parse_document(html);
+        let mut row = Map::new();
+        row.insert("url".to_string(), Value::String(url.to_string()));
+        for (name, selector) in &self.fields {
+            let value = document
+                .select(selector)
+                .next()
+                .map(|element| collapse_whitespace(&element.text().collect::<String>()));
+            row.insert(name.clone(), value.map_or(Value::Null, Value::String));
+        }
+        Value::Object(row)
+    }
+}
@@ -0,0 +1,138 @@
+use crate::report::csv_field;
+use std::collections::BTreeMap;
+use url::Url;
+
+/// How a redirect map is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectFormat {
+    /// `from,to` rows.
+    Csv,
+    /// `location` blocks answering 301, to include in a `server` block.
+    Nginx,
+    /// `RedirectMatch 301` lines of mod_alias.
+    Apache,
+}
+
+/// `moved`, each old URL and where it now permanently lives, in `format`.
+/// The rules of the server formats match paths, so they are grouped under
+/// a comment naming each old host; a target on the same host is given as a
+/// path, any other in full.
+pub fn render(moved: &BTreeMap<Url, Url>, format: RedirectFormat) -> String {
+    if format == RedirectFormat::Csv {
+        let mut csv = String::from("from,to\n");
+        for (from, to) in moved {
+            csv.push_str(&format!(
+                "{},{}\n",
+                csv_field(from.as_str()),
+                csv_field(to.as_str())
+            ));
+        }
+        return csv;
+    }
+    let mut by_host: BTreeMap<&str, Vec<(&Url, &Url)>> = BTreeMap::new();
+    for (from, to) in moved {
+        by_host
+            .entry(from.host_str().unwrap_or_default())
+            .or_default()
+            .push((from, to));
+    }
+    let mut rules = String::new();
+    for (host, moved) in by_host {
+        if !rules.is_empty() {
+            rules.push('\n');
+        }
+        rules.push_str(&format!("# {}\n", host));
+        for (from, to) in moved {
+            let target = if to.origin() == from.origin() {
+                to[url::Position::BeforePath..].to_string()
+            } else {
+                to.to_string()
+            };
+            let rule = match format {
+                RedirectFormat::Nginx => {
+                    format!(
+                        "location = {} {{ return 301 {}; }}\n",
+                        quoted(from.path()),
+                        quoted(&target)
+                    )
+                }
+                _ => {
+                    let path = format!("^{}$", regex::escape(from.path()));
+                    format!("RedirectMatch 301 {} {}\n", quoted(&path), quoted(&target))
+                }
+            };
+            rules.push_str(&rule);
+        }
+    }
+    rules
+}
+
+/// `value` in double quotes when it holds whitespace or quotes, which would end the argument.
+fn quoted(value: &str) -> String {
+    if value.contains(|c: char| c.is_whitespace() || c == '"' || c == '\'' || c == ';') {
+        format!("\"{}\"", value.replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moved() -> BTreeMap<Url, Url> {
+        let url = |url| Url::parse(url).unwrap();
+        BTreeMap::from([
+            (
+                url("https://example.com/old"),
+                url("https://example.com/new?tab=1"),
+            ),
+            (
+                url("https://example.com/a b"),
+                url("https://docs.example.com/b"),
+            ),
+            (
+                url("https://blog.example.com/2019/"),
+                url("https://example.com/blog/"),
+            ),
+        ])
+    }
+
+    #[test]
+    fn renders_csv_rows() {
+        assert_eq!(
+            render(&moved(), RedirectFormat::Csv),
+            "from,to\n\
+             https://blog.example.com/2019/,https://example.com/blog/\n\
+             https://example.com/a%20b,https://docs.example.com/b\n\
+             https://example.com/old,https://example.com/new?tab=1\n"
+        );
+    }
+
+    #[test]
+    fn renders_nginx_locations_by_host() {
+        assert_eq!(
+            render(&moved(), RedirectFormat::Nginx),
+            "# blog.example.com\n\
+             location = /2019/ { return 301 https://example.com/blog/; }\n\
+             \n\
+             # example.com\n\
+             location = /a%20b { return 301 https://docs.example.com/b; }\n\
+             location = /old { return 301 /new?tab=1; }\n"
+        );
+    }
+
+    #[test]
+    fn renders_apache_rules_with_escaped_paths() {
+        let url = |url| Url::parse(url).unwrap();
+        let moved = BTreeMap::from([(
+            url("https://example.com/a.html"),
+            url("https://example.com/b"),
+        )]);
+        assert_eq!(
+            render(&moved, RedirectFormat::Apache),
+            "# example.com\nRedirectMatch 301 ^/a\\.html$ /b\n"
+        );
+        assert_eq!(quoted("/a;b"), "\"/a;b\"");
+    }
+}
@@ -0,0 +1,166 @@
+use crate::error::{HtmlFindError, Result};
+use crate::page::{is_html, Fetched};
+use chromiumoxide::error::CdpError;
+use chromiumoxide::page::ScreenshotParams;
+use chromiumoxide::{Browser, BrowserConfig, Page};
+use futures::StreamExt;
+use reqwest::header::CONTENT_TYPE;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::warn;
+use url::Url;
+
+pub const DEFAULT_RENDER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often a loaded page is looked at again while waiting for it to settle.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What to wait for once a page has loaded, before reading it, so links
+/// injected later and lazily loaded content are there.
+#[derive(Debug, Clone)]
+pub struct Wait {
+    /// Until an element matches this CSS selector.
+    pub selector: Option<String>,
+    /// Until no request has completed for this long.
+    pub network_idle: Option<Duration>,
+    /// Longest to wait before reading the page as it is.
+    pub timeout: Duration,
+}
+
+impl Default for Wait {
+    fn default() -> Self {
+        Wait {
+            selector: None,
+            network_idle: None,
+            timeout: DEFAULT_RENDER_TIMEOUT,
+        }
+    }
+}
+
+/// A headless Chrome that fetched pages are loaded into, so that links their
+/// scripts add, as on single-page apps, are found too. Chrome requests the
+/// pages itself, without the run's headers.
+#[derive(Clone)]
+pub struct Renderer {
+    browser: Arc<Browser>,
+    wait: Wait,
+}
+
+impl fmt::Debug for Renderer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Renderer").finish_non_exhaustive()
+    }
+}
+
+fn failed(url: &Url) -> impl Fn(CdpError) -> HtmlFindError + '_ {
+    move |err| HtmlFindError::Render {
+        url: url.clone(),
+        message: err.to_string(),
+    }
+}
+
+impl Renderer {
+    /// Starts Chrome, found on the `PATH` or in the usual places unless
+    /// `chrome` names the executable. Pages are read once `wait` is over.
+    pub async fn launch(chrome: Option<&Path>, wait: Wait) -> Result<Renderer> {
+        let browser_error = |message: String| HtmlFindError::Browser { message };
+        let mut config = BrowserConfig::builder();
+        if let Some(chrome) = chrome {
+            config = config.chrome_executable(chrome);
+        }
+        let (browser, mut handler) = Browser::launch(config.build().map_err(browser_error)?)
+            .await
+            .map_err(|err| browser_error(err.to_string()))?;
+        // The connection to Chrome only makes progress while its events are polled.
+        tokio::spawn(async move { while handler.next().await.is_some() {} });
+        Ok(Renderer {
+            browser: Arc::new(browser),
+            wait,
+        })
+    }
+
+    /// `page` with its body replaced by the DOM Chrome ends up with once the
+    /// page has loaded and settled. Failed and non-HTML responses are left as
+    /// they are.
+    pub async fn render(&self, mut page: Fetched) -> Result<Fetched> {
+        let content_type = (page.headers.get(CONTENT_TYPE)).and_then(|value| value.to_str().ok());
+        if !page.status.is_success() || !is_html(content_type) {
+            return Ok(page);
+        }
+        page.body = self
+            .load(&page.url.clone(), async |tab| tab.content().await)
+            .await?;
+        Ok(page)
+    }
+
+    /// Loads `url` and saves a PNG of the whole page, once settled, to `path`.
+    pub async fn screenshot(&self, url: &Url, path: &Path) -> Result<()> {
+        let params = ScreenshotParams::builder().full_page(true).build();
+        self.load(url, async |tab| tab.save_screenshot(params, path).await)
+            .await
+            .map(drop)
+    }
+
+    /// Opens `url` in a new tab and, once it has settled, hands the tab to `read`.
+    async fn load<T>(
+        &self,
+        url: &Url,
+        read: impl AsyncFnOnce(&Page) -> chromiumoxide::Result<T>,
+    ) -> Result<T> {
+        let tab = (self.browser.new_page(url.as_str()).await).map_err(failed(url))?;
+        let read = async {
+            tab.wait_for_navigation().await?;
+            self.settle(&tab, url).await?;
+            read(&tab).await
+        };
+        let read = read.await;
+        let _ = tab.close().await;
+        read.map_err(failed(url))
+    }
+
+    /// Waits as `self.wait` says, giving up with a warning at its timeout.
+    async fn settle(&self, tab: &Page, url: &Url) -> chromiumoxide::Result<()> {
+        let deadline = Instant::now() + self.wait.timeout;
+        if let Some(selector) = &self.wait.selector {
+            while tab.find_element(selector.as_str()).await.is_err() {
+                if Instant::now() >= deadline {
+                    warn!("gave up waiting for {} on {}", selector, url);
+                    return Ok(());
+                }
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+        let Some(idle) = self.wait.network_idle else {
+            return Ok(());
+        };
+        // Each request that completes adds a resource timing entry.
+        let completed = || async {
+            let count = tab.evaluate("performance.getEntriesByType('resource').length");
+            count.await?.into_value::<u64>().map_err(CdpError::from)
+        };
+        let (mut requests, mut quiet_since) = (completed().await?, Instant::now());
+        while quiet_since.elapsed() < idle {
+            if Instant::now() >= deadline {
+                warn!("gave up waiting for the network to go idle on {}", url);
+                return Ok(());
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let now = completed().await?;
+            if now != requests {
+                (requests, quiet_since) = (now, Instant::now());
+            }
+        }
+        Ok(())
+    }
+
+    /// Shuts Chrome down once no other clone is left, or else leaves it to be
+    /// killed when the last one is dropped.
+    pub async fn close(self) {
+        if let Some(mut browser) = Arc::into_inner(self.browser) {
+            let _ = browser.close().await;
+            let _ = browser.wait().await;
+        }
+    }
+}
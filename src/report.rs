@@ -0,0 +1,703 @@
+use crate::audit::Issue;
+use crate::budget::PageWeight;
+use crate::caching::CachingIssue;
+use crate::canonical::CanonicalIssue;
+use crate::check::{CheckMode, Validators};
+use crate::duplicates::DuplicateLinks;
+use crate::error::{HtmlFindError, Result};
+use crate::expect::Unmet;
+use crate::grep::GrepMatch;
+use crate::health::{host_health, print_table};
+use crate::signed::Expiring;
+use crate::sitemap::Coverage;
+use crate::structure::DeepPage;
+use console::Style;
+use html_find_core::is_ok_status;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// Most slow URLs listed in the summary.
+const SLOWEST_SHOWN: usize = 10;
+
+/// Most pages listed in the summary as the least linked.
+const LEAST_LINKED_SHOWN: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub url: Url,
+    #[serde(with = "status_code")]
+    pub status: Option<StatusCode>,
+    pub error: Option<String>,
+    /// What kind of failure the error was, when there is one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure: Option<Failure>,
+    /// Taken from the cache of an earlier run instead of requested.
+    pub cached: bool,
+    #[serde(default)]
+    pub validators: Validators,
+    /// Where the URL was linked from, as `file:line` when checking local files.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub found_in: Vec<String>,
+    /// Where the request ended up after following redirects, when it was redirected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirected_to: Option<Url>,
+    /// Milliseconds until the response headers arrived.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<u64>,
+    /// The `Content-Type` the response was sent with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Requests made after the first, when the first failed in a way worth retrying.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub retries: u32,
+    /// The status or error that led to the last request, when there were retries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_cause: Option<String>,
+    /// Answered only when asked again with the headers a browser sends.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub as_browser: bool,
+    /// Answered with the status the run expects of the URL, such as 401,
+    /// so it counts as OK.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub expected: bool,
+    /// The bot protection service, such as Cloudflare, that likely answered
+    /// with a challenge instead of the site, so the URL may well work in a browser.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked: Option<String>,
+    /// The size in bytes of the whole body, as the response gave it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// The SHA-256 digest of the body in hex, when it was downloaded to verify one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// The Wayback Machine's snapshot closest to now, when a broken external link was looked up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived: Option<Url>,
+    /// How the URL's host was checked, when it was only resolved or connected to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<CheckMode>,
+    /// The pages linking to the URL, when it failed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub linked_from: Vec<Url>,
+    /// The response headers the run was asked to keep, by lowercase name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+}
+
+fn is_zero(count: &u32) -> bool {
+    *count == 0
+}
+
+/// The URL followed by its status or error.
+impl fmt::Display for CheckResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.status, &self.error) {
+            (Some(status), _) => write!(f, "{} ({})", self.url, status),
+            (None, Some(error)) => write!(f, "{} ({})", self.url, error),
+            (None, None) => write!(f, "{}", self.url),
+        }
+    }
+}
+
+/// (De)serializes a status as its numeric code.
+mod status_code {
+    use reqwest::StatusCode;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        status: &Option<StatusCode>,
+        s: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        status.map(|status| status.as_u16()).serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> std::result::Result<Option<StatusCode>, D::Error> {
+        Option::<u16>::deserialize(d)?
+            .map(|code| StatusCode::from_u16(code).map_err(D::Error::custom))
+            .transpose()
+    }
+}
+
+impl CheckResult {
+    /// A result for `url` that says nothing about it yet, for the other
+    /// constructors to fill in.
+    pub(crate) fn blank(url: Url) -> Self {
+        CheckResult {
+            url,
+            status: None,
+            error: None,
+            failure: None,
+            cached: false,
+            validators: Validators::default(),
+            found_in: vec![],
+            redirected_to: None,
+            elapsed_ms: None,
+            content_type: None,
+            retries: 0,
+            retry_cause: None,
+            as_browser: false,
+            expected: false,
+            blocked: None,
+            size: None,
+            sha256: None,
+            archived: None,
+            mode: None,
+            linked_from: vec![],
+            headers: BTreeMap::new(),
+        }
+    }
+
+    pub fn new(url: Url, status: StatusCode) -> Self {
+        CheckResult {
+            status: Some(status),
+            ..CheckResult::blank(url)
+        }
+    }
+
+    /// A URL that could not be requested at all.
+    pub fn failed(url: Url, error: &HtmlFindError) -> Self {
+        CheckResult {
+            error: Some(error.to_string()),
+            failure: Some(error.failure()),
+            ..CheckResult::blank(url)
+        }
+    }
+
+    /// A host that was resolved, or connected to, without a request.
+    pub fn reached(url: Url, mode: CheckMode) -> Self {
+        CheckResult {
+            mode: Some(mode),
+            ..CheckResult::blank(url)
+        }
+    }
+
+    /// The outcome in columns: status code, time taken and URL, followed by
+    /// the error or redirect target. The code is colored when `color` is set.
+    pub fn row(&self, color: bool) -> String {
+        let code = match (self.status, self.mode) {
+            (Some(status), _) => status.as_u16().to_string(),
+            (None, Some(CheckMode::Dns)) if self.is_ok() => "DNS".to_string(),
+            (None, Some(CheckMode::Tcp)) if self.is_ok() => "TCP".to_string(),
+            (None, _) => "ERR".to_string(),
+        };
+        let style = if self.blocked.is_some() {
+            Style::new().yellow()
+        } else if !self.is_ok() {
+            Style::new().red()
+        } else if self.redirected_to.is_some() {
+            Style::new().yellow()
+        } else {
+            Style::new().green()
+        };
+        let time = match (self.cached, self.elapsed_ms) {
+            (true, _) => "cached".to_string(),
+            (false, Some(ms)) => format!("{} ms", ms),
+            (false, None) => String::new(),
+        };
+        let mut row = format!(
+            "{}  {:>8}  {}",
+            style.force_styling(color).apply_to(format!("{:>3}", code)),
+            time,
+            self.url
+        );
+        let mut note = match (&self.error, &self.redirected_to) {
+            (Some(error), _) => Some(error.clone()),
+            _ if self.blocked.is_some() => Some(format!(
+                "blocked (likely {} bot protection)",
+                self.blocked.as_deref().unwrap_or_default()
+            )),
+            (None, Some(to)) => Some(format!("-> {}", to)),
+            (None, None) => None,
+        };
+        if self.expected {
+            note = note.or(Some("(as expected)".to_string()));
+        }
+        if self.as_browser {
+            note = Some(match note {
+                Some(note) => format!("{} (as a browser)", note),
+                None => "(as a browser)".to_string(),
+            });
+        }
+        if let Some(cause) = &self.retry_cause {
+            let attempts = format!("{} attempts, the last after {}", self.retries + 1, cause);
+            note = Some(match note {
+                Some(note) => format!("{} ({})", note, attempts),
+                None => format!("({})", attempts),
+            });
+        }
+        if let Some(note) = note {
+            let dim = Style::new().dim().force_styling(color);
+            row.push_str(&format!("  {}", dim.apply_to(note)));
+        }
+        row
+    }
+
+    /// A status [`is_ok_status`] accepts, or whatever status was expected.
+    /// Without a request, whether the host was reached.
+    pub fn is_ok(&self) -> bool {
+        if self.expected {
+            return true;
+        }
+        if self.mode.is_some() {
+            return self.error.is_none();
+        }
+        (self.status).is_some_and(|status| is_ok_status(status.as_u16()))
+    }
+
+    /// Not OK, and not merely blocked by bot protection, so counted as
+    /// broken in [`Report::broken`].
+    pub fn is_broken(&self) -> bool {
+        !self.is_ok() && self.blocked.is_none()
+    }
+}
+
+/// `, N blocked` to follow the broken count, when any were.
+fn blocked_count(blocked: usize) -> String {
+    if blocked == 0 {
+        String::new()
+    } else {
+        format!(", {} blocked", blocked)
+    }
+}
+
+/// Quotes `field` for CSV when it holds a separator, quote or line break.
+pub(crate) fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// What kept a URL from being checked, as far as its error tells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Failure {
+    /// The request, or connecting for it, took too long.
+    Timeout,
+    /// The host couldn't be resolved.
+    Dns,
+    /// The host was resolved but refused or dropped the connection.
+    Connect,
+    Other,
+}
+
+/// Why a run ended before every discovered URL was checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    Interrupted,
+    DeadlineReached,
+    /// The run read as many bytes as it was allowed to.
+    BandwidthExceeded,
+}
+
+/// Results gathered by a run, complete or not.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Report {
+    pub results: Vec<CheckResult>,
+    /// Distinct URLs found on the scanned pages, checked or not.
+    pub discovered: usize,
+    /// Pages whose links were extracted.
+    pub pages: Vec<Url>,
+    /// Pages missing content the run's expectations ask for.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unmet: Vec<Unmet>,
+    /// Lines of fetched pages matching the run's `--grep` pattern.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matches: Vec<GrepMatch>,
+    /// URLs the sitemaps list that don't belong there, when they were audited.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sitemap_issues: Vec<Issue>,
+    /// URLs cached wrongly for what they are, when caching was audited.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub caching_issues: Vec<CachingIssue>,
+    /// Pages repeating links to one URL or linking to themselves, when links were audited.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub duplicate_links: Vec<DuplicateLinks>,
+    /// Pages whose canonical URLs disagree with the site, when they were audited.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub canonical_issues: Vec<CanonicalIssue>,
+    /// The link equity of each page scanned, averaging 1, when pages were ranked.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub page_rank: BTreeMap<Url, f64>,
+    /// Pages without links to other internal pages, when they were looked for.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dead_ends: Vec<Url>,
+    /// Pages only reached by following many links, deepest first, when they were looked for.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deep_pages: Vec<DeepPage>,
+    /// Pages loading more than their budget allows, heaviest first, when pages were weighed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub over_budget: Vec<PageWeight>,
+    /// Signed links that have expired or soon will, when the run looks for them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expiring: Vec<Expiring>,
+    /// How the pages the sitemaps list and those a crawl reached differ, when compared.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sitemap_coverage: Option<Coverage>,
+    /// A screenshot of the page each broken URL was found on, or of its own
+    /// error page, when pages were rendered.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub screenshots: BTreeMap<Url, PathBuf>,
+    /// Count responses bot protection likely blocked as OK rather than blocked.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub blocked_ok: bool,
+    /// Milliseconds past which a response counts as slow, when a run looks for slow ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slow_after_ms: Option<u64>,
+    /// URLs a dry run found it would check.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub planned: Vec<Url>,
+    /// References to each external host, when a run counts them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub domains: BTreeMap<String, usize>,
+    pub stopped: Option<StopReason>,
+}
+
+impl Report {
+    /// Reads a report written by [`Report::save`].
+    pub fn load(path: &Path) -> Result<Report> {
+        let bytes = std::fs::read(path).map_err(|source| HtmlFindError::File {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        serde_json::from_slice(&bytes).map_err(|source| HtmlFindError::Json {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Writes the report as JSON.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let bytes = serde_json::to_vec_pretty(self).expect("report serializes");
+        std::fs::write(path, bytes).map_err(|source| HtmlFindError::File {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Results that failed, other than those bot protection blocked.
+    pub fn broken(&self) -> impl Iterator<Item = &CheckResult> {
+        self.results.iter().filter(|r| r.is_broken())
+    }
+
+    /// The broken results linked from the most pages first, so fixing them
+    /// in order clears the most pages of errors soonest.
+    pub fn by_impact(&self) -> Vec<&CheckResult> {
+        let mut broken: Vec<&CheckResult> = self.broken().collect();
+        broken.sort_by_key(|result| std::cmp::Reverse(result.linked_from.len()));
+        broken
+    }
+
+    /// The ranked pages by their link equity, the lowest first.
+    pub fn least_linked(&self) -> impl Iterator<Item = (&Url, f64)> {
+        let mut pages: Vec<(&Url, f64)> = (self.page_rank.iter())
+            .map(|(page, score)| (page, *score))
+            .collect();
+        pages.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(b.0)));
+        pages.into_iter()
+    }
+
+    /// Results bot protection likely blocked, unless the run counts them as OK.
+    pub fn blocked(&self) -> impl Iterator<Item = &CheckResult> {
+        (self.results.iter()).filter(|r| !self.blocked_ok && !r.is_ok() && r.blocked.is_some())
+    }
+
+    /// Whether `result` was requested and took longer than [`Report::slow_after_ms`].
+    pub fn is_slow(&self, result: &CheckResult) -> bool {
+        match (self.slow_after_ms, result.elapsed_ms) {
+            (Some(threshold), Some(elapsed)) => !result.cached && elapsed > threshold,
+            _ => false,
+        }
+    }
+
+    /// The slow results, slowest first.
+    pub fn slow(&self) -> Vec<&CheckResult> {
+        let mut slow: Vec<&CheckResult> = self.results.iter().filter(|r| self.is_slow(r)).collect();
+        slow.sort_by_key(|r| std::cmp::Reverse(r.elapsed_ms));
+        slow
+    }
+
+    /// Share of discovered URLs that were checked, `1.0` when nothing was found.
+    pub fn coverage(&self) -> f64 {
+        if self.discovered == 0 {
+            1.0
+        } else {
+            self.results.len() as f64 / self.discovered as f64
+        }
+    }
+
+    /// One row per result: URL, status code, error, whether it was cached and
+    /// where it was found, `;`-separated.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("url,status,error,cached,found_in\n");
+        for result in &self.results {
+            let row = [
+                result.url.to_string(),
+                result
+                    .status
+                    .map_or_else(String::new, |status| status.as_u16().to_string()),
+                result.error.clone().unwrap_or_default(),
+                result.cached.to_string(),
+                result.found_in.join(";"),
+            ];
+            let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// A standalone page with the counts and a table of the broken results,
+    /// those linked from the most pages first, linking to their screenshots
+    /// and archived copies when there are any.
+    pub fn to_html(&self) -> String {
+        let broken = self.by_impact();
+        let blocked = self.blocked().count();
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Link report</title></head><body>\n\
+             <p>Checked {} URLs: {} OK, {} broken{}</p>\n",
+            self.results.len(),
+            self.results.len() - broken.len() - blocked,
+            broken.len(),
+            blocked_count(blocked)
+        );
+        if !broken.is_empty() {
+            let screenshots = !self.screenshots.is_empty();
+            let archived = broken.iter().any(|result| result.archived.is_some());
+            html.push_str(
+                "<table>\n<tr><th>URL</th><th>Status</th><th>Pages</th><th>Found in</th>",
+            );
+            if screenshots {
+                html.push_str("<th>Screenshot</th>");
+            }
+            if archived {
+                html.push_str("<th>Archived</th>");
+            }
+            html.push_str("</tr>\n");
+            for result in broken {
+                let status = match (&result.status, &result.error) {
+                    (Some(status), _) => status.to_string(),
+                    (None, Some(error)) => error.clone(),
+                    (None, None) => String::new(),
+                };
+                html.push_str(&format!(
+                    "<tr><td><a href=\"{url}\">{url}</a></td><td>{}</td><td>{}</td><td>{}</td>",
+                    escape_html(&status),
+                    result.linked_from.len(),
+                    escape_html(&result.found_in.join(", ")),
+                    url = escape_html(result.url.as_str()),
+                ));
+                if screenshots {
+                    let link = self.screenshots.get(&result.url).map(|path| {
+                        let path = escape_html(&path.to_string_lossy());
+                        format!("<a href=\"{}\">view</a>", path)
+                    });
+                    html.push_str(&format!("<td>{}</td>", link.unwrap_or_default()));
+                }
+                if archived {
+                    let link = result.archived.as_ref().map(|archived| {
+                        format!(
+                            "<a href=\"{}\">snapshot</a>",
+                            escape_html(archived.as_str())
+                        )
+                    });
+                    html.push_str(&format!("<td>{}</td>", link.unwrap_or_default()));
+                }
+                html.push_str("</tr>\n");
+            }
+            html.push_str("</table>\n");
+        }
+        html.push_str("</body></html>\n");
+        html
+    }
+
+    /// Prints the external hosts, most referenced first.
+    pub fn print_domains(&self) {
+        let mut domains: Vec<(&String, &usize)> = self.domains.iter().collect();
+        domains.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for (host, count) in domains {
+            println!("{:>6}  {}", count, host);
+        }
+    }
+
+    /// Prints the counts and the broken results, those linked from the most pages first.
+    pub fn print_summary(&self) {
+        let broken = self.by_impact();
+        match self.stopped {
+            Some(StopReason::Interrupted) => println!("Interrupted, partial results:"),
+            Some(StopReason::DeadlineReached) => println!("Deadline reached, partial results:"),
+            Some(StopReason::BandwidthExceeded) => {
+                println!("Bandwidth cap reached, partial results:")
+            }
+            None => {}
+        }
+        let blocked: Vec<&CheckResult> = self.blocked().collect();
+        println!(
+            "Checked {} URLs: {} OK, {} broken{}",
+            self.results.len(),
+            self.results.len() - broken.len() - blocked.len(),
+            broken.len(),
+            blocked_count(blocked.len())
+        );
+        if self.stopped.is_some() {
+            println!(
+                "Coverage: {} of {} discovered URLs checked ({:.1}%) across {} pages",
+                self.results.len(),
+                self.discovered,
+                self.coverage() * 100.0,
+                self.pages.len()
+            );
+        }
+        for result in broken {
+            println!("  {}", result.row(console::colors_enabled()));
+            match result.linked_from.len() {
+                0 => {}
+                1 => println!("    linked from 1 page"),
+                pages => println!("    linked from {} pages", pages),
+            }
+            for location in &result.found_in {
+                println!("    in {}", location);
+            }
+            if let Some(archived) = &result.archived {
+                println!("    archived at {}", archived);
+            }
+        }
+        let slow = self.slow();
+        if let (Some(threshold), false) = (self.slow_after_ms, slow.is_empty()) {
+            println!(
+                "{} URLs slower than {} ms, slowest first:",
+                slow.len(),
+                threshold
+            );
+            for result in slow.iter().take(SLOWEST_SHOWN) {
+                println!("  {}", result.row(console::colors_enabled()));
+            }
+            if slow.len() > SLOWEST_SHOWN {
+                println!("  and {} more", slow.len() - SLOWEST_SHOWN);
+            }
+        }
+        if !blocked.is_empty() {
+            println!("{} URLs blocked, likely by bot protection", blocked.len());
+            for result in blocked {
+                println!("  {}", result.row(console::colors_enabled()));
+            }
+        }
+        if !self.results.is_empty() {
+            println!("By host:");
+            print_table(&host_health(&self.results));
+        }
+        if !self.unmet.is_empty() {
+            println!("{} content checks failed", self.unmet.len());
+            for unmet in &self.unmet {
+                println!("  {}", unmet);
+            }
+        }
+        if !self.caching_issues.is_empty() {
+            println!("{} caching issues", self.caching_issues.len());
+            for issue in &self.caching_issues {
+                println!("  {}", issue);
+            }
+        }
+        if !self.duplicate_links.is_empty() {
+            println!("{} pages with duplicate links", self.duplicate_links.len());
+            for duplicates in &self.duplicate_links {
+                println!("  {}", duplicates);
+            }
+        }
+        if !self.canonical_issues.is_empty() {
+            println!("{} canonical URL issues", self.canonical_issues.len());
+            for issue in &self.canonical_issues {
+                println!("  {}", issue);
+            }
+        }
+        if !self.page_rank.is_empty() {
+            println!("Least linked of {} pages ranked", self.page_rank.len());
+            for (page, score) in self.least_linked().take(LEAST_LINKED_SHOWN) {
+                println!("  {:>6.2}  {}", score, page);
+            }
+        }
+        if !self.dead_ends.is_empty() {
+            println!("{} dead-end pages", self.dead_ends.len());
+            for page in &self.dead_ends {
+                println!("  {}", page);
+            }
+        }
+        if !self.deep_pages.is_empty() {
+            println!("{} deep pages", self.deep_pages.len());
+            for deep in &self.deep_pages {
+                println!("  {}", deep);
+            }
+        }
+        if !self.over_budget.is_empty() {
+            println!("{} pages over budget", self.over_budget.len());
+            for weight in &self.over_budget {
+                println!("  {}", weight);
+            }
+        }
+        if !self.expiring.is_empty() {
+            println!("{} signed URLs expired or expiring", self.expiring.len());
+            for expiring in &self.expiring {
+                println!("  {}", expiring);
+            }
+        }
+        if let Some(coverage) = &self.sitemap_coverage {
+            coverage.print();
+        }
+        if !self.sitemap_issues.is_empty() {
+            println!("{} sitemap issues", self.sitemap_issues.len());
+            for issue in &self.sitemap_issues {
+                println!("  {}", issue);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_csv_fields_only_when_needed() {
+        assert_eq!(csv_field("https://example.com/a"), "https://example.com/a");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field(""), "");
+    }
+
+    #[test]
+    fn broken_results_rank_by_impact() {
+        let url = |path: &str| {
+            Url::parse("https://example.com/")
+                .unwrap()
+                .join(path)
+                .unwrap()
+        };
+        let mut once = CheckResult::new(url("/once"), StatusCode::NOT_FOUND);
+        once.linked_from = vec![url("/")];
+        let mut often = CheckResult::new(url("/often"), StatusCode::GONE);
+        often.linked_from = vec![url("/"), url("/a"), url("/b")];
+        let report = Report {
+            results: vec![CheckResult::new(url("/ok"), StatusCode::OK), once, often],
+            ..Report::default()
+        };
+        let ranked: Vec<&str> = report.by_impact().iter().map(|r| r.url.path()).collect();
+        assert_eq!(ranked, ["/often", "/once"]);
+    }
+}
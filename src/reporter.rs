@@ -0,0 +1,116 @@
+use crate::error::Result;
+use crate::grep::GrepMatch;
+use crate::notify::{post_webhook, Summary};
+use crate::report::{CheckResult, Report};
+use futures::future::BoxFuture;
+use std::path::PathBuf;
+use tracing::{info, warn};
+use url::Url;
+
+/// Somewhere a run's outcomes go, given to [`Checker::with_reporter`]; a run
+/// can have several. Each outcome is given as it comes in, and the report
+/// once the run ends.
+///
+/// [`Checker::with_reporter`]: crate::Checker::with_reporter
+pub trait Reporter: Send {
+    /// A URL was checked, taken from the cache, or couldn't be fetched.
+    fn on_result(&mut self, _result: &CheckResult) {}
+
+    /// A recipe turned `page` into `row`.
+    fn on_row(&mut self, _page: &Url, _row: &serde_json::Value) {}
+
+    /// Grep found `found` on a page.
+    fn on_match(&mut self, _found: &GrepMatch) {}
+
+    /// `link` was found when listing links instead of checking them.
+    fn on_listed(&mut self, _link: &Url) {}
+
+    /// The run ended with `report`. An error fails the run.
+    fn finish<'a>(&'a mut self, _report: &'a Report) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Logs each outcome as a row: OK ones at info level, broken ones as
+/// warnings. Runs with `print_progress` have one from the start.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Console;
+
+impl Reporter for Console {
+    fn on_result(&mut self, result: &CheckResult) {
+        let row = result.row(console::colors_enabled_stderr());
+        if result.is_ok() {
+            info!("{}", row);
+        } else {
+            warn!("{}", row);
+        }
+    }
+}
+
+/// Prints what the run outputs as it goes, a line each on stdout: recipe
+/// rows, grep matches and listed links, as the command line does.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stdout;
+
+impl Reporter for Stdout {
+    fn on_row(&mut self, _page: &Url, row: &serde_json::Value) {
+        println!("{}", row);
+    }
+
+    fn on_match(&mut self, found: &GrepMatch) {
+        println!("{}", found);
+    }
+
+    fn on_listed(&mut self, link: &Url) {
+        println!("{}", link);
+    }
+}
+
+/// Saves the report as JSON, as [`Report::save`] does.
+#[derive(Debug, Clone)]
+pub struct JsonFile {
+    pub path: PathBuf,
+}
+
+impl JsonFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        JsonFile { path: path.into() }
+    }
+}
+
+impl Reporter for JsonFile {
+    fn finish<'a>(&'a mut self, report: &'a Report) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move { report.save(&self.path) })
+    }
+}
+
+/// Posts a [`Summary`] of the run to a webhook.
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    client: reqwest::Client,
+    url: Url,
+    /// The URLs the run started from, named in the summary.
+    urls: Vec<Url>,
+    findings: bool,
+}
+
+impl Webhook {
+    /// Posts to `url` with `client`, including every broken result when `findings` is set.
+    pub fn new(client: reqwest::Client, url: Url, urls: &[Url], findings: bool) -> Self {
+        Webhook {
+            client,
+            url,
+            urls: urls.to_vec(),
+            findings,
+        }
+    }
+}
+
+impl Reporter for Webhook {
+    fn finish<'a>(&'a mut self, report: &'a Report) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let summary = Summary::new(&self.urls, report, self.findings);
+            post_webhook(&self.client, &self.url, &summary).await
+        })
+    }
+}
@@ -0,0 +1,193 @@
+use crate::check::{check_host, check_link, Body, CheckMode, Retry, Validators};
+use crate::http::Http;
+use crate::local::LocalSite;
+use crate::report::CheckResult;
+use futures::future::BoxFuture;
+use reqwest::header::HeaderName;
+use reqwest::StatusCode;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use url::Url;
+
+/// Checks the links it handles instead of the run's own HTTP checks, e.g.
+/// for links of another scheme such as `s3://`, or as a test double.
+/// Checkers are given to [`Checker::with_resource_checker`]; links none of
+/// them handle are requested as usual.
+///
+/// [`Checker::with_resource_checker`]: crate::Checker::with_resource_checker
+pub trait ResourceChecker: Send + Sync {
+    /// Whether this checks `url`, e.g. judging by its scheme.
+    fn handles(&self, url: &Url) -> bool;
+
+    /// Checks `url`, one of those this handles.
+    fn check(&self, url: Url) -> BoxFuture<'static, CheckResult>;
+}
+
+/// Requests `http` and `https` links through its client, the way the run's
+/// own checks are made, e.g. to check links another checker has translated.
+/// Unless told otherwise, it keeps no response headers and retries as
+/// [`Retry::default`] does.
+#[derive(Debug, Clone)]
+pub struct HttpChecker {
+    http: Http,
+    capture: Arc<[HeaderName]>,
+    retry: Retry,
+}
+
+impl HttpChecker {
+    pub fn new(http: Http) -> Self {
+        HttpChecker {
+            http,
+            capture: Arc::new([]),
+            retry: Retry::default(),
+        }
+    }
+
+    /// Keeps these response headers in the results.
+    pub fn with_capture(mut self, capture: Arc<[HeaderName]>) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    /// Requests links again as `retry` says.
+    pub fn with_retry(mut self, retry: Retry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Checks `url`, sending `validators` so an unchanged one can answer 304
+    /// Not Modified, and reading as much of its body as `body` says.
+    pub fn request(
+        &self,
+        url: Url,
+        validators: Option<Validators>,
+        body: Body,
+    ) -> BoxFuture<'static, CheckResult> {
+        Box::pin(check_link(
+            self.http.clone(),
+            url,
+            validators,
+            body,
+            self.capture.clone(),
+            self.retry.clone(),
+        ))
+    }
+}
+
+impl ResourceChecker for HttpChecker {
+    fn handles(&self, url: &Url) -> bool {
+        matches!(url.scheme(), "http" | "https")
+    }
+
+    fn check(&self, url: Url) -> BoxFuture<'static, CheckResult> {
+        self.request(url, None, Body::Unread)
+    }
+}
+
+/// Checks links by only reaching their host, as `mode` says, the way the
+/// run checks links with `--check-mode dns` or `tcp`.
+#[derive(Debug, Clone, Copy)]
+pub struct HostChecker {
+    mode: CheckMode,
+}
+
+impl HostChecker {
+    pub fn new(mode: CheckMode) -> Self {
+        HostChecker { mode }
+    }
+}
+
+impl ResourceChecker for HostChecker {
+    fn handles(&self, url: &Url) -> bool {
+        url.has_host()
+    }
+
+    fn check(&self, url: Url) -> BoxFuture<'static, CheckResult> {
+        Box::pin(check_host(url, self.mode))
+    }
+}
+
+/// 200 OK when a server would find something at `path`, as
+/// [`LocalSite::exists`] tells, or else 404 Not Found.
+fn on_disk(url: Url, path: Option<PathBuf>) -> CheckResult {
+    let status = match path {
+        Some(path) if LocalSite::exists(&path) => StatusCode::OK,
+        _ => StatusCode::NOT_FOUND,
+    };
+    CheckResult::new(url, status)
+}
+
+/// Checks `file:` links by whether the file they name exists, or the
+/// directory with an `index.html`, as [`LocalChecker`] does, answering 200
+/// OK or 404 Not Found.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileChecker;
+
+impl ResourceChecker for FileChecker {
+    fn handles(&self, url: &Url) -> bool {
+        url.scheme() == "file"
+    }
+
+    fn check(&self, url: Url) -> BoxFuture<'static, CheckResult> {
+        let path = url.to_file_path().ok();
+        Box::pin(async move { on_disk(url, path) })
+    }
+}
+
+/// Checks the links into a [`LocalSite`], and `file:` links, by whether
+/// their files exist, answering 200 OK or 404 Not Found. This is how
+/// [`Checker::run_path`] checks the links between the pages it is given.
+///
+/// [`Checker::run_path`]: crate::Checker::run_path
+#[derive(Debug, Clone)]
+pub struct LocalChecker {
+    site: Arc<LocalSite>,
+}
+
+impl LocalChecker {
+    pub fn new(site: Arc<LocalSite>) -> Self {
+        LocalChecker { site }
+    }
+}
+
+impl ResourceChecker for LocalChecker {
+    fn handles(&self, url: &Url) -> bool {
+        self.site.resolve(url).is_some()
+    }
+
+    fn check(&self, url: Url) -> BoxFuture<'static, CheckResult> {
+        let path = self.site.resolve(&url);
+        Box::pin(async move { on_disk(url, path) })
+    }
+}
+
+/// Answers the links it was given with their status without any request,
+/// for tests of code that embeds the checker.
+#[derive(Debug, Clone, Default)]
+pub struct MockChecker {
+    statuses: HashMap<Url, StatusCode>,
+}
+
+impl MockChecker {
+    pub fn new() -> Self {
+        MockChecker::default()
+    }
+
+    /// Answers `url` with `status`.
+    pub fn with(mut self, url: Url, status: StatusCode) -> Self {
+        self.statuses.insert(url, status);
+        self
+    }
+}
+
+impl ResourceChecker for MockChecker {
+    fn handles(&self, url: &Url) -> bool {
+        self.statuses.contains_key(url)
+    }
+
+    fn check(&self, url: Url) -> BoxFuture<'static, CheckResult> {
+        let status = self.statuses[&url];
+        Box::pin(async move { CheckResult::new(url, status) })
+    }
+}
@@ -0,0 +1,43 @@
+use regex::Regex;
+use std::collections::HashSet;
+use std::sync::LazyLock;
+use url::Url;
+
+/// An `http` or `https` URL up to the quote, whitespace or bracket that ends it.
+static ABSOLUTE_URL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"https?://[A-Za-z0-9.-]+(?::\d+)?(?:[/?#][^\s"'`<>()\[\]{}\\^|$]*)?"#)
+        .expect("regex is valid")
+});
+
+/// The absolute URLs quoted in the JavaScript `source`, such as API endpoints
+/// and assets only scripts refer to. Escaped slashes, as in JSON, are read as
+/// slashes; hosts without a dot and URLs cut short by string concatenation or
+/// template placeholders are left out, as they are rarely real.
+pub fn urls(source: &str) -> HashSet<Url> {
+    let source = source.replace(r"\/", "/");
+    ABSOLUTE_URL
+        .find_iter(&source)
+        .filter(|found| !cut_short(&source[found.end()..]))
+        .map(|found| {
+            found
+                .as_str()
+                .trim_end_matches(['.', ',', ';', ':', '!', '?'])
+        })
+        .filter_map(|found| Url::parse(found).ok())
+        .filter(|url| {
+            url.host_str()
+                .is_some_and(|host| host.contains('.') && !host.ends_with('.'))
+        })
+        .collect()
+}
+
+/// Whether a URL followed by `rest` goes on in a placeholder, as in
+/// `` `https://host/${path}` ``, or is concatenated with more, as in
+/// `"https://host/" + path`.
+fn cut_short(rest: &str) -> bool {
+    if rest.starts_with("${") {
+        return true;
+    }
+    let after_quote = rest.strip_prefix(['"', '\'', '`']).unwrap_or(rest);
+    after_quote.trim_start().starts_with('+')
+}
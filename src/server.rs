@@ -0,0 +1,363 @@
+use crate::checker::{Checker, Event, Options, DEFAULT_CONCURRENCY, DEFAULT_EVENTS_CAPACITY};
+use crate::error::{HtmlFindError, Result};
+use crate::report::Report;
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+use url::Url;
+
+/// A check to run, the body of `POST /jobs`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobRequest {
+    /// Start URLs, crawled in one run.
+    pub urls: Vec<Url>,
+    #[serde(default)]
+    pub links: bool,
+    #[serde(default)]
+    pub images: bool,
+    /// Treat the URLs as sitemaps or feeds.
+    #[serde(default)]
+    pub sitemap: bool,
+    /// Glob patterns of URLs to skip.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    pub concurrency: Option<usize>,
+    /// How long the run may take, e.g. `"10m"`.
+    pub max_duration: Option<String>,
+}
+
+/// How much work a server takes on.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    /// Jobs running at once; others wait in the queue.
+    pub jobs: usize,
+    /// Jobs waiting to run; submissions beyond this are turned away.
+    pub queue: usize,
+    /// Most requests in flight per job, whatever the job asks for.
+    pub max_concurrency: usize,
+    /// Longest a job may run, whatever it asks for.
+    pub max_duration: Option<Duration>,
+    /// Ended jobs kept for polling; the oldest are forgotten first.
+    pub retain: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            jobs: 2,
+            queue: 32,
+            max_concurrency: DEFAULT_CONCURRENCY,
+            max_duration: None,
+            retain: 100,
+        }
+    }
+}
+
+impl JobRequest {
+    /// The options of the run, on top of the server's `defaults` and within its `limits`.
+    fn options(&self, defaults: &Options, limits: &Limits) -> Result<Options> {
+        let exclude = (self.exclude.iter())
+            .map(|pattern| {
+                glob::Pattern::new(pattern).map_err(|source| HtmlFindError::InvalidGlob {
+                    pattern: pattern.clone(),
+                    source,
+                })
+            })
+            .collect::<Result<_>>()?;
+        let max_duration = (self.max_duration.as_deref())
+            .map(|text| {
+                humantime::parse_duration(text).map_err(|err| HtmlFindError::InvalidRequest {
+                    message: format!("invalid max_duration {:?}: {}", text, err),
+                })
+            })
+            .transpose()?;
+        Ok(Options {
+            links: self.links,
+            images: self.images,
+            sitemap: self.sitemap,
+            exclude,
+            concurrency: (self.concurrency)
+                .unwrap_or(defaults.concurrency)
+                .min(limits.max_concurrency),
+            max_duration: match (max_duration.or(defaults.max_duration), limits.max_duration) {
+                (Some(asked), Some(limit)) => Some(asked.min(limit)),
+                (asked, limit) => asked.or(limit),
+            },
+            ..defaults.clone()
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    /// Waiting for one of the running jobs to end.
+    Queued,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+/// What `GET /jobs/{id}` answers with.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    pub id: u64,
+    pub state: JobState,
+    pub urls: Vec<Url>,
+    pub checked: usize,
+    pub broken: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+struct Job {
+    status: JobStatus,
+    cancel: CancellationToken,
+    /// Set once the run has ended.
+    report: Option<Arc<Report>>,
+}
+
+/// The jobs a server knows about.
+#[derive(Default)]
+struct Jobs {
+    next_id: u64,
+    jobs: BTreeMap<u64, Job>,
+}
+
+impl JobState {
+    fn ended(self) -> bool {
+        !matches!(self, JobState::Queued | JobState::Running)
+    }
+}
+
+impl Jobs {
+    fn queued(&self) -> usize {
+        (self.jobs.values())
+            .filter(|job| job.status.state == JobState::Queued)
+            .count()
+    }
+
+    /// Forgets the oldest ended jobs beyond the `retain` most recent.
+    fn prune(&mut self, retain: usize) {
+        let ended: Vec<u64> = (self.jobs.iter())
+            .filter(|(_, job)| job.status.state.ended())
+            .map(|(&id, _)| id)
+            .collect();
+        for id in &ended[..ended.len().saturating_sub(retain)] {
+            self.jobs.remove(id);
+        }
+    }
+}
+
+struct Shared {
+    /// What jobs run with, unless their request says otherwise.
+    defaults: Options,
+    limits: Limits,
+    /// One per job allowed to run at once.
+    running: Arc<Semaphore>,
+    jobs: Mutex<Jobs>,
+}
+
+type AppState = Arc<Shared>;
+
+/// Answers with the error as `{"error": ...}` and `status`.
+fn error(status: StatusCode, message: impl ToString) -> Response {
+    let body = serde_json::json!({ "error": message.to_string() });
+    (status, Json(body)).into_response()
+}
+
+fn not_found(id: u64) -> Response {
+    error(StatusCode::NOT_FOUND, format!("no job {}", id))
+}
+
+/// Serves the job API on `listener` until `shutdown` completes, running
+/// jobs within `limits`:
+///
+/// - `POST /jobs` queues a check described by a [`JobRequest`]
+/// - `GET /jobs` lists every job's [`JobStatus`]
+/// - `GET /jobs/{id}` polls one
+/// - `GET /jobs/{id}/results` fetches the report of a job that ended
+/// - `DELETE /jobs/{id}` cancels a queued or running job
+pub async fn serve(
+    listener: TcpListener,
+    defaults: Options,
+    limits: Limits,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    let state = Arc::new(Shared {
+        defaults: Options {
+            print_progress: false,
+            ..defaults
+        },
+        running: Arc::new(Semaphore::new(limits.jobs.max(1))),
+        limits,
+        jobs: Mutex::default(),
+    });
+    let app = Router::new()
+        .route("/jobs", get(list).post(submit))
+        .route("/jobs/{id}", get(status).delete(cancel))
+        .route("/jobs/{id}/results", get(results))
+        .with_state(state);
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown)
+        .await?;
+    Ok(())
+}
+
+async fn submit(State(state): State<AppState>, Json(request): Json<JobRequest>) -> Response {
+    if request.urls.is_empty() {
+        return error(StatusCode::UNPROCESSABLE_ENTITY, "no urls to check");
+    }
+    let options = match request.options(&state.defaults, &state.limits) {
+        Ok(options) => options,
+        Err(err) => return error(StatusCode::UNPROCESSABLE_ENTITY, err),
+    };
+    let cancel = CancellationToken::new();
+    let status = {
+        let mut jobs = state.jobs.lock().expect("jobs lock");
+        if jobs.queued() >= state.limits.queue {
+            return error(StatusCode::SERVICE_UNAVAILABLE, "the job queue is full");
+        }
+        jobs.next_id += 1;
+        let status = JobStatus {
+            id: jobs.next_id,
+            state: JobState::Queued,
+            urls: request.urls.clone(),
+            checked: 0,
+            broken: 0,
+            error: None,
+        };
+        let job = Job {
+            status: status.clone(),
+            cancel: cancel.clone(),
+            report: None,
+        };
+        jobs.jobs.insert(status.id, job);
+        status
+    };
+    tokio::spawn(run(state, status.id, request.urls, options, cancel));
+    let location = format!("/jobs/{}", status.id);
+    (
+        StatusCode::ACCEPTED,
+        [(header::LOCATION, location)],
+        Json(status),
+    )
+        .into_response()
+}
+
+/// Runs job `id` once there is room, keeping its counts up to date as
+/// outcomes come in.
+async fn run(
+    state: AppState,
+    id: u64,
+    urls: Vec<Url>,
+    options: Options,
+    cancel: CancellationToken,
+) {
+    let _permit = tokio::select! {
+        permit = state.running.clone().acquire_owned() => permit.ok(),
+        _ = cancel.cancelled() => {
+            let mut jobs = state.jobs.lock().expect("jobs lock");
+            if let Some(job) = jobs.jobs.get_mut(&id) {
+                job.status.state = JobState::Cancelled;
+            }
+            jobs.prune(state.limits.retain);
+            return;
+        }
+    };
+    if let Some(job) = state.jobs.lock().expect("jobs lock").jobs.get_mut(&id) {
+        job.status.state = JobState::Running;
+    }
+    let (events, mut received) = tokio::sync::mpsc::channel(DEFAULT_EVENTS_CAPACITY);
+    let checker = Checker::new(options)
+        .with_cancellation(cancel)
+        .with_events(events);
+    let run = tokio::spawn(async move { checker.run(&urls).await });
+    while let Some(event) = received.recv().await {
+        let Event::Checked(result) = event else {
+            continue;
+        };
+        let mut jobs = state.jobs.lock().expect("jobs lock");
+        if let Some(job) = jobs.jobs.get_mut(&id) {
+            job.status.checked += 1;
+            if result.is_broken() {
+                job.status.broken += 1;
+            }
+        }
+    }
+    let outcome = match run.await {
+        Ok(outcome) => outcome,
+        Err(err) => Err(err.into()),
+    };
+    let mut jobs = state.jobs.lock().expect("jobs lock");
+    let Some(job) = jobs.jobs.get_mut(&id) else {
+        return;
+    };
+    match outcome {
+        Ok(report) => {
+            job.status.state = if job.cancel.is_cancelled() {
+                JobState::Cancelled
+            } else {
+                JobState::Done
+            };
+            job.status.checked = report.results.len();
+            job.status.broken = report.broken().count();
+            job.report = Some(Arc::new(report));
+        }
+        Err(err) => {
+            job.status.state = JobState::Failed;
+            job.status.error = Some(err.to_string());
+        }
+    }
+    jobs.prune(state.limits.retain);
+}
+
+async fn list(State(state): State<AppState>) -> Json<Vec<JobStatus>> {
+    let jobs = state.jobs.lock().expect("jobs lock");
+    Json(jobs.jobs.values().map(|job| job.status.clone()).collect())
+}
+
+async fn status(State(state): State<AppState>, Path(id): Path<u64>) -> Response {
+    let jobs = state.jobs.lock().expect("jobs lock");
+    match jobs.jobs.get(&id) {
+        Some(job) => Json(job.status.clone()).into_response(),
+        None => not_found(id),
+    }
+}
+
+async fn results(State(state): State<AppState>, Path(id): Path<u64>) -> Response {
+    let report = {
+        let jobs = state.jobs.lock().expect("jobs lock");
+        match jobs.jobs.get(&id) {
+            Some(job) => job.report.clone(),
+            None => return not_found(id),
+        }
+    };
+    match report {
+        Some(report) => Json(&*report).into_response(),
+        None => error(StatusCode::CONFLICT, format!("job {} has not ended", id)),
+    }
+}
+
+async fn cancel(State(state): State<AppState>, Path(id): Path<u64>) -> Response {
+    let jobs = state.jobs.lock().expect("jobs lock");
+    match jobs.jobs.get(&id) {
+        Some(job) => {
+            job.cancel.cancel();
+            StatusCode::ACCEPTED.into_response()
+        }
+        None => not_found(id),
+    }
+}
@@ -0,0 +1,119 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use url::Url;
+
+/// A signed link found on a page that has expired, or will within the run's window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Expiring {
+    pub url: Url,
+    /// The page it was found on, unless it was given directly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<Url>,
+    pub expires: DateTime<Utc>,
+    /// Whether it had already expired when found.
+    pub expired: bool,
+}
+
+impl fmt::Display for Expiring {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let expires = self.expires.to_rfc3339_opts(SecondsFormat::Secs, true);
+        let verb = if self.expired { "expired" } else { "expires" };
+        write!(f, "{} {} {}", self.url, verb, expires)?;
+        if let Some(page) = &self.page {
+            write!(f, " (on {})", page)?;
+        }
+        Ok(())
+    }
+}
+
+/// When `url` stops working, if it is a signed URL of S3, CloudFront, Google
+/// Cloud Storage or an Azure shared access signature.
+pub fn expiry(url: &Url) -> Option<DateTime<Utc>> {
+    let params: HashMap<String, String> = (url.query_pairs())
+        .map(|(name, value)| (name.to_ascii_lowercase(), value.into_owned()))
+        .collect();
+    let param = |name: &str| params.get(name).map(String::as_str);
+    // Version 4 signatures: signed at a time, valid for a number of seconds.
+    for (date, expires) in [
+        ("x-amz-date", "x-amz-expires"),
+        ("x-goog-date", "x-goog-expires"),
+    ] {
+        if let (Some(date), Some(expires)) = (param(date), param(expires)) {
+            let signed = NaiveDateTime::parse_from_str(date, "%Y%m%dT%H%M%SZ").ok()?;
+            return Some(signed.and_utc() + TimeDelta::try_seconds(expires.parse().ok()?)?);
+        }
+    }
+    // Older S3 and Google signatures and CloudFront: a Unix time.
+    if let (Some(expires), Some(_)) = (param("expires"), param("signature")) {
+        return DateTime::from_timestamp(expires.parse().ok()?, 0);
+    }
+    // Azure: the "signed expiry" as an ISO 8601 time or date.
+    if let (Some(end), Some(_)) = (param("se"), param("sig")) {
+        let time = (DateTime::parse_from_rfc3339(end))
+            .or_else(|_| DateTime::parse_from_str(end, "%Y-%m-%dT%H:%M%#z"));
+        if let Ok(end) = time {
+            return Some(end.to_utc());
+        }
+        let end = NaiveDate::parse_from_str(end, "%Y-%m-%d").ok()?;
+        return Some(end.and_hms_opt(0, 0, 0)?.and_utc());
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expiry_of(url: &str) -> Option<String> {
+        let expires = expiry(&Url::parse(url).unwrap())?;
+        Some(expires.to_rfc3339_opts(SecondsFormat::Secs, true))
+    }
+
+    #[test]
+    fn reads_version_4_signatures() {
+        let s3 = "https://bucket.s3.amazonaws.com/a.pdf?X-Amz-Date=20240101T000000Z\
+                  &X-Amz-Expires=3600&X-Amz-Signature=abc";
+        assert_eq!(expiry_of(s3).as_deref(), Some("2024-01-01T01:00:00Z"));
+        let gcs = "https://storage.googleapis.com/b/a.pdf?x-goog-date=20240101T120000Z\
+                   &x-goog-expires=60&x-goog-signature=abc";
+        assert_eq!(expiry_of(gcs).as_deref(), Some("2024-01-01T12:01:00Z"));
+    }
+
+    #[test]
+    fn reads_unix_times_and_azure_expiries() {
+        let cloudfront = "https://d1.cloudfront.net/a.mp4?Expires=1700000000&Signature=abc";
+        assert_eq!(
+            expiry_of(cloudfront).as_deref(),
+            Some("2023-11-14T22:13:20Z")
+        );
+        let azure = "https://a.blob.core.windows.net/c/a.pdf?se=2024-05-01T12:30Z&sig=abc";
+        assert_eq!(expiry_of(azure).as_deref(), Some("2024-05-01T12:30:00Z"));
+        let azure = "https://a.blob.core.windows.net/c/a.pdf?se=2024-05-01&sig=abc";
+        assert_eq!(expiry_of(azure).as_deref(), Some("2024-05-01T00:00:00Z"));
+    }
+
+    #[test]
+    fn leaves_unsigned_links_alone() {
+        assert_eq!(expiry_of("https://example.com/?expires=1700000000"), None);
+        assert_eq!(
+            expiry_of("https://example.com/?X-Amz-Date=soon&X-Amz-Expires=60"),
+            None
+        );
+    }
+
+    #[test]
+    fn display_names_the_page() {
+        let expiring = Expiring {
+            url: Url::parse("https://cdn.example/a.pdf").unwrap(),
+            page: Some(Url::parse("https://example.com/").unwrap()),
+            expires: DateTime::from_timestamp(1700000000, 0).unwrap(),
+            expired: true,
+        };
+        assert_eq!(
+            expiring.to_string(),
+            "https://cdn.example/a.pdf expired 2023-11-14T22:13:20Z (on https://example.com/)"
+        );
+    }
+}
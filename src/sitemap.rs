@@ -0,0 +1,642 @@
+use crate::error::{HtmlFindError, Phase, Result};
+use crate::http::Http;
+use crate::page::get_page;
+use crate::report::Report;
+use chrono::DateTime;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use quick_xml::escape::escape;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+use url::Url;
+
+/// Where sitemaps usually live, tried when robots.txt lists none.
+const COMMON_PATHS: [&str; 2] = ["/sitemap.xml", "/sitemap_index.xml"];
+
+/// The entries of a `<urlset>`, or of a `<sitemapindex>` listing more sitemaps.
+#[derive(Debug, Clone, Default)]
+pub struct Sitemap {
+    pub index: bool,
+    pub entries: Vec<Entry>,
+}
+
+/// A `<url>` or `<sitemap>` entry.
+#[derive(Debug, Clone, Default)]
+pub struct Entry {
+    pub loc: String,
+    /// As written, e.g. `2024-05-01` or `2024-05-01T12:00:00+00:00`.
+    pub lastmod: Option<String>,
+    pub changefreq: Option<String>,
+    pub priority: Option<String>,
+    /// `<image:loc>` of each `<image:image>`.
+    pub images: Vec<String>,
+    pub videos: Vec<Video>,
+}
+
+/// A `<video:video>` of an entry.
+#[derive(Debug, Clone, Default)]
+pub struct Video {
+    pub thumbnail_loc: Option<String>,
+    pub content_loc: Option<String>,
+    pub player_loc: Option<String>,
+}
+
+/// What a media URL an entry lists should serve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Media {
+    Image,
+    Video,
+}
+
+impl Media {
+    /// Whether a response sent as `content_type` fits; HLS and DASH
+    /// playlists count as video.
+    pub fn accepts(self, content_type: &str) -> bool {
+        let essence = content_type.split(';').next().unwrap_or_default().trim();
+        let essence = essence.to_ascii_lowercase();
+        match self {
+            Media::Image => essence.starts_with("image/"),
+            Media::Video => {
+                essence.starts_with("video/")
+                    || matches!(
+                        essence.as_str(),
+                        "application/vnd.apple.mpegurl"
+                            | "application/x-mpegurl"
+                            | "application/dash+xml"
+                    )
+            }
+        }
+    }
+}
+
+impl fmt::Display for Media {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Media::Image => "an image",
+            Media::Video => "a video",
+        })
+    }
+}
+
+impl Entry {
+    /// The image and video URLs of the entry, with what each should serve;
+    /// players are pages of any kind.
+    pub fn media(&self) -> Vec<(&str, Option<Media>)> {
+        let images = self
+            .images
+            .iter()
+            .map(|image| (image.as_str(), Some(Media::Image)));
+        let videos = self.videos.iter().flat_map(|video| {
+            [
+                (&video.thumbnail_loc, Some(Media::Image)),
+                (&video.content_loc, Some(Media::Video)),
+                (&video.player_loc, None),
+            ]
+            .into_iter()
+            .filter_map(|(loc, media)| Some((loc.as_deref()?, media)))
+        });
+        images.chain(videos).collect()
+    }
+}
+
+impl Sitemap {
+    /// Parses the sitemap at `url`: an XML `<urlset>` or `<sitemapindex>`,
+    /// an RSS or Atom feed, or a plain-text list of URLs, one per line.
+    pub fn parse(url: &Url, body: &str) -> Result<Sitemap> {
+        if body
+            .trim_start_matches('\u{feff}')
+            .trim_start()
+            .starts_with('<')
+        {
+            Sitemap::parse_xml(url, body)
+        } else {
+            Sitemap::parse_text(url, body)
+        }
+    }
+
+    fn parse_text(url: &Url, text: &str) -> Result<Sitemap> {
+        let mut sitemap = Sitemap::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim_start_matches('\u{feff}').trim();
+            if line.is_empty() {
+                continue;
+            }
+            if Url::parse(line).is_err() {
+                return Err(HtmlFindError::InvalidSitemap {
+                    url: url.clone(),
+                    message: format!("line {} is not a URL", number + 1),
+                });
+            }
+            sitemap.entries.push(Entry {
+                loc: line.to_string(),
+                ..Entry::default()
+            });
+        }
+        Ok(sitemap)
+    }
+
+    /// Elements are matched by local name, so namespace prefixes don't
+    /// matter; unknown extensions are skipped.
+    fn parse_xml(url: &Url, xml: &str) -> Result<Sitemap> {
+        let invalid = |reader: &Reader<&[u8]>, message: String| HtmlFindError::InvalidSitemap {
+            url: url.clone(),
+            message: format!("{} at byte {}", message, reader.buffer_position()),
+        };
+        let mut reader = Reader::from_str(xml);
+        reader.trim_text(true);
+        let mut sitemap = Sitemap::default();
+        let mut root = String::new();
+        let mut closed = false;
+        // Local names of the open elements below the root, outermost first.
+        let mut open: Vec<String> = Vec::new();
+        loop {
+            let event = reader
+                .read_event()
+                .map_err(|err| invalid(&reader, err.to_string()))?;
+            let text = match event {
+                Event::Start(ref start) | Event::Empty(ref start) => {
+                    let name = String::from_utf8_lossy(start.local_name().as_ref()).into_owned();
+                    if root.is_empty() {
+                        sitemap.index = match name.as_str() {
+                            "sitemapindex" => true,
+                            "urlset" | "rss" | "RDF" | "feed" => false,
+                            _ => return Err(invalid(&reader, format!("unexpected <{}>", name))),
+                        };
+                        root = name;
+                        closed = matches!(event, Event::Empty(_));
+                        continue;
+                    }
+                    open.push(name);
+                    let path: Vec<&str> = open.iter().map(String::as_str).collect();
+                    match (root.as_str(), path.as_slice()) {
+                        ("urlset", ["url"])
+                        | ("sitemapindex", ["sitemap"])
+                        | ("rss", ["channel", "item"])
+                        | ("RDF", ["item"])
+                        | ("feed", ["entry"]) => sitemap.entries.push(Entry::default()),
+                        ("urlset", ["url", "video"]) => {
+                            if let Some(entry) = sitemap.entries.last_mut() {
+                                entry.videos.push(Video::default());
+                            }
+                        }
+                        // The entry's page is its first link that isn't to an
+                        // enclosure, a comment thread or the like.
+                        ("feed", ["entry", "link"]) => {
+                            let attribute = |key: &[u8]| {
+                                (start.attributes().flatten())
+                                    .find(|attribute| attribute.key.local_name().as_ref() == key)
+                                    .and_then(|attribute| attribute.unescape_value().ok())
+                                    .map(|value| value.into_owned())
+                            };
+                            let alternate = attribute(b"rel").is_none_or(|rel| rel == "alternate");
+                            if let (Some(entry), Some(href), true) =
+                                (sitemap.entries.last_mut(), attribute(b"href"), alternate)
+                            {
+                                if entry.loc.is_empty() {
+                                    entry.loc = href.trim().to_string();
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                    if matches!(event, Event::Empty(_)) {
+                        open.pop();
+                    }
+                    continue;
+                }
+                Event::End(_) => {
+                    closed |= open.pop().is_none();
+                    continue;
+                }
+                Event::Text(text) => text
+                    .unescape()
+                    .map_err(|err| invalid(&reader, err.to_string()))?
+                    .into_owned(),
+                Event::CData(data) => String::from_utf8_lossy(&data.into_inner()).into_owned(),
+                Event::Eof => break,
+                _ => continue,
+            };
+            let Some(entry) = sitemap.entries.last_mut() else {
+                continue;
+            };
+            let path: Vec<&str> = open.iter().map(String::as_str).collect();
+            let text = text.trim().to_string();
+            match (root.as_str(), path.as_slice()) {
+                ("urlset", ["url", "loc"])
+                | ("sitemapindex", ["sitemap", "loc"])
+                | ("rss", ["channel", "item", "link"])
+                | ("RDF", ["item", "link"]) => entry.loc = text,
+                ("urlset", ["url", "lastmod"]) | ("sitemapindex", ["sitemap", "lastmod"]) => {
+                    entry.lastmod = Some(text)
+                }
+                ("urlset", ["url", "changefreq"]) => entry.changefreq = Some(text),
+                ("urlset", ["url", "priority"]) => entry.priority = Some(text),
+                ("urlset", ["url", "image", "loc"]) => entry.images.push(text),
+                ("urlset", ["url", "video", field]) => {
+                    if let Some(video) = entry.videos.last_mut() {
+                        match *field {
+                            "thumbnail_loc" => video.thumbnail_loc = Some(text),
+                            "content_loc" => video.content_loc = Some(text),
+                            "player_loc" => video.player_loc = Some(text),
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if root.is_empty() {
+            return Err(invalid(
+                &reader,
+                "no <urlset>, <sitemapindex> or feed".to_string(),
+            ));
+        }
+        if !closed {
+            let name = open.last().unwrap_or(&root);
+            return Err(invalid(&reader, format!("unclosed <{}>", name)));
+        }
+        Ok(sitemap)
+    }
+
+    /// The `<loc>` of every entry.
+    pub fn urls(&self) -> Vec<String> {
+        (self.entries.iter())
+            .filter(|entry| !entry.loc.is_empty())
+            .map(|entry| entry.loc.clone())
+            .collect()
+    }
+}
+
+/// The `urls` on the host of `domain` or its subdomains, by the host each
+/// parses to, so that neither `https://example.com.evil.net/` nor
+/// `https://other.org/?ref=example.com` passes for `example.com`. URLs that
+/// don't parse are left out.
+pub fn filter_urls(urls: Vec<String>, domain: &Url) -> Result<Vec<String>> {
+    let host = domain
+        .host_str()
+        .ok_or_else(|| HtmlFindError::MissingHost {
+            url: domain.clone(),
+        })?;
+    let on_site = |url: &String| {
+        Url::parse(url).ok().is_some_and(|url| {
+            url.host_str().is_some_and(|listed| {
+                listed == host || (listed.strip_suffix(host)).is_some_and(|sub| sub.ends_with('.'))
+            })
+        })
+    };
+    Ok(urls.into_iter().filter(on_site).collect())
+}
+
+/// Whether `url` is the root of a site rather than a sitemap, e.g.
+/// `https://example.com/`, so its sitemaps have to be found first.
+pub fn is_site_root(url: &Url) -> bool {
+    url.path() == "/" && url.query().is_none()
+}
+
+/// The URLs of the `Sitemap:` lines in the robots.txt at `robots`.
+pub fn robots_sitemaps(robots: &Url, text: &str) -> Vec<Url> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(key, _)| key.trim().eq_ignore_ascii_case("sitemap"))
+        .filter_map(|(_, value)| robots.join(value.trim()).ok())
+        .collect()
+}
+
+/// The sitemaps of the site at `root`: those its robots.txt lists, or else
+/// whichever of the common paths exist.
+pub async fn discover(http: &Http, root: &Url) -> Vec<Url> {
+    let robots = root.join("/robots.txt").expect("an absolute path joins");
+    match get_page(http, &robots, Phase::FetchSitemap).await {
+        Ok((status, body)) if status.is_success() => {
+            let listed = robots_sitemaps(&robots, &body);
+            if !listed.is_empty() {
+                return listed;
+            }
+        }
+        Ok((status, _)) => debug!(status = status.as_u16(), "no {}", robots),
+        Err(err) => debug!("{}", err),
+    }
+    let mut found = Vec::new();
+    for path in COMMON_PATHS {
+        let url = root.join(path).expect("an absolute path joins");
+        match http.send(http.get(&url)).await {
+            Ok(res) => {
+                let exists = res.status().is_success();
+                let _ = http.consume(res).await;
+                if exists {
+                    found.push(url);
+                }
+            }
+            Err(err) => debug!("probing {} failed: {}", url, err),
+        }
+    }
+    found
+}
+
+/// Most URLs the protocol allows in one sitemap.
+pub const MAX_URLS: usize = 50_000;
+
+/// The pages a crawl found, as entries for a sitemap of the site: those that
+/// were scanned, answered 200 without redirecting, with the `Last-Modified`
+/// they were sent with as their lastmod.
+pub fn crawled(report: &Report) -> Vec<Entry> {
+    let pages: HashSet<&Url> = report.pages.iter().collect();
+    let mut entries: Vec<Entry> = (report.results.iter())
+        .filter(|result| {
+            result.is_ok() && result.redirected_to.is_none() && pages.contains(&result.url)
+        })
+        .map(|result| Entry {
+            loc: result.url.to_string(),
+            lastmod: (result.validators.last_modified.as_deref())
+                .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+                .map(|date| humantime::format_rfc3339_seconds(date.into()).to_string()),
+            ..Entry::default()
+        })
+        .collect();
+    entries.sort_by(|a, b| a.loc.cmp(&b.loc));
+    entries
+}
+
+/// Writes `entries` as a sitemap at `output`, gzipped with `.gz` appended
+/// when `gzip` is set. Past [`MAX_URLS`] they are split into shards beside
+/// it, `output` becoming an index of them that lists them as served from the
+/// root of `site`. Returns the files written.
+pub fn write(output: &Path, site: &Url, entries: &[Entry], gzip: bool) -> Result<Vec<PathBuf>> {
+    if entries.len() <= MAX_URLS {
+        return Ok(vec![write_file(output, &urlset(entries), gzip)?]);
+    }
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = output.extension().unwrap_or_default().to_string_lossy();
+    let mut written = Vec::new();
+    let mut shards = Vec::new();
+    for (number, shard) in entries.chunks(MAX_URLS).enumerate() {
+        let name = format!("{}-{}.{}", stem, number + 1, extension);
+        let path = write_file(&output.with_file_name(&name), &urlset(shard), gzip)?;
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        shards.push(site.join(&format!("/{}", name)).map_err(|source| {
+            HtmlFindError::InvalidUrl {
+                url: name.to_string(),
+                source,
+            }
+        })?);
+        written.push(path);
+    }
+    written.insert(0, write_file(output, &index(&shards), gzip)?);
+    Ok(written)
+}
+
+fn urlset(entries: &[Entry]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for entry in entries {
+        xml.push_str(&format!("  <url><loc>{}</loc>", escape(&entry.loc)));
+        if let Some(lastmod) = &entry.lastmod {
+            xml.push_str(&format!("<lastmod>{}</lastmod>", escape(lastmod)));
+        }
+        xml.push_str("</url>\n");
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+fn index(shards: &[Url]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for shard in shards {
+        xml.push_str(&format!(
+            "  <sitemap><loc>{}</loc></sitemap>\n",
+            escape(shard.as_str())
+        ));
+    }
+    xml.push_str("</sitemapindex>\n");
+    xml
+}
+
+/// Writes `xml` to `path`, or gzipped to `path` with `.gz` appended.
+fn write_file(path: &Path, xml: &str, gzip: bool) -> Result<PathBuf> {
+    let path = if gzip {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".gz");
+        PathBuf::from(name)
+    } else {
+        path.to_path_buf()
+    };
+    let file_error = |source| HtmlFindError::File {
+        path: path.clone(),
+        source,
+    };
+    let bytes = if gzip {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(xml.as_bytes()).map_err(file_error)?;
+        encoder.finish().map_err(file_error)?
+    } else {
+        xml.as_bytes().to_vec()
+    };
+    std::fs::write(&path, bytes).map_err(file_error)?;
+    Ok(path)
+}
+
+/// The two ways the pages sitemaps list and those a crawl reaches can differ.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Coverage {
+    /// Listed, but never linked to from the pages the crawl reached.
+    pub unreachable: Vec<Url>,
+    /// Reached by the crawl, answering 200, but not listed.
+    pub unlisted: Vec<Url>,
+}
+
+impl Coverage {
+    /// Compares the pages the sitemaps `listed` with those `report` reached.
+    pub fn new(listed: &HashSet<Url>, report: &Report) -> Coverage {
+        let reached: HashSet<&Url> = report.results.iter().map(|result| &result.url).collect();
+        let mut unreachable: Vec<Url> = (listed.iter())
+            .filter(|url| !reached.contains(url))
+            .cloned()
+            .collect();
+        unreachable.sort();
+        let unlisted = (crawled(report).into_iter())
+            .filter_map(|entry| Url::parse(&entry.loc).ok())
+            .filter(|url| !listed.contains(url))
+            .collect();
+        Coverage {
+            unreachable,
+            unlisted,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.unreachable.is_empty() && self.unlisted.is_empty()
+    }
+
+    pub fn print(&self) {
+        if !self.unreachable.is_empty() {
+            println!(
+                "{} pages in the sitemaps aren't linked from the site",
+                self.unreachable.len()
+            );
+            for url in &self.unreachable {
+                println!("  {}", url);
+            }
+        }
+        if !self.unlisted.is_empty() {
+            println!(
+                "{} pages linked from the site are missing from the sitemaps",
+                self.unlisted.len()
+            );
+            for url in &self.unlisted {
+                println!("  {}", url);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn parses_urlset_entries_with_media() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+                    xmlns:image="http://www.google.com/schemas/sitemap-image/1.1"
+                    xmlns:video="http://www.google.com/schemas/sitemap-video/1.1">
+              <url>
+                <loc>https://example.com/</loc>
+                <lastmod>2024-05-01</lastmod>
+                <changefreq>daily</changefreq>
+                <priority>0.8</priority>
+                <image:image><image:loc>https://example.com/a.png</image:loc></image:image>
+                <video:video>
+                  <video:thumbnail_loc>https://example.com/t.jpg</video:thumbnail_loc>
+                  <video:content_loc>https://example.com/v.mp4</video:content_loc>
+                </video:video>
+              </url>
+              <url><loc><![CDATA[https://example.com/b?x=1&y=2]]></loc></url>
+            </urlset>"#;
+        let sitemap = Sitemap::parse(&url("https://example.com/sitemap.xml"), xml).unwrap();
+        assert!(!sitemap.index);
+        assert_eq!(
+            sitemap.urls(),
+            ["https://example.com/", "https://example.com/b?x=1&y=2"]
+        );
+        let entry = &sitemap.entries[0];
+        assert_eq!(entry.lastmod.as_deref(), Some("2024-05-01"));
+        assert_eq!(entry.changefreq.as_deref(), Some("daily"));
+        assert_eq!(entry.priority.as_deref(), Some("0.8"));
+        assert_eq!(
+            entry.media(),
+            [
+                ("https://example.com/a.png", Some(Media::Image)),
+                ("https://example.com/t.jpg", Some(Media::Image)),
+                ("https://example.com/v.mp4", Some(Media::Video)),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_sitemap_index() {
+        let xml = "<sitemapindex><sitemap><loc>https://example.com/a.xml</loc>\
+                   <lastmod>2024-01-01</lastmod></sitemap></sitemapindex>";
+        let sitemap = Sitemap::parse(&url("https://example.com/"), xml).unwrap();
+        assert!(sitemap.index);
+        assert_eq!(sitemap.urls(), ["https://example.com/a.xml"]);
+        assert_eq!(sitemap.entries[0].lastmod.as_deref(), Some("2024-01-01"));
+    }
+
+    #[test]
+    fn parses_feeds() {
+        let rss = "<rss><channel><link>https://example.com/</link>\
+                   <item><link>https://example.com/post</link></item></channel></rss>";
+        let sitemap = Sitemap::parse(&url("https://example.com/feed"), rss).unwrap();
+        assert_eq!(sitemap.urls(), ["https://example.com/post"]);
+
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom"><entry>
+            <link rel="replies" href="https://example.com/post#comments"/>
+            <link href="https://example.com/post"/>
+            <link rel="alternate" href="https://example.com/other"/>
+            </entry></feed>"#;
+        let sitemap = Sitemap::parse(&url("https://example.com/atom"), atom).unwrap();
+        assert_eq!(sitemap.urls(), ["https://example.com/post"]);
+    }
+
+    #[test]
+    fn parses_plain_text() {
+        let text = "\u{feff}https://example.com/a\n\n  https://example.com/b  \n";
+        let sitemap = Sitemap::parse(&url("https://example.com/sitemap.txt"), text).unwrap();
+        assert_eq!(
+            sitemap.urls(),
+            ["https://example.com/a", "https://example.com/b"]
+        );
+        let bad = Sitemap::parse(&url("https://example.com/sitemap.txt"), "https://a/\nnope");
+        assert!(bad.unwrap_err().to_string().contains("line 2 is not a URL"));
+    }
+
+    #[test]
+    fn rejects_malformed_xml() {
+        let base = url("https://example.com/sitemap.xml");
+        assert!(Sitemap::parse(&base, "<html><body/></html>").is_err());
+        assert!(Sitemap::parse(&base, "<urlset><url><loc>x</loc></url>").is_err());
+    }
+
+    #[test]
+    fn filter_urls_keeps_the_domain() {
+        let urls = vec![
+            "https://example.com/a".to_string(),
+            "https://other.org/b".to_string(),
+            "https://example.com/c".to_string(),
+        ];
+        let kept = filter_urls(urls, &url("https://example.com/sitemap.xml")).unwrap();
+        assert_eq!(kept, ["https://example.com/a", "https://example.com/c"]);
+        assert!(filter_urls(vec![], &url("mailto:a@example.com")).is_err());
+    }
+
+    #[test]
+    fn filter_urls_compares_hosts() {
+        let urls = [
+            "https://example.com.evil.net/",
+            "https://other.org/?ref=example.com",
+            "https://notexample.com/",
+            "https://blog.example.com/post",
+            "https://EXAMPLE.com/about",
+            "not a url example.com",
+        ];
+        let urls = urls.iter().map(|url| url.to_string()).collect();
+        let kept = filter_urls(urls, &url("https://example.com/sitemap.xml")).unwrap();
+        assert_eq!(
+            kept,
+            ["https://blog.example.com/post", "https://EXAMPLE.com/about"]
+        );
+    }
+
+    #[test]
+    fn finds_robots_sitemaps() {
+        let robots = url("https://example.com/robots.txt");
+        let text = "User-agent: *\nDisallow: /x\nSitemap: /sitemap.xml # main\n\
+                    sitemap: https://cdn.example.com/more.xml\n";
+        assert_eq!(
+            robots_sitemaps(&robots, text),
+            [
+                url("https://example.com/sitemap.xml"),
+                url("https://cdn.example.com/more.xml")
+            ]
+        );
+        assert!(is_site_root(&url("https://example.com/")));
+        assert!(!is_site_root(&url("https://example.com/sitemap.xml")));
+    }
+}
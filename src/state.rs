@@ -0,0 +1,95 @@
+use crate::audit::Issue;
+use crate::caching::CachingIssue;
+use crate::checker::Job;
+use crate::duplicates::DuplicateLinks;
+use crate::error::{HtmlFindError, Result};
+use crate::expect::Unmet;
+use crate::grep::GrepMatch;
+use crate::report::CheckResult;
+use crate::signed::Expiring;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use url::Url;
+
+/// Everything needed to pick an interrupted crawl back up.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct CrawlState {
+    pub starts: Vec<Url>,
+    /// Documents not fetched yet, including those that were in flight.
+    pub frontier: Vec<Job>,
+    /// Links discovered but not checked yet.
+    pub pending: Vec<Url>,
+    pub viewed: Vec<String>,
+    pub results: Vec<CheckResult>,
+    pub discovered: usize,
+    pub pages: Vec<Url>,
+    #[serde(default)]
+    pub unmet: Vec<Unmet>,
+    #[serde(default)]
+    pub matches: Vec<GrepMatch>,
+    #[serde(default)]
+    pub sitemap_issues: Vec<Issue>,
+    #[serde(default)]
+    pub caching_issues: Vec<CachingIssue>,
+    #[serde(default)]
+    pub duplicate_links: Vec<DuplicateLinks>,
+    #[serde(default)]
+    pub expiring: Vec<Expiring>,
+    /// The resources each page loads, when weighing pages.
+    #[serde(default)]
+    pub resources: HashMap<Url, HashSet<Url>>,
+    /// The pages linking to each URL.
+    #[serde(default)]
+    pub referrers: HashMap<Url, HashSet<Url>>,
+    /// Pages the sitemaps list, when comparing them with the crawl.
+    #[serde(default)]
+    pub listed: Vec<Url>,
+    /// The canonical URL each page names, when auditing them.
+    #[serde(default)]
+    pub canonicals: BTreeMap<Url, Url>,
+}
+
+impl CrawlState {
+    /// Reads the state at `path`, or `None` when there is no such file.
+    pub fn load(path: &Path) -> Result<Option<CrawlState>> {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(source) => {
+                return Err(HtmlFindError::File {
+                    path: path.to_path_buf(),
+                    source,
+                })
+            }
+        };
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|source| HtmlFindError::Json {
+                path: path.to_path_buf(),
+                source,
+            })
+    }
+
+    /// Writes through a temporary file so an interruption never leaves a torn state.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let file_error = |source| HtmlFindError::File {
+            path: path.to_path_buf(),
+            source,
+        };
+        let bytes = serde_json::to_vec(self).expect("crawl state serializes");
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, bytes).map_err(file_error)?;
+        std::fs::rename(&tmp, path).map_err(file_error)
+    }
+
+    pub fn remove(path: &Path) -> Result<()> {
+        match std::fs::remove_file(path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(HtmlFindError::File {
+                path: path.to_path_buf(),
+                source: err,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
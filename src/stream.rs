@@ -0,0 +1,111 @@
+use crate::checker::{Checker, Event};
+use crate::error::Result;
+use crate::report::{CheckResult, Report};
+use futures::Stream;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tokio::sync::mpsc::{channel, Receiver};
+use tokio::task::{JoinError, JoinHandle};
+use tokio_util::sync::CancellationToken;
+use url::Url;
+
+/// The outcomes of a run in progress, as they come in. The run goes on in
+/// the background until the stream holds as many events as it has room for,
+/// then waits for it to be polled; once it ends, [`CheckStream::report`] has
+/// the whole report. Dropping the stream cancels the run, as
+/// [`CheckStream::cancel`] does.
+#[derive(Debug)]
+pub struct CheckStream {
+    events: Receiver<Event>,
+    run: JoinHandle<Result<Report>>,
+    /// What the run ended with, once the stream has seen it end.
+    ended: Option<Result<Report>>,
+    cancel: CancellationToken,
+}
+
+impl Checker {
+    /// Starts crawling from each of `urls`, as [`Checker::run`] does, on the
+    /// current Tokio runtime, giving each outcome as it comes in. The stream
+    /// holds up to `capacity` events, at least one, outcomes and the links found on pages
+    /// alike, before the run waits for it, e.g. [`DEFAULT_EVENTS_CAPACITY`].
+    /// Events sent to a receiver from [`Checker::with_events`] go to the
+    /// stream instead. Only crawls stream: the other runs, such as
+    /// [`Checker::run_list`] and [`Checker::run_path`], give their outcomes
+    /// to [`Checker::with_events`].
+    ///
+    /// [`DEFAULT_EVENTS_CAPACITY`]: crate::checker::DEFAULT_EVENTS_CAPACITY
+    pub fn stream(self, urls: Vec<Url>, capacity: usize) -> CheckStream {
+        let (sender, events) = channel(capacity);
+        let checker = self.with_events(sender);
+        let cancel = checker.cancellation();
+        let run = tokio::spawn(async move { checker.run(&urls).await });
+        CheckStream {
+            events,
+            run,
+            ended: None,
+            cancel,
+        }
+    }
+}
+
+impl CheckStream {
+    /// Stops the run early. Checks in flight get the run's shutdown grace to
+    /// finish, then the stream ends and [`CheckStream::report`] has what was gathered.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Whether the run has ended, so the stream has no more outcomes to come
+    /// than it holds.
+    pub fn is_finished(&self) -> bool {
+        self.run.is_finished()
+    }
+
+    /// Waits for the run to end and returns its report, including the
+    /// outcomes the stream gave. A panic of the run is resumed here.
+    pub async fn report(mut self) -> Result<Report> {
+        match self.ended.take() {
+            Some(ended) => ended,
+            None => joined((&mut self.run).await),
+        }
+    }
+}
+
+/// What the run ended with, resuming its panic if it had one.
+fn joined(joined: std::result::Result<Result<Report>, JoinError>) -> Result<Report> {
+    match joined {
+        Ok(ended) => ended,
+        Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+impl Stream for CheckStream {
+    type Item = CheckResult;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<CheckResult>> {
+        loop {
+            match self.events.poll_recv(cx) {
+                Poll::Ready(Some(Event::Checked(result))) => return Poll::Ready(Some(*result)),
+                Poll::Ready(Some(_)) => continue,
+                Poll::Ready(None) => {
+                    // The events end once the run does, or once it panics:
+                    // waiting for it tells them apart.
+                    if self.ended.is_none() {
+                        let ended = ready!(Pin::new(&mut self.run).poll(cx));
+                        self.ended = Some(joined(ended));
+                    }
+                    return Poll::Ready(None);
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl Drop for CheckStream {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use url::Url;
+
+/// A page only reached by following many links from the start pages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeepPage {
+    pub url: Url,
+    /// The fewest links followed to reach it.
+    pub depth: usize,
+}
+
+impl fmt::Display for DeepPage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = if self.depth == 1 { "" } else { "s" };
+        write!(
+            f,
+            "{} is {} link{} from the start",
+            self.url, self.depth, plural
+        )
+    }
+}
+
+/// The site's pages as the links between them, built from the pages linking
+/// to each URL.
+pub struct LinkGraph<'a> {
+    links: BTreeMap<&'a Url, HashSet<&'a Url>>,
+}
+
+impl<'a> LinkGraph<'a> {
+    /// The links between `pages`, `referrers` giving the pages linking to
+    /// each URL. Links of a page to itself are left out.
+    pub fn new(pages: &'a [Url], referrers: &'a HashMap<Url, HashSet<Url>>) -> Self {
+        let mut links: BTreeMap<&Url, HashSet<&Url>> =
+            pages.iter().map(|page| (page, HashSet::new())).collect();
+        for (target, sources) in referrers {
+            for source in sources.iter().filter(|source| *source != target) {
+                if let Some(out) = links.get_mut(source) {
+                    out.insert(target);
+                }
+            }
+        }
+        LinkGraph { links }
+    }
+
+    /// Pages without links to any other page of the graph, so readers
+    /// reaching them can't go on: links to images, to other sites or to
+    /// broken pages don't count.
+    pub fn dead_ends(&self) -> Vec<Url> {
+        (self.links.iter())
+            .filter(|(_, out)| !out.iter().any(|target| self.links.contains_key(target)))
+            .map(|(page, _)| (*page).clone())
+            .collect()
+    }
+
+    /// Pages no fewer than `min_depth` links from the nearest of `starts`,
+    /// the deepest first.
+    pub fn deep_pages(&self, starts: &[Url], min_depth: usize) -> Vec<DeepPage> {
+        let mut depths: HashMap<&Url, usize> = HashMap::new();
+        let mut queue: VecDeque<&Url> = VecDeque::new();
+        for start in starts {
+            if let Some((page, _)) = self.links.get_key_value(start) {
+                if depths.insert(page, 0).is_none() {
+                    queue.push_back(page);
+                }
+            }
+        }
+        while let Some(page) = queue.pop_front() {
+            let depth = depths[page] + 1;
+            for target in &self.links[page] {
+                if let Some((target, _)) = self.links.get_key_value(target) {
+                    if !depths.contains_key(target) {
+                        depths.insert(target, depth);
+                        queue.push_back(target);
+                    }
+                }
+            }
+        }
+        let mut deep: Vec<DeepPage> = (depths.into_iter())
+            .filter(|(_, depth)| *depth >= min_depth)
+            .map(|(url, depth)| DeepPage {
+                url: url.clone(),
+                depth,
+            })
+            .collect();
+        deep.sort_by(|a, b| b.depth.cmp(&a.depth).then(a.url.cmp(&b.url)));
+        deep
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(path: &str) -> Url {
+        Url::parse("https://example.com/")
+            .unwrap()
+            .join(path)
+            .unwrap()
+    }
+
+    fn referrers(links: &[(&str, &str)]) -> HashMap<Url, HashSet<Url>> {
+        let mut referrers: HashMap<Url, HashSet<Url>> = HashMap::new();
+        for (from, to) in links {
+            referrers.entry(url(to)).or_default().insert(url(from));
+        }
+        referrers
+    }
+
+    #[test]
+    fn finds_dead_ends() {
+        let pages = [url("/"), url("/a"), url("/b"), url("/c")];
+        let referrers = referrers(&[
+            ("/", "/a"),
+            ("/", "/b"),
+            ("/a", "/"),
+            // Links to itself, to an image and to a broken page lead nowhere.
+            ("/b", "/b"),
+            ("/b", "/logo.png"),
+            ("/b", "/gone"),
+            ("/c", "/b"),
+        ]);
+        let graph = LinkGraph::new(&pages, &referrers);
+        assert_eq!(graph.dead_ends(), [url("/b")]);
+    }
+
+    #[test]
+    fn measures_depth_from_the_nearest_start() {
+        let pages = [url("/"), url("/a"), url("/a/b"), url("/a/b/c"), url("/x")];
+        let referrers = referrers(&[
+            ("/", "/a"),
+            ("/a", "/a/b"),
+            ("/a/b", "/a/b/c"),
+            ("/a/b/c", "/"),
+            ("/x", "/a/b/c"),
+        ]);
+        let graph = LinkGraph::new(&pages, &referrers);
+        let deep = graph.deep_pages(&[url("/")], 2);
+        let found: Vec<(&str, usize)> = (deep.iter())
+            .map(|page| (page.url.path(), page.depth))
+            .collect();
+        assert_eq!(found, [("/a/b/c", 3), ("/a/b", 2)]);
+        assert_eq!(
+            deep[1].to_string(),
+            "https://example.com/a/b is 2 links from the start"
+        );
+
+        // A second start page brings its part of the site closer.
+        let deep = graph.deep_pages(&[url("/"), url("/x")], 2);
+        assert_eq!(deep.len(), 1);
+        assert_eq!(deep[0].url, url("/a/b"));
+    }
+}
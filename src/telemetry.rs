@@ -0,0 +1,54 @@
+use crate::error::{HtmlFindError, Result};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::trace::{SdkTracer, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Exports the spans of a run, such as each page fetch and link check, to an
+/// OpenTelemetry collector over OTLP/HTTP.
+pub struct Telemetry {
+    provider: SdkTracerProvider,
+}
+
+fn failed(err: impl ToString) -> HtmlFindError {
+    HtmlFindError::Telemetry {
+        message: err.to_string(),
+    }
+}
+
+impl Telemetry {
+    /// Sets up the exporter. Spans go to `endpoint`, a full URL such as
+    /// `http://localhost:4318/v1/traces`, or else to where the standard
+    /// `OTEL_EXPORTER_OTLP_*` variables point.
+    pub fn new(endpoint: Option<&str>) -> Result<Telemetry> {
+        let mut exporter = SpanExporter::builder().with_http();
+        if let Some(endpoint) = endpoint {
+            exporter = exporter.with_endpoint(endpoint);
+        }
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter.build().map_err(failed)?)
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(env!("CARGO_PKG_NAME"))
+                    .build(),
+            )
+            .build();
+        Ok(Telemetry { provider })
+    }
+
+    /// A layer handing spans to the exporter, to pass to [`crate::logging::init`].
+    pub fn layer<S>(&self) -> OpenTelemetryLayer<S, SdkTracer>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        tracing_opentelemetry::layer().with_tracer(self.provider.tracer(env!("CARGO_PKG_NAME")))
+    }
+
+    /// Sends the spans not exported yet.
+    pub fn shutdown(self) -> Result<()> {
+        self.provider.shutdown().map_err(failed)
+    }
+}
@@ -0,0 +1,39 @@
+/// Broken counts of a run compared with the runs before it.
+#[derive(Debug, Clone)]
+pub struct Trend {
+    pub broken: u64,
+    /// Broken counts of earlier runs, newest first.
+    pub history: Vec<u64>,
+}
+
+impl Trend {
+    pub fn new(broken: u64, history: Vec<u64>) -> Self {
+        Trend { broken, history }
+    }
+
+    /// Mean broken count of the earlier runs, `None` without history.
+    pub fn rolling_average(&self) -> Option<f64> {
+        if self.history.is_empty() {
+            return None;
+        }
+        Some(self.history.iter().sum::<u64>() as f64 / self.history.len() as f64)
+    }
+
+    /// Whether this run has more than `delta` broken URLs above the rolling average.
+    pub fn is_regression(&self, delta: f64) -> bool {
+        self.rolling_average()
+            .is_some_and(|average| self.broken as f64 - average > delta)
+    }
+
+    pub fn print(&self) {
+        match self.rolling_average() {
+            Some(average) => println!(
+                "Broken: {} (average of previous {} runs: {:.1})",
+                self.broken,
+                self.history.len(),
+                average
+            ),
+            None => println!("Broken: {} (no previous runs)", self.broken),
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use crate::checker::Event;
+use crate::error::Result;
+use crate::report::CheckResult;
+use ratatui::crossterm::event::{self, Event as Input, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio::sync::mpsc::Receiver;
+use tokio_util::sync::CancellationToken;
+use url::Url;
+
+/// How often the screen is redrawn while waiting for keys.
+const TICK: Duration = Duration::from_millis(100);
+
+const HELP: &str = "q quit  f filter  s sort  p pages  Enter open  Esc back";
+
+/// Which results are listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Filter {
+    All,
+    Broken,
+    Ok,
+    Redirected,
+}
+
+impl Filter {
+    fn next(self) -> Self {
+        match self {
+            Filter::All => Filter::Broken,
+            Filter::Broken => Filter::Ok,
+            Filter::Ok => Filter::Redirected,
+            Filter::Redirected => Filter::All,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Filter::All => "all",
+            Filter::Broken => "broken",
+            Filter::Ok => "ok",
+            Filter::Redirected => "redirected",
+        }
+    }
+
+    fn keeps(self, result: &CheckResult) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::Broken => !result.is_ok(),
+            Filter::Ok => result.is_ok(),
+            Filter::Redirected => result.redirected_to.is_some(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sort {
+    /// In the order outcomes came in.
+    Arrival,
+    Url,
+    Status,
+    /// Slowest first.
+    Time,
+}
+
+impl Sort {
+    fn next(self) -> Self {
+        match self {
+            Sort::Arrival => Sort::Url,
+            Sort::Url => Sort::Status,
+            Sort::Status => Sort::Time,
+            Sort::Time => Sort::Arrival,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Sort::Arrival => "arrival",
+            Sort::Url => "url",
+            Sort::Status => "status",
+            Sort::Time => "time",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum View {
+    /// Every outcome.
+    Results,
+    /// The pages links were found on.
+    Pages,
+    /// The outcomes of the links on one page.
+    Page(Url),
+}
+
+/// What the browser knows about the run so far.
+struct Browser {
+    results: Vec<CheckResult>,
+    /// Index into `results` by URL.
+    checked: HashMap<Url, usize>,
+    /// Pages each link was found on.
+    linked_from: HashMap<Url, Vec<Url>>,
+    /// Links found on each page.
+    links_on: BTreeMap<Url, Vec<Url>>,
+    filter: Filter,
+    sort: Sort,
+    view: View,
+    table: TableState,
+    /// The run has ended and no more events will come.
+    done: bool,
+}
+
+impl Browser {
+    fn new() -> Self {
+        Browser {
+            results: Vec::new(),
+            checked: HashMap::new(),
+            linked_from: HashMap::new(),
+            links_on: BTreeMap::new(),
+            filter: Filter::All,
+            sort: Sort::Arrival,
+            view: View::Results,
+            table: TableState::default().with_selected(0),
+            done: false,
+        }
+    }
+
+    fn apply(&mut self, event: Event) {
+        match event {
+            Event::Linked { page, link } => {
+                self.linked_from
+                    .entry(link.clone())
+                    .or_default()
+                    .push(page.clone());
+                self.links_on.entry(page).or_default().push(link);
+            }
+            Event::Checked(result) => {
+                self.checked.insert(result.url.clone(), self.results.len());
+                self.results.push(*result);
+            }
+            Event::Row { .. } | Event::Matched(_) | Event::Listed(_) => {}
+        }
+    }
+
+    /// The results the current view lists, filtered and sorted.
+    fn rows(&self) -> Vec<&CheckResult> {
+        let mut rows: Vec<&CheckResult> = match &self.view {
+            View::Page(page) => (self.links_on.get(page).into_iter().flatten())
+                .filter_map(|link| self.checked.get(link))
+                .map(|&at| &self.results[at])
+                .collect(),
+            _ => self.results.iter().collect(),
+        };
+        rows.retain(|result| self.filter.keeps(result));
+        match self.sort {
+            Sort::Arrival => {}
+            Sort::Url => rows.sort_by(|a, b| a.url.cmp(&b.url)),
+            Sort::Status => rows.sort_by_key(|result| (result.is_ok(), result.status)),
+            Sort::Time => rows.sort_by_key(|result| std::cmp::Reverse(result.elapsed_ms)),
+        }
+        rows
+    }
+
+    /// The source pages with their link and broken counts.
+    fn pages(&self) -> Vec<(&Url, usize, usize)> {
+        self.links_on
+            .iter()
+            .map(|(page, links)| {
+                let broken = (links.iter())
+                    .filter_map(|link| self.checked.get(link))
+                    .filter(|&&at| !self.results[at].is_ok())
+                    .count();
+                (page, links.len(), broken)
+            })
+            .filter(|(_, _, broken)| self.filter != Filter::Broken || *broken > 0)
+            .collect()
+    }
+
+    fn len(&self) -> usize {
+        match self.view {
+            View::Pages => self.pages().len(),
+            _ => self.rows().len(),
+        }
+    }
+
+    fn show(&mut self, view: View) {
+        self.view = view;
+        self.table.select(Some(0));
+    }
+
+    /// Handles a key press, returning whether to quit.
+    fn key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        match code {
+            KeyCode::Char('q') => return true,
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return true,
+            KeyCode::Char('f') => self.filter = self.filter.next(),
+            KeyCode::Char('s') => self.sort = self.sort.next(),
+            KeyCode::Char('p') => self.show(match self.view {
+                View::Pages => View::Results,
+                _ => View::Pages,
+            }),
+            KeyCode::Enter if self.view == View::Pages => {
+                let selected = self.table.selected().unwrap_or(0);
+                if let Some(&(page, _, _)) = self.pages().get(selected) {
+                    self.show(View::Page(page.clone()));
+                }
+            }
+            KeyCode::Esc => match self.view {
+                View::Page(_) => self.show(View::Pages),
+                View::Pages => self.show(View::Results),
+                View::Results => {}
+            },
+            KeyCode::Down | KeyCode::Char('j') => self.table.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => self.table.select_previous(),
+            KeyCode::PageDown => self.table.scroll_down_by(20),
+            KeyCode::PageUp => self.table.scroll_up_by(20),
+            KeyCode::Home | KeyCode::Char('g') => self.table.select_first(),
+            KeyCode::End | KeyCode::Char('G') => self.table.select_last(),
+            _ => {}
+        }
+        false
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [header, body, details, help] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(6),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let broken = self.results.iter().filter(|result| !result.is_ok()).count();
+        frame.render_widget(
+            Paragraph::new(format!(
+                "{} checked, {} broken, {} pages{} | filter: {} | sort: {}",
+                self.results.len(),
+                broken,
+                self.links_on.len(),
+                if self.done { "" } else { ", running…" },
+                self.filter.name(),
+                self.sort.name(),
+            ))
+            .style(Style::new().add_modifier(Modifier::BOLD)),
+            header,
+        );
+        frame.render_widget(Paragraph::new(HELP).style(Style::new().dim()), help);
+
+        let len = self.len();
+        if self
+            .table
+            .selected()
+            .is_some_and(|selected| selected >= len)
+        {
+            self.table.select(len.checked_sub(1));
+        }
+        let highlight = Style::new().add_modifier(Modifier::REVERSED);
+        if self.view == View::Pages {
+            let rows: Vec<Row> = (self.pages().into_iter())
+                .map(|(page, links, broken)| {
+                    let style = if broken > 0 {
+                        Style::new().fg(Color::Red)
+                    } else {
+                        Style::new()
+                    };
+                    Row::new([links.to_string(), broken.to_string(), page.to_string()]).style(style)
+                })
+                .collect();
+            let table = Table::new(
+                rows,
+                [
+                    Constraint::Length(6),
+                    Constraint::Length(6),
+                    Constraint::Fill(1),
+                ],
+            )
+            .header(Row::new(["links", "broken", "page"]).style(Style::new().underlined()))
+            .block(Block::bordered().title("Source pages"))
+            .row_highlight_style(highlight);
+            frame.render_stateful_widget(table, body, &mut self.table);
+            frame.render_widget(Block::bordered(), details);
+            return;
+        }
+
+        let results = self.rows();
+        let selected = (self.table.selected()).and_then(|selected| results.get(selected).copied());
+        let detail = selected
+            .map(|result| self.details(result))
+            .unwrap_or_default();
+        let rows: Vec<Row> = (results.iter())
+            .map(|result| {
+                let color = if !result.is_ok() {
+                    Color::Red
+                } else if result.redirected_to.is_some() {
+                    Color::Yellow
+                } else {
+                    Color::Green
+                };
+                let elapsed = match (result.cached, result.elapsed_ms) {
+                    (true, _) => "cached".to_string(),
+                    (false, Some(ms)) => format!("{} ms", ms),
+                    (false, None) => String::new(),
+                };
+                let sources = self.linked_from.get(&result.url).map_or(0, Vec::len);
+                Row::new([
+                    status(result),
+                    elapsed,
+                    sources.to_string(),
+                    result.url.to_string(),
+                ])
+                .style(Style::new().fg(color))
+            })
+            .collect();
+        let title = match &self.view {
+            View::Page(page) => format!("Links on {}", page),
+            _ => "Results".to_string(),
+        };
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(6),
+                Constraint::Length(9),
+                Constraint::Length(5),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new(["status", "time", "pages", "url"]).style(Style::new().underlined()))
+        .block(Block::bordered().title(title))
+        .row_highlight_style(highlight);
+        frame.render_stateful_widget(table, body, &mut self.table);
+        frame.render_widget(Paragraph::new(detail).block(Block::bordered()), details);
+    }
+
+    /// The lines describing `result` below the table.
+    fn details(&self, result: &CheckResult) -> Vec<Line<'static>> {
+        let mut lines = vec![Line::from(result.to_string())];
+        if let Some(to) = &result.redirected_to {
+            lines.push(Line::from(format!("Redirected to {}", to)));
+        }
+        let sources = self
+            .linked_from
+            .get(&result.url)
+            .map_or(&[][..], Vec::as_slice);
+        if !sources.is_empty() {
+            lines.push(Line::from(format!("Linked from ({}):", sources.len())));
+            lines.extend(sources.iter().map(|page| Line::from(format!("  {}", page))));
+        }
+        lines.extend(
+            result
+                .found_in
+                .iter()
+                .map(|at| Line::from(format!("  {}", at))),
+        );
+        lines
+    }
+}
+
+/// The status code, or "error" for URLs that could not be requested.
+fn status(result: &CheckResult) -> String {
+    match result.status {
+        Some(status) => status.as_u16().to_string(),
+        None => "error".to_string(),
+    }
+}
+
+/// Shows the outcomes of a run as they arrive on `events`, until the user
+/// quits. Quitting before the run ends cancels it with `cancel`. Blocks, so
+/// call it off the async runtime, e.g. with `spawn_blocking`.
+pub fn browse(events: Receiver<Event>, cancel: CancellationToken) -> Result<()> {
+    let mut terminal = ratatui::init();
+    let browsed = run(&mut terminal, events);
+    ratatui::restore();
+    if browsed? {
+        cancel.cancel();
+    }
+    Ok(())
+}
+
+/// The browser's event loop, returning whether the user quit early.
+fn run(terminal: &mut DefaultTerminal, mut events: Receiver<Event>) -> Result<bool> {
+    let mut browser = Browser::new();
+    loop {
+        while !browser.done {
+            match events.try_recv() {
+                Ok(event) => browser.apply(event),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => browser.done = true,
+            }
+        }
+        terminal.draw(|frame| browser.draw(frame))?;
+        if !event::poll(TICK)? {
+            continue;
+        }
+        if let Input::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && browser.key(key.code, key.modifiers) {
+                return Ok(!browser.done);
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use crate::error::{HtmlFindError, Result};
+use rusqlite::Connection;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// URLs a run has already seen, so each is fetched or checked once.
+pub trait VisitedSet: Send {
+    /// Marks `url` as seen, returning whether it was new.
+    fn insert(&mut self, url: &str) -> Result<bool>;
+
+    /// Every URL seen so far, for checkpoints.
+    fn urls(&self) -> Result<Vec<String>>;
+}
+
+impl VisitedSet for HashSet<String> {
+    fn insert(&mut self, url: &str) -> Result<bool> {
+        Ok(HashSet::insert(self, url.to_string()))
+    }
+
+    fn urls(&self) -> Result<Vec<String>> {
+        Ok(self.iter().cloned().collect())
+    }
+}
+
+/// A visited set kept in an SQLite file, so memory stays flat however many
+/// URLs a crawl goes through.
+pub struct SqliteVisited {
+    path: PathBuf,
+    conn: Connection,
+}
+
+impl SqliteVisited {
+    /// Opens the set at `path`, forgetting URLs left over from an earlier run.
+    pub fn open(path: &Path) -> Result<SqliteVisited> {
+        let visited = SqliteVisited {
+            path: path.to_path_buf(),
+            conn: Connection::open(path).map_err(|source| HtmlFindError::Db {
+                path: path.to_path_buf(),
+                source,
+            })?,
+        };
+        // Durability is the state file's job; losing this file only costs a restart.
+        visited
+            .conn
+            .execute_batch(
+                "PRAGMA journal_mode = OFF;
+                 PRAGMA synchronous = OFF;
+                 CREATE TABLE IF NOT EXISTS visited (url TEXT PRIMARY KEY) WITHOUT ROWID;
+                 DELETE FROM visited;",
+            )
+            .map_err(|source| visited.error(source))?;
+        Ok(visited)
+    }
+
+    fn error(&self, source: rusqlite::Error) -> HtmlFindError {
+        HtmlFindError::Db {
+            path: self.path.clone(),
+            source,
+        }
+    }
+}
+
+impl VisitedSet for SqliteVisited {
+    fn insert(&mut self, url: &str) -> Result<bool> {
+        self.conn
+            .prepare_cached("INSERT OR IGNORE INTO visited (url) VALUES (?1)")
+            .and_then(|mut stmt| stmt.execute([url]))
+            .map(|inserted| inserted > 0)
+            .map_err(|source| self.error(source))
+    }
+
+    fn urls(&self) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT url FROM visited")
+            .map_err(|source| self.error(source))?;
+        let urls = stmt
+            .query_map([], |row| row.get(0))
+            .and_then(|rows| rows.collect())
+            .map_err(|source| self.error(source));
+        urls
+    }
+}
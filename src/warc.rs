@@ -0,0 +1,210 @@
+use crate::error::{HtmlFindError, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use reqwest::header::HeaderMap;
+use reqwest::{StatusCode, Version};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use url::Url;
+
+/// Appends fetched responses to a WARC/1.1 file. Paths ending in `.gz` get
+/// one gzip member per record, as WARC readers expect.
+///
+/// Recording never fails the fetch it belongs to: the first write error is
+/// kept and returned by [`Warc::flush`].
+#[derive(Debug, Clone)]
+pub struct Warc {
+    path: PathBuf,
+    gzip: bool,
+    out: Arc<Mutex<Output>>,
+}
+
+#[derive(Debug)]
+struct Output {
+    file: BufWriter<File>,
+    error: Option<std::io::Error>,
+}
+
+impl Warc {
+    /// Creates the file at `path`, starting it with a `warcinfo` record.
+    pub fn create(path: &Path) -> Result<Warc> {
+        let file = File::create(path).map_err(|source| HtmlFindError::File {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let warc = Warc {
+            path: path.to_path_buf(),
+            gzip: path.extension().is_some_and(|ext| ext == "gz"),
+            out: Arc::new(Mutex::new(Output {
+                file: BufWriter::new(file),
+                error: None,
+            })),
+        };
+        let info = format!(
+            "software: {}/{}\r\nformat: WARC File Format 1.1\r\n",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        );
+        warc.write_record("warcinfo", None, "application/warc-fields", info.as_bytes());
+        warc.flush()?;
+        Ok(warc)
+    }
+
+    /// Writes a `response` record holding the status line, headers and `body`.
+    pub fn record_response(
+        &self,
+        url: &Url,
+        version: Version,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) {
+        let mut block = format!(
+            "{:?} {} {}\r\n",
+            version,
+            status.as_u16(),
+            status.canonical_reason().unwrap_or("")
+        )
+        .into_bytes();
+        for (name, value) in headers {
+            block.extend_from_slice(name.as_str().as_bytes());
+            block.extend_from_slice(b": ");
+            block.extend_from_slice(value.as_bytes());
+            block.extend_from_slice(b"\r\n");
+        }
+        block.extend_from_slice(b"\r\n");
+        block.extend_from_slice(body);
+        self.write_record(
+            "response",
+            Some(url),
+            "application/http; msgtype=response",
+            &block,
+        );
+    }
+
+    fn write_record(&self, kind: &str, target: Option<&Url>, content_type: &str, block: &[u8]) {
+        let mut record = format!(
+            "WARC/1.1\r\nWARC-Type: {}\r\nWARC-Record-ID: <urn:uuid:{}>\r\nWARC-Date: {}\r\n",
+            kind,
+            uuid::Uuid::new_v4(),
+            humantime::format_rfc3339_seconds(SystemTime::now())
+        );
+        if let Some(target) = target {
+            record.push_str(&format!("WARC-Target-URI: {}\r\n", target));
+        }
+        record.push_str(&format!(
+            "Content-Type: {}\r\nContent-Length: {}\r\n\r\n",
+            content_type,
+            block.len()
+        ));
+        let mut record = record.into_bytes();
+        record.extend_from_slice(block);
+        record.extend_from_slice(b"\r\n\r\n");
+
+        let mut out = self.out.lock().expect("WARC lock");
+        if out.error.is_some() {
+            return;
+        }
+        let written = if self.gzip {
+            let mut encoder = GzEncoder::new(&mut out.file, Compression::default());
+            encoder
+                .write_all(&record)
+                .and_then(|_| encoder.finish().map(|_| ()))
+        } else {
+            out.file.write_all(&record)
+        };
+        out.error = written.err();
+    }
+
+    /// Flushes buffered records, reporting the first error hit while recording.
+    pub fn flush(&self) -> Result<()> {
+        let mut out = self.out.lock().expect("WARC lock");
+        let flushed = match out.error.take() {
+            Some(err) => Err(err),
+            None => out.file.flush(),
+        };
+        flushed.map_err(|source| HtmlFindError::File {
+            path: self.path.clone(),
+            source,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::MultiGzDecoder;
+    use reqwest::header::{HeaderValue, CONTENT_TYPE};
+    use std::io::Read;
+
+    fn record(name: &str) -> (PathBuf, String) {
+        let path = std::env::temp_dir().join(format!("html_find_{}_{}", std::process::id(), name));
+        let warc = Warc::create(&path).unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/html"));
+        let url = Url::parse("https://example.com/").unwrap();
+        warc.record_response(
+            &url,
+            Version::HTTP_11,
+            StatusCode::OK,
+            &headers,
+            b"<p>hi</p>",
+        );
+        warc.flush().unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        if warc.gzip {
+            let mut decoded = vec![];
+            MultiGzDecoder::new(bytes.as_slice())
+                .read_to_end(&mut decoded)
+                .unwrap();
+            bytes = decoded;
+        }
+        (path, String::from_utf8(bytes).unwrap())
+    }
+
+    /// The headers and block of each record.
+    fn records(warc: &str) -> Vec<(&str, &str)> {
+        (warc.split("WARC/1.1\r\n").skip(1))
+            .map(|record| {
+                let (headers, block) = record.split_once("\r\n\r\n").unwrap();
+                (headers, block.strip_suffix("\r\n\r\n").unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn records_start_with_warcinfo() {
+        let (path, warc) = record("response.warc");
+        let records = records(&warc);
+        assert_eq!(records.len(), 2);
+        let (info, fields) = records[0];
+        assert!(warc.starts_with("WARC/1.1\r\n"));
+        assert!(info.starts_with("WARC-Type: warcinfo\r\n"));
+        assert!(fields.starts_with("software: html_find/"));
+        let (headers, block) = records[1];
+        assert!(headers.contains("WARC-Type: response\r\n"));
+        assert!(headers.contains("WARC-Target-URI: https://example.com/\r\n"));
+        assert!(headers.ends_with(&format!("Content-Length: {}", block.len())));
+        assert_eq!(
+            block,
+            "HTTP/1.1 200 OK\r\ncontent-type: text/html\r\n\r\n<p>hi</p>"
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn gzips_each_record_separately() {
+        let (path, warc) = record("response.warc.gz");
+        assert_eq!(records(&warc).len(), 2);
+        let bytes = std::fs::read(&path).unwrap();
+        let members = bytes
+            .windows(3)
+            .filter(|w| w == &[0x1f, 0x8b, 0x08])
+            .count();
+        assert_eq!(members, 2);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,43 @@
+use crate::error::{HtmlFindError, Phase, Result};
+use crate::http::Http;
+use serde::Deserialize;
+use url::Url;
+
+/// The Internet Archive's availability API.
+const AVAILABILITY_API: &str = "https://archive.org/wayback/available";
+
+#[derive(Deserialize)]
+struct Availability {
+    #[serde(default)]
+    archived_snapshots: Snapshots,
+}
+
+#[derive(Default, Deserialize)]
+struct Snapshots {
+    closest: Option<Snapshot>,
+}
+
+#[derive(Deserialize)]
+struct Snapshot {
+    available: bool,
+    url: Url,
+}
+
+/// The Wayback Machine snapshot of `url` closest to now, if it has one.
+pub async fn closest(http: &Http, url: &Url) -> Result<Option<Url>> {
+    let mut api = Url::parse(AVAILABILITY_API).expect("API URL is valid");
+    api.query_pairs_mut().append_pair("url", url.as_str());
+    let res = http
+        .send(http.get(&api))
+        .await
+        .and_then(|res| res.error_for_status())
+        .map_err(HtmlFindError::request(url, Phase::QueryArchive))?;
+    let availability: Availability = res
+        .json()
+        .await
+        .map_err(HtmlFindError::request(url, Phase::QueryArchive))?;
+    let snapshot = availability.archived_snapshots.closest;
+    Ok(snapshot
+        .filter(|snapshot| snapshot.available)
+        .map(|snapshot| snapshot.url))
+}
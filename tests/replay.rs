@@ -0,0 +1,137 @@
+//! Runs the checker against a small site recorded into `tests/fixtures/site`
+//! with `--record`, so no server is needed. The site, as served when it was
+//! recorded on `localhost:8790`:
+//!
+//! - `/` links to `/about.html`, `/missing.html` (404) and `/old`, a 301 to
+//!   `/about.html`, and shows `/logo.png` and `/broken.png` (404)
+//! - `/about.html` links to `/` and `/docs.html`
+//! - `/docs.html` links to `/missing.html` and to a part of itself
+
+use futures::StreamExt;
+use html_find::checker::DEFAULT_EVENTS_CAPACITY;
+use html_find::fixtures::Fixtures;
+use html_find::{CheckResult, Checker, Options, Report};
+use reqwest::StatusCode;
+use std::path::PathBuf;
+use std::time::Duration;
+use url::Url;
+
+fn url(path: &str) -> Url {
+    Url::parse("http://localhost:8790/")
+        .unwrap()
+        .join(path)
+        .unwrap()
+}
+
+fn replaying(options: Options) -> Options {
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/site");
+    Options {
+        fixtures: Some(Fixtures::Replay(fixtures)),
+        print_progress: false,
+        ..options
+    }
+}
+
+async fn replay(options: Options) -> Report {
+    Checker::new(replaying(options))
+        .run(&[url("/")])
+        .await
+        .unwrap()
+}
+
+fn status(report: &Report, path: &str) -> Option<StatusCode> {
+    (report.results.iter())
+        .find(|result| result.url == url(path))
+        .and_then(|result| result.status)
+}
+
+#[tokio::test]
+async fn checks_links_and_images_of_the_start_page() {
+    let report = replay(Options {
+        links: true,
+        images: true,
+        ..Options::default()
+    })
+    .await;
+    assert_eq!(report.pages, [url("/")]);
+    assert_eq!(status(&report, "/about.html"), Some(StatusCode::OK));
+    assert_eq!(status(&report, "/logo.png"), Some(StatusCode::OK));
+    let old = (report.results.iter()).find(|result| result.url == url("/old"));
+    assert_eq!(
+        old.and_then(|result| result.redirected_to.clone()),
+        Some(url("/about.html"))
+    );
+
+    let mut broken: Vec<&str> = report.broken().map(|result| result.url.path()).collect();
+    broken.sort();
+    assert_eq!(broken, ["/broken.png", "/missing.html"]);
+    assert!(report
+        .broken()
+        .all(|result| result.linked_from == [url("/")]));
+}
+
+#[tokio::test]
+async fn follows_internal_links_through_the_site() {
+    let report = replay(Options {
+        links: true,
+        follow: true,
+        dead_ends: true,
+        deep_after: Some(2),
+        ..Options::default()
+    })
+    .await;
+    let mut pages: Vec<&str> = report.pages.iter().map(Url::path).collect();
+    pages.sort();
+    assert_eq!(pages, ["/", "/about.html", "/docs.html"]);
+    assert_eq!(report.dead_ends, [url("/docs.html")]);
+    assert_eq!(report.deep_pages.len(), 1);
+    assert_eq!(report.deep_pages[0].url, url("/docs.html"));
+    assert_eq!(report.deep_pages[0].depth, 2);
+    let broken: Vec<&CheckResult> = report.broken().collect();
+    assert_eq!(broken.len(), 1);
+    assert_eq!(broken[0].url, url("/missing.html"));
+    assert_eq!(broken[0].linked_from, [url("/"), url("/docs.html")]);
+}
+
+#[tokio::test]
+async fn streams_each_outcome_the_report_has() {
+    let options = replaying(Options {
+        links: true,
+        images: true,
+        ..Options::default()
+    });
+    let mut stream = Checker::new(options).stream(vec![url("/")], DEFAULT_EVENTS_CAPACITY);
+    let mut streamed = Vec::new();
+    while let Some(result) = stream.next().await {
+        streamed.push(result.url);
+    }
+    let report = stream.report().await.unwrap();
+    let mut reported: Vec<Url> = (report.results.iter())
+        .map(|result| result.url.clone())
+        .collect();
+    streamed.sort();
+    reported.sort();
+    assert!(!streamed.is_empty());
+    assert_eq!(streamed, reported);
+}
+
+#[tokio::test]
+async fn a_stream_not_polled_holds_the_run_back() {
+    let options = replaying(Options {
+        links: true,
+        images: true,
+        ..Options::default()
+    });
+    let mut stream = Checker::new(options).stream(vec![url("/")], 1);
+    assert!(stream.next().await.is_some());
+    // Replayed, the whole run takes a few milliseconds when nothing holds it back.
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(!stream.is_finished());
+
+    let mut streamed = 1;
+    while stream.next().await.is_some() {
+        streamed += 1;
+    }
+    let report = stream.report().await.unwrap();
+    assert_eq!(streamed, report.results.len());
+}